@@ -0,0 +1,226 @@
+//! breakables: props that shatter into debris when something hits them hard enough, from a body
+//! crashing into them to a player landing on them from a height
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::TnuaProximitySensor;
+
+use crate::{Player, PlayerLanded, SceneObject, TimeScale, PHYSICS_HZ};
+
+/// the collision group debris is in, alone. players collide with every group but this one, so
+/// debris can't get under their ground sensor and have them standing on it, or trip them up
+pub const DEBRIS_GROUP: Group = Group::GROUP_32;
+
+/// seconds debris lies around for, shrinking away over the last DEBRIS_FADE of them
+const DEBRIS_LIFETIME: f32 = 4.0;
+const DEBRIS_FADE: f32 = 1.0;
+
+/// speed debris flies apart at, on top of the speed the prop was going
+const DEBRIS_SPEED: f32 = 2.0;
+
+/// breaks a prop once a single hit's impulse reaches `threshold`, in newton seconds: a body
+/// hitting it, it hitting something, or a player landing on it, worth their mass times the speed
+/// they landed at. the prop is despawned and debris spawned in its place, a `pieces` by `pieces`
+/// by `pieces` grid of little dynamic cuboids filling its collider's bounds
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Breakable {
+    pub threshold: f32,
+    pub pieces: u32,
+}
+
+impl Breakable {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            pieces: 2,
+        }
+    }
+
+    pub fn with_pieces(mut self, pieces: u32) -> Self {
+        self.pieces = pieces;
+        self
+    }
+}
+
+/// event sent when a Breakable breaks, e.g. to play a sound
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct PropDestroyed {
+    /// the prop, despawned by now
+    pub entity: Entity,
+    pub position: Vec3,
+    /// the hit that broke it
+    pub impulse: f32,
+}
+
+/// a piece of a broken prop
+#[derive(Component)]
+pub(crate) struct Debris {
+    /// seconds it's been lying around
+    age: f32,
+    /// the scale it shrinks away from
+    scale: Vec3,
+}
+
+/// breakable props and their debris
+#[derive(Clone, Default)]
+pub struct BreakablePlugin;
+
+impl Plugin for BreakablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PropDestroyed>()
+            .add_systems(
+                FixedUpdate,
+                (arm_breakables, fade_debris).before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(FixedUpdate, break_props.after(PhysicsSet::Writeback));
+    }
+}
+
+/// collision groups for players: everything but debris
+pub(crate) fn player_groups() -> CollisionGroups {
+    CollisionGroups::new(Group::ALL, Group::ALL - DEBRIS_GROUP)
+}
+
+/// have rapier report the contact forces on new breakables strong enough to break them
+pub(crate) fn arm_breakables(
+    mut commands: Commands,
+    mut breakables: Query<(Entity, &Breakable, Option<&mut ActiveEvents>), Added<Breakable>>,
+) {
+    for (entity, breakable, events) in &mut breakables {
+        match events {
+            Some(mut events) => *events |= ActiveEvents::CONTACT_FORCE_EVENTS,
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(ActiveEvents::CONTACT_FORCE_EVENTS);
+            }
+        }
+        // rapier reports the force over a step, so the threshold's impulse over the longest
+        // tick TimeScale allows; break_props checks the impulse over the tick it happened in
+        commands.entity(entity).insert(ContactForceEventThreshold(
+            breakable.threshold * PHYSICS_HZ as f32 / TimeScale::MAX,
+        ));
+    }
+}
+
+/// break the breakables hit hard enough this tick, swapping them for debris
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn break_props(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut er_contact_force: EventReader<ContactForceEvent>,
+    mut er_landed: EventReader<PlayerLanded>,
+    mut ew_destroyed: EventWriter<PropDestroyed>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    rapier_context: Res<RapierContext>,
+    breakables: Query<(
+        &Breakable,
+        &Collider,
+        &GlobalTransform,
+        Option<&Velocity>,
+        Option<&Handle<StandardMaterial>>,
+        Has<SceneObject>,
+    )>,
+    players: Query<&TnuaProximitySensor, With<Player>>,
+) {
+    let mut hits: Vec<(Entity, f32)> = Vec::new();
+    for ev in er_contact_force.read() {
+        let impulse = ev.total_force_magnitude * time.delta_seconds();
+        hits.push((ev.collider1, impulse));
+        hits.push((ev.collider2, impulse));
+    }
+    for ev in er_landed.read() {
+        let Some(ground) = players
+            .get(ev.player)
+            .ok()
+            .and_then(|sensor| sensor.output.as_ref())
+        else {
+            continue;
+        };
+        let mass = rapier_context
+            .entity2body()
+            .get(&ev.player)
+            .and_then(|&handle| rapier_context.bodies.get(handle))
+            .map_or(0.0, |body| body.mass());
+        hits.push((ground.entity, mass * ev.impact_speed));
+    }
+
+    let mut broken = Vec::new();
+    for (collider, impulse) in hits {
+        // hits are on colliders; break the prop they belong to
+        let entity = rapier_context.collider_parent(collider).unwrap_or(collider);
+        let Ok((breakable, collider, transform, velocity, material, in_scene)) =
+            breakables.get(entity)
+        else {
+            continue;
+        };
+        if impulse < breakable.threshold || broken.contains(&entity) {
+            continue;
+        }
+        broken.push(entity);
+
+        let aabb = collider.raw.compute_local_aabb();
+        let mins = Vec3::from(aabb.mins.coords);
+        let pieces = breakable.pieces.max(1);
+        let size = (Vec3::from(aabb.maxs.coords) - mins) / pieces as f32;
+        let mesh = meshes.add(Cuboid::from_size(size));
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let velocity = velocity.copied().unwrap_or_default();
+        for x in 0..pieces {
+            for y in 0..pieces {
+                for z in 0..pieces {
+                    let local = mins + size * (Vec3::new(x as f32, y as f32, z as f32) + 0.5);
+                    let position = transform.transform_point(local);
+                    let out = (rotation * local).normalize_or_zero();
+                    let mut piece = commands.spawn(PbrBundle {
+                        mesh: mesh.clone(),
+                        transform: Transform::from_translation(position)
+                            .with_rotation(rotation)
+                            .with_scale(scale),
+                        ..default()
+                    });
+                    piece
+                        .insert(RigidBody::Dynamic)
+                        .insert(Collider::cuboid(size.x * 0.5, size.y * 0.5, size.z * 0.5))
+                        .insert(CollisionGroups::new(DEBRIS_GROUP, Group::ALL))
+                        .insert(Velocity {
+                            linvel: velocity.linvel + out * DEBRIS_SPEED,
+                            angvel: velocity.angvel,
+                        })
+                        .insert(Debris { age: 0.0, scale });
+                    if let Some(material) = material {
+                        piece.insert(material.clone());
+                    }
+                    // cleared away with the scene it broke off
+                    if in_scene {
+                        piece.insert(SceneObject);
+                    }
+                }
+            }
+        }
+
+        commands.entity(entity).despawn_recursive();
+        ew_destroyed.send(PropDestroyed {
+            entity,
+            position: translation,
+            impulse,
+        });
+    }
+}
+
+/// shrink debris away at the end of its lifetime, then despawn it
+pub(crate) fn fade_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris: Query<(Entity, &mut Debris, &mut Transform)>,
+) {
+    for (entity, mut debris, mut transform) in &mut debris {
+        debris.age += time.delta_seconds();
+        let left = DEBRIS_LIFETIME - debris.age;
+        if left <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        } else if left < DEBRIS_FADE {
+            transform.scale = debris.scale * left / DEBRIS_FADE;
+        }
+    }
+}
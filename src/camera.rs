@@ -0,0 +1,1724 @@
+//! the player cameras: the rig, mouse look, zoom, lean, shake, control modes, split-screen
+//! viewports, viewmodels and mouse locking
+
+use std::f32::consts::PI;
+
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    input::mouse::MouseMotion,
+    pbr::NotShadowCaster,
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+    render::view::RenderLayers,
+    time::Real,
+    utils::HashSet,
+    window::{CursorGrabMode, PrimaryWindow, WindowFocused, WindowResized},
+};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_arch = "wasm32")]
+use crate::menu::MenuState;
+use crate::{
+    camera_path::CameraPathPlayback,
+    input::{Action, KeyBindings, Keys, MouseSmoothing},
+    photo_mode::PhotoMode,
+    speed_fov, GamepadInput, InputSnapshot, KeyboardMouseInput, Player, SpeedFovConfig, WorldUp,
+};
+
+/// resource to control mouse locking
+#[derive(Resource)]
+pub struct MouseLocked(pub bool);
+
+/// when the cursor is grabbed again after the window regains focus
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RegrabMode {
+    /// grab as soon as the window is focused
+    OnFocus,
+    /// wait for a click inside the window
+    OnClick,
+}
+
+/// cursor locking configuration
+#[derive(Resource, Clone, Debug)]
+pub struct CursorSettings {
+    /// whether the mouse starts locked
+    pub initially_locked: bool,
+    pub regrab: RegrabMode,
+    /// keep the cursor visible and confined to the window while locked, instead of hidden
+    pub visible_when_locked: bool,
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            initially_locked: true,
+            regrab: RegrabMode::OnFocus,
+            visible_when_locked: false,
+        }
+    }
+}
+
+/// set while the cursor is released because the window lost focus
+#[derive(Resource, Default)]
+pub(crate) struct CursorSuspended(pub(crate) bool);
+
+/// set while the game wants the mouse locked but the browser hasn't granted pointer lock, which
+/// it only does from a click
+#[cfg(target_arch = "wasm32")]
+#[derive(Resource, Default)]
+pub(crate) struct PointerLockPending(pub(crate) bool);
+
+/// what the browser has said about pointer lock since sync_pointer_lock last looked, pushed by
+/// the document's listeners. winit doesn't report it
+#[cfg(target_arch = "wasm32")]
+#[derive(Resource, Clone, Default)]
+pub(crate) struct PointerLockEvents(std::sync::Arc<std::sync::Mutex<Vec<PointerLockEvent>>>);
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PointerLockEvent {
+    /// pointer lock was granted or released (Esc, switching tabs, or the game letting go)
+    Changed,
+    /// a request for pointer lock was turned down, e.g. one too soon after Esc released it
+    Refused,
+}
+
+/// camera placement relative to the player, shared by every player camera. starts as
+/// CameraPreset::FirstPerson; insert your own before the plugins, or change it at runtime
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct CameraRig {
+    /// height of the orbit pivot above the player's center
+    pub height: f32,
+    /// distance the camera sits behind the pivot; zero for first person
+    pub distance: f32,
+    /// sideways offset so the player model doesn't block the center of the view
+    pub shoulder_offset: f32,
+    /// pitch added on top of mouse look, in radians
+    pub pitch_bias: f32,
+    /// yaw of the camera around the player, in radians
+    pub yaw_bias: f32,
+    /// how far mouse look may pitch up, in degrees
+    pub pitch_up_limit: f32,
+    /// how far mouse look may pitch down, in degrees
+    pub pitch_down_limit: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        CameraPreset::default().rig()
+    }
+}
+
+impl CameraRig {
+    pub fn is_first_person(&self) -> bool {
+        self.distance <= 0.0
+    }
+
+    /// clamp a mouse look pitch (radians) to the rig's limits and the configured look range
+    fn clamp_pitch(&self, pitch: f32, look: &LookConfig) -> f32 {
+        let (mut min, mut max) = look.pitch_range();
+        if look.prevent_flip {
+            // keep the final pitch, bias included, short of straight up or down
+            min = min.max(-FLIP_LIMIT - self.pitch_bias);
+            max = max.min(FLIP_LIMIT - self.pitch_bias);
+        }
+        let min = min.max(-self.pitch_down_limit.to_radians());
+        let max = max.min(self.pitch_up_limit.to_radians());
+
+        // ranges that don't overlap settle on the configured minimum
+        pitch.clamp(min, max.max(min))
+    }
+
+    /// camera transform local to the player for a given mouse look pitch
+    fn camera_transform(&self, pitch: f32) -> Transform {
+        let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw_bias, pitch + self.pitch_bias, 0.0);
+        let offset = rotation * Vec3::new(self.shoulder_offset, 0.0, self.distance);
+        Transform {
+            translation: Vec3::new(0.0, self.height, 0.0) + offset,
+            rotation,
+            ..default()
+        }
+    }
+}
+
+/// built-in camera rigs, cycled with Action::CycleCameraRig
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum CameraPreset {
+    #[default]
+    FirstPerson,
+    ThirdPerson,
+    OverTheShoulder,
+    TopDown,
+}
+
+impl CameraPreset {
+    pub fn rig(self) -> CameraRig {
+        let first_person = CameraRig {
+            height: 0.5,
+            distance: 0.0,
+            shoulder_offset: 0.0,
+            pitch_bias: 0.0,
+            yaw_bias: 0.0,
+            pitch_up_limit: 90.0,
+            pitch_down_limit: 90.0,
+        };
+
+        match self {
+            Self::FirstPerson => first_person,
+            Self::ThirdPerson => CameraRig {
+                height: 1.0,
+                distance: 6.0,
+                pitch_up_limit: 70.0,
+                pitch_down_limit: 70.0,
+                ..first_person
+            },
+            Self::OverTheShoulder => CameraRig {
+                height: 0.8,
+                distance: 3.0,
+                shoulder_offset: 0.8,
+                pitch_up_limit: 70.0,
+                pitch_down_limit: 70.0,
+                ..first_person
+            },
+            // keep the mouse pitch range small so the view never flips past vertical
+            Self::TopDown => CameraRig {
+                height: 0.0,
+                distance: 16.0,
+                pitch_bias: -1.2,
+                pitch_up_limit: 15.0,
+                pitch_down_limit: 15.0,
+                ..first_person
+            },
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::FirstPerson => Self::ThirdPerson,
+            Self::ThirdPerson => Self::OverTheShoulder,
+            Self::OverTheShoulder => Self::TopDown,
+            Self::TopDown => Self::FirstPerson,
+        }
+    }
+}
+
+/// how close to straight up or down the view may pitch while flip prevention is on, in radians
+const FLIP_LIMIT: f32 = 89.0 * PI / 180.0;
+
+/// mouse look configuration
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct LookConfig {
+    /// radians of rotation per horizontal mouse count
+    pub(crate) sensitivity: f32,
+    /// vertical sensitivity as a fraction of `sensitivity`; below 1 looks up and down slower
+    /// than it turns
+    pub(crate) vertical_ratio: f32,
+    pub(crate) invert_y: bool,
+    /// vertical field of view, in degrees
+    pub(crate) fov: f32,
+    /// use mouse deltas as they arrive, skipping `average_frames` and `smoothing`
+    pub(crate) raw_input: bool,
+    /// frames of mouse deltas to average over when not raw; 1 disables averaging
+    average_frames: usize,
+    /// time constant of the mouse smoothing filter when not raw, in seconds; 0 disables it
+    smoothing: f32,
+    /// in first person, look yaw turns only the camera; the body snaps to it when moving
+    pub(crate) free_look: bool,
+    /// lowest look pitch, in degrees; the camera rig may limit it further
+    min_pitch: f32,
+    /// highest look pitch, in degrees; the camera rig may limit it further
+    max_pitch: f32,
+    /// stop the view just short of straight up or down so it can't tip over the top
+    prevent_flip: bool,
+}
+
+impl Default for LookConfig {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.005,
+            vertical_ratio: 1.0,
+            invert_y: false,
+            fov: 90.0,
+            raw_input: true,
+            average_frames: 4,
+            smoothing: 0.0,
+            free_look: false,
+            min_pitch: -90.0,
+            max_pitch: 90.0,
+            prevent_flip: true,
+        }
+    }
+}
+
+impl LookConfig {
+    /// the configured pitch range in radians
+    fn pitch_range(&self) -> (f32, f32) {
+        debug_assert!(
+            self.min_pitch < self.max_pitch,
+            "min_pitch must be below max_pitch"
+        );
+        (self.min_pitch.to_radians(), self.max_pitch.to_radians())
+    }
+
+    /// reset an empty or inverted pitch range, which would otherwise pin the view
+    pub(crate) fn validate_pitch_range(&mut self) {
+        if self.min_pitch >= self.max_pitch {
+            warn!(
+                "look min_pitch ({}) must be below max_pitch ({}), using the defaults",
+                self.min_pitch, self.max_pitch
+            );
+            let defaults = Self::default();
+            self.min_pitch = defaults.min_pitch;
+            self.max_pitch = defaults.max_pitch;
+        }
+    }
+
+    /// whether first-person free look is active, keeping look yaw on the camera
+    pub(crate) fn free_looks(&self, rig: &CameraRig, mode: ControlMode) -> bool {
+        self.free_look && rig.is_first_person() && mode == ControlMode::MouseLook
+    }
+
+    /// a mouse delta in counts turned into radians of yaw and pitch
+    pub(crate) fn mouse_delta(&self, counts: Vec2) -> Vec2 {
+        counts * Vec2::new(self.sensitivity, self.sensitivity * self.vertical_ratio)
+    }
+
+    /// filter a frame's mouse delta unless raw input is on: average it over the last
+    /// `average_frames` frames, then exponentially smooth it. both release every count of input
+    /// eventually, so the view doesn't drift or lose motion.
+    pub(crate) fn smooth(&self, delta: Vec2, state: &mut MouseSmoothing, dt: f32) -> Vec2 {
+        if self.raw_input {
+            state.reset();
+            return delta;
+        }
+
+        // each frame's delta is spread evenly over the next `frames` frames
+        let frames = self.average_frames.max(1);
+        state.history.push_back(delta / frames as f32);
+        while state.history.len() > frames {
+            state.history.pop_front();
+        }
+        let delta = state.history.iter().sum::<Vec2>();
+
+        if self.smoothing <= 0.0 {
+            state.pending = Vec2::ZERO;
+            return delta;
+        }
+        state.pending += delta;
+        // release the remainder once it's below a count, so the filter settles exactly
+        let out = if state.pending.length_squared() < 1.0 {
+            state.pending
+        } else {
+            state.pending * (1.0 - (-dt / self.smoothing).exp())
+        };
+        state.pending -= out;
+        out
+    }
+}
+
+#[cfg(test)]
+mod pitch_tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    const PRESETS: [CameraPreset; 4] = [
+        CameraPreset::FirstPerson,
+        CameraPreset::ThirdPerson,
+        CameraPreset::OverTheShoulder,
+        CameraPreset::TopDown,
+    ];
+
+    /// mouse look deltas from a twitch to far past a full turn, both ways
+    fn deltas() -> impl Iterator<Item = f32> {
+        [1e-4, 0.01, 0.5, 1.0, PI, 10.0, 1e3, 1e6]
+            .into_iter()
+            .flat_map(|delta| [delta, -delta])
+    }
+
+    /// pitch after looking by each delta in turn, checking every step stays within `range`
+    fn assert_holds(rig: &CameraRig, look: &LookConfig, (min, max): (f32, f32)) {
+        let mut pitch = 0.0;
+        for delta in deltas() {
+            for _ in 0..3 {
+                pitch = rig.clamp_pitch(pitch + delta, look);
+                assert!(
+                    pitch >= min - 1e-5 && pitch <= max + 1e-5,
+                    "pitch {pitch} outside {min}..{max} after {delta}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn holds_the_rig_and_look_limits_for_any_delta() {
+        let look = LookConfig {
+            prevent_flip: false,
+            min_pitch: -60.0,
+            max_pitch: 80.0,
+            ..default()
+        };
+        for preset in PRESETS {
+            let rig = preset.rig();
+            let min = (-60f32)
+                .to_radians()
+                .max(-rig.pitch_down_limit.to_radians());
+            let max = 80f32.to_radians().min(rig.pitch_up_limit.to_radians());
+            assert_holds(&rig, &look, (min, max));
+
+            // and reaches them
+            assert_eq!(rig.clamp_pitch(1e6, &look), max);
+            assert_eq!(rig.clamp_pitch(-1e6, &look), min);
+        }
+    }
+
+    #[test]
+    fn prevent_flip_keeps_the_biased_view_short_of_vertical() {
+        let look = LookConfig::default();
+        for preset in PRESETS {
+            let rig = preset.rig();
+            assert_holds(
+                &rig,
+                &look,
+                (-FLIP_LIMIT - rig.pitch_bias, FLIP_LIMIT - rig.pitch_bias),
+            );
+        }
+
+        // a bias that would tip the view over the top is held back too
+        let rig = CameraRig {
+            pitch_bias: -1.2,
+            ..CameraPreset::FirstPerson.rig()
+        };
+        let final_pitch = rig.clamp_pitch(-1e6, &look) + rig.pitch_bias;
+        assert!((final_pitch + FLIP_LIMIT).abs() < 1e-5, "{final_pitch}");
+
+        // without it, the first person view goes all the way
+        let free = LookConfig {
+            prevent_flip: false,
+            ..look
+        };
+        let up = CameraPreset::FirstPerson.rig().clamp_pitch(1e6, &free);
+        assert!((up - FRAC_PI_2).abs() < 1e-5, "{up}");
+    }
+
+    #[test]
+    fn inverted_ranges_are_reset_instead_of_pinning_the_view() {
+        let mut look = LookConfig {
+            min_pitch: 30.0,
+            max_pitch: -30.0,
+            ..default()
+        };
+        look.validate_pitch_range();
+        let defaults = LookConfig::default();
+        assert_eq!(
+            (look.min_pitch, look.max_pitch),
+            (defaults.min_pitch, defaults.max_pitch)
+        );
+        let rig = CameraPreset::FirstPerson.rig();
+        assert_holds(&rig, &look, (-FLIP_LIMIT, FLIP_LIMIT));
+        assert!(rig.clamp_pitch(1.0, &look) > rig.clamp_pitch(-1.0, &look));
+    }
+
+    #[test]
+    fn ranges_missing_the_rig_settle_on_the_minimum() {
+        // a look range entirely above what the top down rig allows
+        let look = LookConfig {
+            min_pitch: 30.0,
+            max_pitch: 60.0,
+            ..default()
+        };
+        let rig = CameraPreset::TopDown.rig();
+        for delta in deltas() {
+            assert_eq!(rig.clamp_pitch(delta, &look), 30f32.to_radians());
+        }
+    }
+}
+
+/// aim-down-sights zoom on the right mouse button
+#[derive(Resource)]
+pub(crate) struct ZoomConfig {
+    /// vertical field of view when fully zoomed, in degrees
+    fov: f32,
+    /// how quickly the zoom eases in and out, per second
+    speed: f32,
+    /// press to toggle zoom instead of holding
+    toggle: bool,
+}
+
+impl Default for ZoomConfig {
+    fn default() -> Self {
+        Self {
+            fov: 40.0,
+            speed: 12.0,
+            toggle: false,
+        }
+    }
+}
+
+/// current zoom state
+#[derive(Resource, Default)]
+pub(crate) struct Zoom {
+    active: bool,
+    /// eased zoom amount, 0 (none) to 1 (fully zoomed)
+    amount: f32,
+}
+
+impl Zoom {
+    /// resolve the camera fov in degrees from the base fov, LookConfig's or the speed fov; any
+    /// fov kick layered on top of it must be scaled by `1.0 - amount` so zoom wins; zoom is
+    /// applied last
+    fn fov(&self, base: f32, config: &ZoomConfig) -> f32 {
+        base.lerp(config.fov, self.amount)
+    }
+
+    /// sensitivity multiplier that keeps on-screen angular speed consistent while zoomed
+    pub(crate) fn sensitivity_scale(&self, look: &LookConfig, config: &ZoomConfig) -> f32 {
+        let half_fov = |fov: f32| (fov.to_radians() * 0.5).tan();
+        half_fov(self.fov(look.fov, config)) / half_fov(look.fov)
+    }
+}
+
+/// event to point the keyboard and mouse player's view at a world-space target once, easing
+/// there and then leaving the view to the player. any mouse motion cancels the ease
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LookAt {
+    pub target: Vec3,
+    /// seconds to ease over; zero snaps immediately
+    pub duration: f32,
+}
+
+/// an eased look-at in progress; any mouse motion cancels it
+#[derive(Resource)]
+pub(crate) struct LookAtTween {
+    from: Vec2,
+    to: Vec2,
+    elapsed: f32,
+    duration: f32,
+}
+
+/// something for CameraLookTarget to look at
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LookTarget {
+    /// followed as it moves; despawning it clears the target
+    Entity(Entity),
+    Point(Vec3),
+}
+
+/// keep turning the keyboard and mouse player's view towards a target while it's set, for
+/// cutscenes or aim assist. unlike LookAt it keeps tracking, and hands the view back once
+/// cleared. the pitch stays within the rig's and LookConfig's limits
+#[derive(Resource, Clone, Copy, PartialEq, Default, Debug)]
+pub struct CameraLookTarget(pub Option<LookTarget>);
+
+/// how CameraLookTarget turns the view
+#[derive(Resource, Clone, Debug)]
+pub struct LookAssistConfig {
+    /// how hard the view is pulled onto the target, as the inverse of a time constant in
+    /// seconds; around 3 is a gentle aim assist and 10+ a hard lock
+    pub rate: f32,
+    /// seconds to blend the pull in once a target is set and out once it's cleared
+    pub blend: f32,
+    /// let look input keep nudging the view while a target is set; off to take the view over
+    /// entirely, e.g. for a cutscene
+    pub allow_input: bool,
+}
+
+impl Default for LookAssistConfig {
+    fn default() -> Self {
+        Self {
+            rate: 8.0,
+            blend: 0.3,
+            allow_input: true,
+        }
+    }
+}
+
+/// how far CameraLookTarget's pull is blended in, and the last point it looked at, to blend
+/// out from
+#[derive(Default)]
+pub(crate) struct LookAssist {
+    weight: f32,
+    towards: Option<Vec3>,
+}
+
+/// render layer for first-person viewmodels, drawn by their own camera on top of the world
+const VIEWMODEL_LAYER: usize = 1;
+
+/// vertical field of view of the viewmodel camera, independent of the world fov
+const VIEWMODEL_FOV: f32 = 50.0;
+
+/// most local players supported by split screen; each gets its own viewmodel layer
+pub(crate) const MAX_LOCAL_PLAYERS: usize = 4;
+
+/// first render layer for players' bodies in first person, one per local player after the
+/// viewmodel layers. only the other players' cameras see it, but lights do too, for shadows
+const BODY_LAYER: usize = VIEWMODEL_LAYER + MAX_LOCAL_PLAYERS;
+
+/// render layers seen by the world cameras and all viewmodel cameras, and the first-person
+/// bodies so they still cast shadows, for lights
+pub(crate) fn world_and_viewmodel_layers() -> RenderLayers {
+    (0..MAX_LOCAL_PLAYERS).fold(RenderLayers::layer(0), |layers, index| {
+        layers
+            .with(VIEWMODEL_LAYER + index)
+            .with(BODY_LAYER + index)
+    })
+}
+
+/// render layers the world camera of the local player in `slot` sees: the world and every
+/// other player's first-person body
+pub(crate) fn world_camera_layers(slot: usize) -> RenderLayers {
+    (0..MAX_LOCAL_PLAYERS)
+        .filter(|&index| index != slot)
+        .fold(RenderLayers::layer(0), |layers, index| {
+            layers.with(BODY_LAYER + index)
+        })
+}
+
+/// what a player sees of their own capsule in first person. other players' cameras always see
+/// it, and in third person so does theirs
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum FirstPersonBody {
+    Visible,
+    /// out of sight, but still casting a shadow
+    #[default]
+    ShadowOnly,
+    /// out of sight and casting no shadow
+    Hidden,
+}
+
+/// camera drawing the viewmodel layer, a child of the player camera
+#[derive(Component)]
+pub(crate) struct ViewModelCamera;
+
+/// a first-person viewmodel; it and its descendants are moved onto the viewmodel layer
+#[derive(Component)]
+pub(crate) struct ViewModel {
+    /// local transform when at rest
+    rest: Transform,
+    /// current sway offset, lagging behind look input
+    sway: Vec2,
+    /// render layer of the owning player's viewmodel camera
+    layer: usize,
+}
+
+impl ViewModel {
+    fn new(rest: Transform, layer: usize) -> Self {
+        Self {
+            rest,
+            sway: Vec2::ZERO,
+            layer,
+        }
+    }
+}
+
+/// how players are controlled and viewed
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ControlMode {
+    /// mouse (or right stick) look through the camera rig, moving relative to facing
+    MouseLook,
+    /// fixed orthographic camera above each player, moving in world axes and facing the
+    /// movement direction
+    TopDown,
+}
+
+/// height of the top-down camera above the player
+const TOP_DOWN_HEIGHT: f32 = 20.0;
+
+/// world units visible vertically in the top-down camera
+const TOP_DOWN_VIEW_HEIGHT: f32 = 20.0;
+
+/// number of local players, each with their own split-screen viewport, from
+/// PlayerControllerPlugin
+#[derive(Resource)]
+pub(crate) struct SplitScreen {
+    pub(crate) players: usize,
+}
+
+/// a local player's split-screen slot, on its cameras
+#[derive(Component)]
+pub(crate) struct SplitScreenSlot(pub(crate) usize);
+
+/// level geometry that fades out while it blocks a third-person camera's view of its player
+#[derive(Component)]
+pub(crate) struct FadeWhenOccluding;
+
+/// how see-through occluding geometry becomes
+#[derive(Resource)]
+pub(crate) struct OcclusionFadeConfig {
+    /// material alpha while occluding, from 0 (invisible) to 1 (opaque)
+    alpha: f32,
+}
+
+impl Default for OcclusionFadeConfig {
+    fn default() -> Self {
+        Self { alpha: 0.25 }
+    }
+}
+
+/// the material to restore once an entity stops occluding
+#[derive(Component)]
+pub(crate) struct Faded {
+    original: Handle<StandardMaterial>,
+}
+
+/// a player's camera, holding its look pitch in radians
+#[derive(Component)]
+pub struct PlayerCamera(pub f32);
+
+/// yaw of a player camera around its player, relative to the body. only nonzero while
+/// CameraRecenter's orbiting third-person camera or first-person free look is active
+#[derive(Component, Default)]
+pub(crate) struct CameraYaw(pub(crate) f32);
+
+/// current first-person lean of a player camera, eased towards the held lean direction
+#[derive(Component, Default)]
+pub(crate) struct Lean {
+    /// roll, in radians; positive leans right
+    angle: f32,
+    /// sideways shift from the rig position; positive is right
+    offset: f32,
+}
+
+/// how hard a player camera is shaking, from 0 to 1, settling by itself. position_camera turns
+/// the camera by its square, so small knocks barely show and big ones rattle
+#[derive(Component, Default)]
+pub(crate) struct CameraShake {
+    pub(crate) trauma: f32,
+    /// seconds it's been shaking, for where it is along the wobble
+    time: f32,
+}
+
+/// how quickly camera shake trauma settles, per second
+const SHAKE_DECAY: f32 = 1.5;
+
+/// furthest a camera turns each way at full shake, in degrees
+const SHAKE_ANGLE: f32 = 4.0;
+
+/// how fast the shake wobbles, in radians per second
+const SHAKE_FREQUENCY: f32 = 40.0;
+
+/// lean limits and speed
+#[derive(Resource)]
+pub(crate) struct LeanConfig {
+    /// roll at full lean, in degrees
+    max_angle: f32,
+    /// sideways shift at full lean
+    max_offset: f32,
+    /// how quickly the lean eases in and out, per second
+    speed: f32,
+}
+
+impl Default for LeanConfig {
+    fn default() -> Self {
+        Self {
+            max_angle: 15.0,
+            max_offset: 0.4,
+            speed: 10.0,
+        }
+    }
+}
+
+/// seconds since a player camera last received look input
+#[derive(Component, Default)]
+pub(crate) struct TimeSinceLook(f32);
+
+/// third-person orbit camera that swings back behind the player after a while without look input
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct CameraRecenter {
+    /// orbit the camera around the player in third person, with the body turning to face its
+    /// movement direction, instead of the mouse turning the body
+    pub(crate) enabled: bool,
+    /// seconds without look input, while moving, before recentering starts
+    idle_delay: f32,
+    /// recentering speed, in radians per second
+    speed: f32,
+}
+
+impl Default for CameraRecenter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_delay: 2.0,
+            speed: 2.0,
+        }
+    }
+}
+
+impl CameraRecenter {
+    /// whether look yaw orbits the camera rather than turning the body
+    pub(crate) fn orbits(&self, rig: &CameraRig, mode: ControlMode) -> bool {
+        self.enabled && !rig.is_first_person() && mode == ControlMode::MouseLook
+    }
+}
+
+/// wrap an angle to -PI..PI
+pub(crate) fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// lock/unlock mouse based on MouseLocked resource
+pub(crate) fn mouse_lock(
+    settings: Res<CursorSettings>,
+    locked: Res<MouseLocked>,
+    suspended: Res<CursorSuspended>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if locked.is_changed() || suspended.is_changed() || settings.is_changed() {
+        let mut window = window.single_mut();
+        (window.cursor.grab_mode, window.cursor.visible) = if !locked.0 || suspended.0 {
+            (CursorGrabMode::None, true)
+        } else if settings.visible_when_locked && !cfg!(target_arch = "wasm32") {
+            (CursorGrabMode::Confined, true)
+        } else {
+            // bevy falls back to confined on platforms that can't lock the cursor (e.g. windows).
+            // the web can't confine at all, so it always gets the browser's pointer lock
+            (CursorGrabMode::Locked, false)
+        };
+    }
+}
+
+/// release the cursor when the primary window loses focus, and regrab it on focus if configured
+pub(crate) fn window_focus(
+    settings: Res<CursorSettings>,
+    mut suspended: ResMut<CursorSuspended>,
+    mut er_focus: EventReader<WindowFocused>,
+    window: Query<(), With<PrimaryWindow>>,
+) {
+    for ev in er_focus.read() {
+        if !window.contains(ev.window) {
+            continue;
+        }
+
+        if !ev.focused {
+            suspended.0 = true;
+        } else if settings.regrab == RegrabMode::OnFocus {
+            suspended.0 = false;
+        }
+    }
+}
+
+/// regrab a suspended cursor when the window is clicked
+pub(crate) fn click_to_regrab(
+    settings: Res<CursorSettings>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut suspended: ResMut<CursorSuspended>,
+) {
+    if suspended.0
+        && settings.regrab == RegrabMode::OnClick
+        && mouse.just_pressed(MouseButton::Left)
+    {
+        suspended.0 = false;
+    }
+}
+
+/// listen for the document's pointer lock events, for sync_pointer_lock
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn listen_for_pointer_lock(events: Res<PointerLockEvents>) {
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    for (name, event) in [
+        ("pointerlockchange", PointerLockEvent::Changed),
+        ("pointerlockerror", PointerLockEvent::Refused),
+    ] {
+        let events = events.0.clone();
+        let listener = Closure::<dyn FnMut()>::new(move || {
+            events.lock().unwrap().push(event);
+        });
+        if document
+            .add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())
+            .is_err()
+        {
+            warn!("couldn't listen for {name}, so a lost pointer lock won't be noticed");
+        }
+        // listening for the life of the page
+        listener.forget();
+    }
+}
+
+/// notice the browser releasing pointer lock (Esc, switching tabs) or refusing it, so
+/// MouseLocked stops claiming the mouse is locked until the next click
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn sync_pointer_lock(
+    events: Res<PointerLockEvents>,
+    mut locked: ResMut<MouseLocked>,
+    mut pending: ResMut<PointerLockPending>,
+) {
+    let events = std::mem::take(&mut *events.0.lock().unwrap());
+    for event in events {
+        let held = web_sys::window()
+            .and_then(|window| window.document())
+            .is_some_and(|document| document.pointer_lock_element().is_some());
+        // the game releasing the lock itself (menus, top-down mode) has already cleared MouseLocked
+        if (event == PointerLockEvent::Refused || !held) && locked.0 {
+            locked.0 = false;
+            pending.0 = true;
+        }
+    }
+}
+
+/// ask for pointer lock on a click in the canvas, the only time browsers grant it
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn click_to_lock(
+    mouse: Res<ButtonInput<MouseButton>>,
+    state: Res<State<MenuState>>,
+    mode: Res<ControlMode>,
+    playback: Option<Res<CameraPathPlayback>>,
+    mut locked: ResMut<MouseLocked>,
+    mut pending: ResMut<PointerLockPending>,
+) {
+    if pending.0
+        && mouse.just_pressed(MouseButton::Left)
+        && *state.get() == MenuState::Closed
+        && *mode == ControlMode::MouseLook
+        && playback.is_none()
+    {
+        pending.0 = false;
+        locked.0 = true;
+    }
+}
+
+/// add a viewmodel camera and placeholder held item under a player camera
+pub(crate) fn spawn_viewmodel(
+    camera: &mut EntityCommands,
+    slot: usize,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    insert_input: impl Fn(&mut EntityCommands),
+) {
+    let viewmodel_layer = VIEWMODEL_LAYER + slot;
+    camera.with_children(|children| {
+        children
+            .spawn(Camera3dBundle {
+                camera: Camera {
+                    order: 1 + slot as isize,
+                    clear_color: ClearColorConfig::None,
+                    ..default()
+                },
+                projection: Projection::Perspective(PerspectiveProjection {
+                    fov: VIEWMODEL_FOV.to_radians(),
+                    ..default()
+                }),
+                ..default()
+            })
+            .insert(RenderLayers::layer(viewmodel_layer))
+            .insert(ViewModelCamera)
+            .insert(SplitScreenSlot(slot))
+            .with_children(|children| {
+                // placeholder held item
+                let rest = Transform::from_xyz(0.35, -0.3, -0.7);
+                let mut item = children.spawn(PbrBundle {
+                    mesh: meshes.add(Cuboid::new(0.12, 0.12, 0.5)),
+                    material: materials
+                        .add(StandardMaterial::from_color(Color::srgb(0.3, 0.3, 0.35))),
+                    transform: rest,
+                    ..default()
+                });
+                insert_input(&mut item);
+                item.insert(ViewModel::new(rest, viewmodel_layer));
+            });
+    });
+}
+
+/// split the window between local players' cameras, top to bottom
+pub(crate) fn update_viewports(
+    split: Res<SplitScreen>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut er_resized: EventReader<WindowResized>,
+    mut cameras: Query<(&mut Camera, Ref<SplitScreenSlot>)>,
+) {
+    let resized = er_resized.read().count() > 0;
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    let players = split.players.clamp(1, MAX_LOCAL_PLAYERS) as u32;
+    let size = window.physical_size();
+    let height = size.y / players;
+
+    for (mut camera, slot) in &mut cameras {
+        if !resized && !slot.is_added() {
+            continue;
+        }
+
+        camera.viewport = (players > 1).then(|| Viewport {
+            physical_position: UVec2::new(0, height * slot.0 as u32),
+            physical_size: UVec2::new(size.x, height),
+            ..default()
+        });
+    }
+}
+
+/// the settings that decide how look input turns a player, for systems reading it
+#[derive(SystemParam)]
+pub(crate) struct LookControls<'w> {
+    pub(crate) mode: Res<'w, ControlMode>,
+    pub(crate) rig: Res<'w, CameraRig>,
+    pub(crate) recenter: Res<'w, CameraRecenter>,
+    pub(crate) look: Res<'w, LookConfig>,
+}
+
+/// rotate player entities by their InputSnapshot's look X, but their cameras by look Y
+#[allow(clippy::type_complexity)]
+pub(crate) fn player_rotation(
+    controls: LookControls,
+    up: Res<WorldUp>,
+    mut players: Query<(&mut Transform, &InputSnapshot, Option<&Children>), With<Player>>,
+    mut player_camera: Query<(&mut PlayerCamera, &mut CameraYaw, &mut TimeSinceLook)>,
+) {
+    let LookControls {
+        mode,
+        rig,
+        recenter,
+        look,
+    } = controls;
+    // look yaw goes to the camera rather than the body while orbiting or free looking
+    let yaw_on_camera = recenter.orbits(&rig, *mode) || look.free_looks(&rig, *mode);
+
+    for (mut player_transform, snapshot, children) in &mut players {
+        let delta = snapshot.look;
+        if delta == Vec2::ZERO {
+            continue;
+        }
+
+        if !yaw_on_camera {
+            up.turn(&mut player_transform, -delta.x);
+        }
+
+        for &child in children.into_iter().flatten() {
+            if let Ok((mut player_camera, mut yaw, mut since_look)) = player_camera.get_mut(child) {
+                player_camera.0 = rig.clamp_pitch(player_camera.0 - delta.y, &look);
+                if yaw_on_camera {
+                    yaw.0 -= delta.x;
+                }
+                // any look input cancels recentering straight away
+                since_look.0 = 0.0;
+            }
+        }
+    }
+}
+
+/// listen for Action::LookAtOrigin to look at the scene origin
+pub(crate) fn look_at_key(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut ew_look_at: EventWriter<LookAt>,
+) {
+    if bindings.just_pressed(&keyboard, Action::LookAtOrigin) {
+        ew_look_at.send(LookAt {
+            target: Vec3::ZERO,
+            duration: 0.5,
+        });
+    }
+}
+
+/// compute the body yaw and camera pitch the keyboard and mouse player needs to face a LookAt target
+pub(crate) fn start_look_at(
+    mut commands: Commands,
+    mut er_look_at: EventReader<LookAt>,
+    rig: Res<CameraRig>,
+    look: Res<LookConfig>,
+    up: Res<WorldUp>,
+    mut player_transform: Query<&mut Transform, (With<Player>, With<KeyboardMouseInput>)>,
+    mut player_camera: Query<(&mut PlayerCamera, &mut CameraYaw), With<KeyboardMouseInput>>,
+) {
+    let Some(ev) = er_look_at.read().last() else {
+        return;
+    };
+
+    let (Ok(mut player_transform), Ok((mut player_camera, mut yaw))) = (
+        player_transform.get_single_mut(),
+        player_camera.get_single_mut(),
+    ) else {
+        return;
+    };
+
+    // the tween turns the body, so hand it any orbit yaw first
+    if yaw.0 != 0.0 {
+        up.turn(&mut player_transform, yaw.0);
+        yaw.0 = 0.0;
+    }
+
+    let eye = player_transform.translation + *up.up * rig.height;
+    let to_target = up.to_local(ev.target - eye);
+    let yaw = f32::atan2(-to_target.x, -to_target.z) - rig.yaw_bias;
+    let pitch = f32::atan2(to_target.y, to_target.xz().length()) - rig.pitch_bias;
+    let to = Vec2::new(yaw, rig.clamp_pitch(pitch, &look));
+
+    if ev.duration <= 0.0 {
+        player_transform.rotation = up.rotation(to.x);
+        player_camera.0 = to.y;
+        commands.remove_resource::<LookAtTween>();
+    } else {
+        commands.insert_resource(LookAtTween {
+            from: Vec2::new(up.yaw(&player_transform), player_camera.0),
+            to,
+            elapsed: 0.0,
+            duration: ev.duration,
+        });
+    }
+}
+
+/// ease towards the look-at target, cancelling if the mouse moves
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn ease_look_at(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    locked: Res<MouseLocked>,
+    tween: Option<ResMut<LookAtTween>>,
+    up: Res<WorldUp>,
+    mut er_motion: EventReader<MouseMotion>,
+    mut player_transform: Query<&mut Transform, (With<Player>, With<KeyboardMouseInput>)>,
+    mut player_camera: Query<&mut PlayerCamera, With<KeyboardMouseInput>>,
+) {
+    let moved = locked.0 && er_motion.read().any(|ev| ev.delta != Vec2::ZERO);
+    let Some(mut tween) = tween else {
+        return;
+    };
+
+    let (Ok(mut player_transform), Ok(mut player_camera)) = (
+        player_transform.get_single_mut(),
+        player_camera.get_single_mut(),
+    ) else {
+        return;
+    };
+
+    if moved {
+        commands.remove_resource::<LookAtTween>();
+        return;
+    }
+
+    tween.elapsed += time.delta_seconds();
+    let t = (tween.elapsed / tween.duration).min(1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+
+    // turn the short way around
+    let yaw_delta = wrap_angle(tween.to.x - tween.from.x);
+    player_transform.rotation = up.rotation(tween.from.x + yaw_delta * t);
+    player_camera.0 = tween.from.y.lerp(tween.to.y, t);
+
+    if tween.elapsed >= tween.duration {
+        commands.remove_resource::<LookAtTween>();
+    }
+}
+
+/// ease the keyboard and mouse player's view towards CameraLookTarget, on top of mouse look
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn assist_look(
+    time: Res<Time<Real>>,
+    mut target: ResMut<CameraLookTarget>,
+    config: Res<LookAssistConfig>,
+    controls: LookControls,
+    up: Res<WorldUp>,
+    mut assist: Local<LookAssist>,
+    targets: Query<&GlobalTransform>,
+    mut player_transform: Query<&mut Transform, (With<Player>, With<KeyboardMouseInput>)>,
+    mut player_camera: Query<
+        (&mut PlayerCamera, &mut CameraYaw, &mut TimeSinceLook),
+        With<KeyboardMouseInput>,
+    >,
+) {
+    let LookControls {
+        mode,
+        rig,
+        recenter,
+        look,
+    } = controls;
+    let point = match target.0 {
+        Some(LookTarget::Entity(entity)) => {
+            let point = targets.get(entity).ok().map(GlobalTransform::translation);
+            if point.is_none() {
+                target.0 = None;
+            }
+            point
+        }
+        Some(LookTarget::Point(point)) => Some(point),
+        None => None,
+    };
+    let step = if config.blend > 0.0 {
+        time.delta_seconds() / config.blend
+    } else {
+        1.0
+    };
+    if point.is_some() {
+        assist.weight = (assist.weight + step).min(1.0);
+        assist.towards = point;
+    } else {
+        assist.weight = (assist.weight - step).max(0.0);
+    }
+    if assist.weight == 0.0 {
+        assist.towards = None;
+    }
+    let Some(point) = assist.towards else {
+        return;
+    };
+
+    let (Ok(mut player_transform), Ok((mut pitch, mut yaw, mut since_look))) = (
+        player_transform.get_single_mut(),
+        player_camera.get_single_mut(),
+    ) else {
+        return;
+    };
+
+    let eye = player_transform.translation + *up.up * rig.height;
+    let to_target = up.to_local(point - eye);
+    let facing = f32::atan2(-to_target.x, -to_target.z) - rig.yaw_bias;
+    let target_pitch = f32::atan2(to_target.y, to_target.xz().length()) - rig.pitch_bias;
+
+    // smoothstep the blend so the pull doesn't start or stop with a jolt
+    let weight = assist.weight * assist.weight * (3.0 - 2.0 * assist.weight);
+    let t = weight * (1.0 - (-config.rate * time.delta_seconds()).exp());
+    let turn = wrap_angle(facing - (up.yaw(&player_transform) + yaw.0)) * t;
+    // orbiting and free look turn the camera around the body rather than the body itself
+    if recenter.orbits(&rig, *mode) || look.free_looks(&rig, *mode) {
+        yaw.0 += turn;
+    } else {
+        up.turn(&mut player_transform, turn);
+    }
+    pitch.0 = rig.clamp_pitch(pitch.0 + (target_pitch - pitch.0) * t, &look);
+    // hold off recentering while the view is being turned
+    since_look.0 = 0.0;
+}
+
+/// apply the configured field of view to the player camera, or the speed fov while it's on
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_fov(
+    look: Res<LookConfig>,
+    zoom_config: Res<ZoomConfig>,
+    zoom: Res<Zoom>,
+    speed_fov: Res<SpeedFovConfig>,
+    mut camera: Query<
+        (
+            &mut Projection,
+            Has<KeyboardMouseInput>,
+            Option<&speed_fov::SpeedFov>,
+        ),
+        With<PlayerCamera>,
+    >,
+) {
+    // the speed fov changes as the player moves, so it's applied every frame
+    if !speed_fov.enabled
+        && !speed_fov.is_changed()
+        && !look.is_changed()
+        && !zoom_config.is_changed()
+        && !zoom.is_changed()
+    {
+        return;
+    }
+
+    // the zoom is driven by the right mouse button, so it only applies to the mouse player
+    for (mut projection, mouse, speed) in &mut camera {
+        if let Projection::Perspective(perspective) = &mut *projection {
+            let base = match speed {
+                Some(speed) if speed_fov.enabled => speed_fov.fov_at(speed.0),
+                _ => look.fov,
+            };
+            let fov = if mouse {
+                zoom.fov(base, &zoom_config)
+            } else {
+                base
+            };
+            perspective.fov = fov.to_radians();
+        }
+    }
+}
+
+/// zoom in while the right mouse button is held (or toggled), easing the zoom amount
+pub(crate) fn update_zoom(
+    time: Res<Time<Real>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    config: Res<ZoomConfig>,
+    mut zoom: ResMut<Zoom>,
+) {
+    if config.toggle {
+        if mouse.just_pressed(MouseButton::Right) {
+            zoom.active = !zoom.active;
+        }
+    } else {
+        zoom.active = mouse.pressed(MouseButton::Right);
+    }
+
+    let target = if zoom.active { 1.0 } else { 0.0 };
+    if zoom.amount != target {
+        let t = 1.0 - (-config.speed * time.delta_seconds()).exp();
+        zoom.amount = zoom.amount.lerp(target, t);
+        if (zoom.amount - target).abs() < 0.001 {
+            zoom.amount = target;
+        }
+    }
+}
+
+/// move viewmodels and all of their descendants onto the viewmodel render layer
+pub(crate) fn assign_viewmodel_layers(
+    mut commands: Commands,
+    viewmodels: Query<(Entity, &ViewModel), Added<ViewModel>>,
+    children: Query<&Children>,
+) {
+    for (entity, viewmodel) in &viewmodels {
+        let layers = RenderLayers::layer(viewmodel.layer);
+        commands.entity(entity).insert(layers.clone());
+        for descendant in children.iter_descendants(entity) {
+            commands.entity(descendant).insert(layers.clone());
+        }
+    }
+}
+
+/// make viewmodels lag slightly behind look input, easing back to rest
+pub(crate) fn viewmodel_sway(
+    time: Res<Time>,
+    locked: Res<MouseLocked>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut er_motion: EventReader<MouseMotion>,
+    mut viewmodels: Query<(
+        &mut ViewModel,
+        &mut Transform,
+        Option<&GamepadInput>,
+        Has<KeyboardMouseInput>,
+    )>,
+) {
+    const SWAY: f32 = 0.0005;
+    /// sway from the right stick at full deflection, in mouse counts per second
+    const STICK_SWAY: f32 = 600.0;
+    const MAX_SWAY: f32 = 0.06;
+    const RETURN_SPEED: f32 = 10.0;
+
+    let mouse = er_motion.read().map(|ev| ev.delta).sum::<Vec2>();
+    let mouse = if locked.0 { mouse } else { Vec2::ZERO };
+    let decay = (-RETURN_SPEED * time.delta_seconds()).exp();
+
+    for (mut viewmodel, mut transform, gamepad_input, keyboard_mouse) in &mut viewmodels {
+        let delta = match gamepad_input {
+            Some(input) => input.gamepad(&gamepads).map_or(Vec2::ZERO, |gamepad| {
+                let axis = |axis_type| {
+                    axes.get(GamepadAxis::new(gamepad, axis_type))
+                        .unwrap_or(0.0)
+                };
+                Vec2::new(
+                    axis(GamepadAxisType::RightStickX),
+                    -axis(GamepadAxisType::RightStickY),
+                ) * STICK_SWAY
+                    * time.delta_seconds()
+            }),
+            None if keyboard_mouse => mouse,
+            None => Vec2::ZERO,
+        };
+        viewmodel.sway = ((viewmodel.sway - delta * SWAY) * decay).clamp_length_max(MAX_SWAY);
+
+        let sway = viewmodel.sway;
+        transform.translation = viewmodel.rest.translation + Vec3::new(sway.x, -sway.y, 0.0);
+        transform.rotation = viewmodel.rest.rotation
+            * Quat::from_euler(EulerRot::YXZ, sway.x * 2.0, sway.y * 2.0, 0.0);
+    }
+}
+
+/// only draw the viewmodel in first person, from the player's own view
+pub(crate) fn toggle_viewmodel(
+    rig: Res<CameraRig>,
+    mode: Res<ControlMode>,
+    photo_mode: Option<Res<PhotoMode>>,
+    playback: Option<Res<CameraPathPlayback>>,
+    mut camera: Query<&mut Camera, With<ViewModelCamera>>,
+) {
+    let active = rig.is_first_person()
+        && *mode == ControlMode::MouseLook
+        && photo_mode.is_none()
+        && playback.is_none();
+    for mut camera in &mut camera {
+        if camera.is_active != active {
+            camera.is_active = active;
+        }
+    }
+}
+
+/// move each player's capsule onto their own first-person body layer while their camera is in
+/// first person, so it sees past it, and back again in third person, in photo mode or during
+/// a camera path
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_first_person_body(
+    mut commands: Commands,
+    body: Res<FirstPersonBody>,
+    rig: Res<CameraRig>,
+    mode: Res<ControlMode>,
+    photo_mode: Option<Res<PhotoMode>>,
+    playback: Option<Res<CameraPathPlayback>>,
+    cameras: Query<(&Parent, &SplitScreenSlot), With<PlayerCamera>>,
+    players: Query<(Option<&RenderLayers>, Has<NotShadowCaster>), With<Player>>,
+) {
+    let first_person = rig.is_first_person()
+        && *mode == ControlMode::MouseLook
+        && photo_mode.is_none()
+        && playback.is_none();
+    for (parent, slot) in &cameras {
+        let Ok((layers, not_shadow_caster)) = players.get(parent.get()) else {
+            continue;
+        };
+        let own = RenderLayers::layer(BODY_LAYER + slot.0);
+        let (target, casts_shadow) = match *body {
+            FirstPersonBody::ShadowOnly if first_person => (own, true),
+            FirstPersonBody::Hidden if first_person => (own, false),
+            _ => (RenderLayers::layer(0), true),
+        };
+        let mut player = commands.entity(parent.get());
+        if layers.cloned().unwrap_or_default() != target {
+            player.insert(target);
+        }
+        if casts_shadow && not_shadow_caster {
+            player.remove::<NotShadowCaster>();
+        } else if !casts_shadow && !not_shadow_caster {
+            player.insert(NotShadowCaster);
+        }
+    }
+}
+
+/// switch between control modes with Action::ToggleControlMode
+pub(crate) fn toggle_control_mode(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut mode: ResMut<ControlMode>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ToggleControlMode) {
+        *mode = match *mode {
+            ControlMode::MouseLook => ControlMode::TopDown,
+            ControlMode::TopDown => ControlMode::MouseLook,
+        };
+    }
+}
+
+/// swap camera projections and the mouse lock when the control mode changes
+pub(crate) fn apply_control_mode(
+    mode: Res<ControlMode>,
+    look: Res<LookConfig>,
+    zoom_config: Res<ZoomConfig>,
+    zoom: Res<Zoom>,
+    mut locked: ResMut<MouseLocked>,
+    mut camera: Query<(&mut Projection, &mut PlayerCamera, Has<KeyboardMouseInput>)>,
+) {
+    if !mode.is_changed() {
+        return;
+    }
+
+    match *mode {
+        ControlMode::TopDown => {
+            // mouse look isn't used from above
+            locked.0 = false;
+            for (mut projection, _, _) in &mut camera {
+                *projection = Projection::Orthographic(OrthographicProjection {
+                    scaling_mode: ScalingMode::FixedVertical(TOP_DOWN_VIEW_HEIGHT),
+                    ..default()
+                });
+            }
+        }
+        ControlMode::MouseLook => {
+            // keep the startup cursor settings when starting in this mode
+            if !mode.is_added() {
+                locked.0 = true;
+            }
+            for (mut projection, mut player_camera, mouse) in &mut camera {
+                let fov = if mouse {
+                    zoom.fov(look.fov, &zoom_config)
+                } else {
+                    look.fov
+                };
+                *projection = Projection::Perspective(PerspectiveProjection {
+                    fov: fov.to_radians(),
+                    ..default()
+                });
+                // have position_camera put the camera back on the rig
+                player_camera.set_changed();
+            }
+        }
+    }
+}
+
+/// hold cameras straight above their players, looking down with world-fixed axes
+pub(crate) fn top_down_camera(
+    players: Query<&Transform, (With<Player>, Without<PlayerCamera>)>,
+    mut camera: Query<(&Parent, &mut Transform), With<PlayerCamera>>,
+) {
+    let down = Transform::IDENTITY
+        .looking_to(Vec3::NEG_Y, Vec3::NEG_Z)
+        .rotation;
+
+    for (parent, mut transform) in &mut camera {
+        let Ok(player_transform) = players.get(parent.get()) else {
+            continue;
+        };
+
+        // undo the player's yaw so the view doesn't spin as they turn
+        *transform = Transform {
+            translation: Vec3::Y * TOP_DOWN_HEIGHT,
+            rotation: player_transform.rotation.inverse() * down,
+            ..default()
+        };
+    }
+}
+
+/// fade FadeWhenOccluding geometry between third-person cameras and their players, restoring
+/// the original materials once it no longer occludes
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn fade_occluders(
+    mut commands: Commands,
+    rig: Res<CameraRig>,
+    mode: Res<ControlMode>,
+    config: Res<OcclusionFadeConfig>,
+    rapier_context: Res<RapierContext>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cameras: Query<(&GlobalTransform, &Parent), With<PlayerCamera>>,
+    players: Query<&GlobalTransform, With<Player>>,
+    fadeable: Query<(), With<FadeWhenOccluding>>,
+    mut occluders: Query<
+        (Entity, &mut Handle<StandardMaterial>, Option<&Faded>),
+        With<FadeWhenOccluding>,
+    >,
+) {
+    let mut occluding = HashSet::new();
+
+    if !rig.is_first_person() && *mode == ControlMode::MouseLook {
+        let is_fadeable = |entity| fadeable.contains(entity);
+        let filter = QueryFilter::new().exclude_sensors().predicate(&is_fadeable);
+
+        for (camera_transform, parent) in &cameras {
+            let Ok(player_transform) = players.get(parent.get()) else {
+                continue;
+            };
+
+            // cast from the player out to the camera, collecting everything in between
+            let origin = player_transform.translation();
+            let offset = camera_transform.translation() - origin;
+            let distance = offset.length();
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            rapier_context.intersections_with_ray(
+                origin,
+                offset / distance,
+                distance,
+                true,
+                filter,
+                |entity, _| {
+                    occluding.insert(entity);
+                    true
+                },
+            );
+        }
+    }
+
+    for (entity, mut material, faded) in &mut occluders {
+        match (occluding.contains(&entity), faded) {
+            (true, None) => {
+                // fade a private copy so other users of the material are unaffected
+                let Some(mut copy) = materials.get(&*material).cloned() else {
+                    continue;
+                };
+                copy.base_color.set_alpha(config.alpha);
+                copy.alpha_mode = AlphaMode::Blend;
+
+                let original = std::mem::replace(&mut *material, materials.add(copy));
+                commands.entity(entity).insert(Faded { original });
+            }
+            (true, Some(_)) if config.is_changed() => {
+                if let Some(copy) = materials.get_mut(&*material) {
+                    copy.base_color.set_alpha(config.alpha);
+                }
+            }
+            (false, Some(faded)) => {
+                // drop the faded copy so it doesn't leak
+                materials.remove(&*material);
+                *material = faded.original.clone();
+                commands.entity(entity).remove::<Faded>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// place the camera according to the camera rig and the current pitch
+#[allow(clippy::type_complexity)]
+pub(crate) fn position_camera(
+    rig: Res<CameraRig>,
+    look: Res<LookConfig>,
+    mut camera: Query<(
+        &mut Transform,
+        &mut PlayerCamera,
+        Ref<CameraYaw>,
+        Ref<Lean>,
+        Ref<CameraShake>,
+    )>,
+) {
+    for (mut transform, mut player_camera, yaw, lean, shake) in &mut camera {
+        let changed = player_camera.is_changed()
+            || yaw.is_changed()
+            || lean.is_changed()
+            || shake.is_changed();
+        if !rig.is_changed() && !look.is_changed() && !changed {
+            continue;
+        }
+
+        // re-clamp in case the rig's or look config's pitch limits changed
+        let pitch = rig.clamp_pitch(player_camera.0, &look);
+        if pitch != player_camera.0 {
+            player_camera.0 = pitch;
+        }
+
+        // orbit the rig around the player's origin
+        let orbit = Quat::from_rotation_y(yaw.0);
+        *transform = Transform::from_rotation(orbit) * rig.camera_transform(pitch);
+
+        // shift sideways and roll for lean
+        transform.translation += orbit * Vec3::X * lean.offset;
+        transform.rotate_local_z(-lean.angle);
+
+        // and wobble for shake, along a few sine waves out of step with each other
+        let angle = shake.trauma * shake.trauma * SHAKE_ANGLE.to_radians();
+        if angle > 0.0 {
+            let t = shake.time * SHAKE_FREQUENCY;
+            let wobble = |phase: f32| ((t + phase).sin() + (2.3 * t + phase).sin()) * 0.5;
+            transform.rotate_local_x(angle * wobble(0.0));
+            transform.rotate_local_y(angle * wobble(1.7));
+            transform.rotate_local_z(angle * wobble(3.1));
+        }
+    }
+}
+
+/// settle shaking cameras
+pub(crate) fn shake_cameras(time: Res<Time>, mut cameras: Query<&mut CameraShake>) {
+    for mut shake in &mut cameras {
+        if shake.trauma > 0.0 {
+            shake.time += time.delta_seconds();
+            shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_seconds()).max(0.0);
+        } else if shake.time != 0.0 {
+            shake.time = 0.0;
+        }
+    }
+}
+
+/// lean the keyboard and mouse player's first-person camera while the lean keys are held,
+/// shortening the lean where a wall is in the way
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn update_lean(
+    time: Res<Time>,
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    rig: Res<CameraRig>,
+    config: Res<LeanConfig>,
+    up: Res<WorldUp>,
+    rapier_context: Res<RapierContext>,
+    players: Query<(Entity, &Transform), (With<Player>, With<KeyboardMouseInput>)>,
+    mut camera: Query<(&mut Lean, &CameraYaw), With<KeyboardMouseInput>>,
+) {
+    /// gap kept between a leaning camera and the wall it leans towards
+    const WALL_MARGIN: f32 = 0.15;
+
+    let (Ok((player, player_transform)), Ok((mut lean, yaw))) =
+        (players.get_single(), camera.get_single_mut())
+    else {
+        return;
+    };
+
+    let mut direction = 0.0;
+    if rig.is_first_person() {
+        if bindings.pressed(&keyboard, Action::LeanLeft) {
+            direction -= 1.0;
+        }
+        if bindings.pressed(&keyboard, Action::LeanRight) {
+            direction += 1.0;
+        }
+    }
+
+    // limit the lean to the free space beside the eye
+    let mut reach = 1.0;
+    if direction != 0.0 && config.max_offset > 0.0 {
+        let eye = player_transform.translation + *up.up * rig.height;
+        let side = player_transform.rotation * Quat::from_rotation_y(yaw.0) * Vec3::X * direction;
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(player);
+        if let Some((_, distance)) =
+            rapier_context.cast_ray(eye, side, config.max_offset + WALL_MARGIN, true, filter)
+        {
+            reach = ((distance - WALL_MARGIN) / config.max_offset).clamp(0.0, 1.0);
+        }
+    }
+
+    let target = direction * reach;
+    let t = 1.0 - (-config.speed * time.delta_seconds()).exp();
+    let angle = lean.angle.lerp(target * config.max_angle.to_radians(), t);
+    let offset = lean.offset.lerp(target * config.max_offset, t);
+
+    // settle exactly, so position_camera stops updating once centered
+    let (angle, offset) = if direction == 0.0 && offset.abs() < 1e-3 {
+        (0.0, 0.0)
+    } else {
+        (angle, offset)
+    };
+    if angle != lean.angle || offset != lean.offset {
+        lean.angle = angle;
+        lean.offset = offset;
+    }
+}
+
+/// advance look idle timers, and swing orbiting cameras back behind moving players once idle.
+/// when orbiting and free look stop, the camera's yaw is handed back to the body so the view
+/// doesn't jump
+pub(crate) fn recenter_camera(
+    time: Res<Time<Real>>,
+    controls: LookControls,
+    up: Res<WorldUp>,
+    mut players: Query<(&mut Transform, &Velocity), With<Player>>,
+    mut cameras: Query<(&Parent, &mut CameraYaw, &mut TimeSinceLook)>,
+) {
+    /// horizontal speed above which a player counts as moving
+    const MOVING_SPEED: f32 = 0.5;
+
+    let LookControls {
+        mode,
+        rig,
+        recenter,
+        look,
+    } = controls;
+    let orbits = recenter.orbits(&rig, *mode);
+    let free_looks = look.free_looks(&rig, *mode);
+
+    for (parent, mut yaw, mut since_look) in &mut cameras {
+        since_look.0 += time.delta_seconds();
+
+        let Ok((mut player_transform, velocity)) = players.get_mut(parent.get()) else {
+            continue;
+        };
+
+        if !orbits {
+            if !free_looks && yaw.0 != 0.0 {
+                up.turn(&mut player_transform, yaw.0);
+                yaw.0 = 0.0;
+            }
+            continue;
+        }
+
+        let moving = up.horizontal(velocity.linvel).length() > MOVING_SPEED;
+        if moving && since_look.0 >= recenter.idle_delay && yaw.0 != 0.0 {
+            let step = recenter.speed * time.delta_seconds();
+            let current = wrap_angle(yaw.0);
+            yaw.0 = current - current.clamp(-step, step);
+        }
+    }
+}
+
+/// listen for Action::CycleCameraRig to cycle through camera rig presets
+pub(crate) fn cycle_camera_preset(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut preset: Local<CameraPreset>,
+    mut rig: ResMut<CameraRig>,
+) {
+    if bindings.just_pressed(&keyboard, Action::CycleCameraRig) {
+        *preset = preset.next();
+        *rig = preset.rig();
+    }
+}
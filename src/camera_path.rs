@@ -0,0 +1,337 @@
+//! cutscene camera paths: a catmull-rom spline flown by a camera of its own, started from a key
+//! or a trigger volume
+
+use std::f32::consts::PI;
+
+use bevy::{
+    color::palettes::css::{ORANGE, YELLOW},
+    math::cubic_splines::{CubicCardinalSpline, CubicCurve, CubicGenerator},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    input::{Action, KeyBindings, Keys},
+    MouseLocked, Player, PlayerCamera, WorldUp,
+};
+
+/// a single cutscene keyframe
+#[derive(Clone, Copy)]
+struct CameraKeyframe {
+    position: Vec3,
+    look_at: Vec3,
+}
+
+/// cutscene camera path, flown along a catmull-rom spline through the keyframes
+#[derive(Resource)]
+pub(crate) struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    /// total playback time in seconds
+    duration: f32,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        // a slow orbit around the scene
+        let keyframes = (0..=6)
+            .map(|i| {
+                let angle = i as f32 / 6.0 * PI * 1.5;
+                CameraKeyframe {
+                    position: Vec3::new(angle.cos() * 12.0, 4.0 + i as f32, angle.sin() * 12.0),
+                    look_at: Vec3::ZERO,
+                }
+            })
+            .collect();
+
+        Self {
+            keyframes,
+            duration: 8.0,
+        }
+    }
+}
+
+impl CameraPath {
+    /// build the position and look-at curves, if there are enough keyframes
+    fn curves(&self) -> Option<(CubicCurve<Vec3>, CubicCurve<Vec3>)> {
+        if self.keyframes.len() < 2 {
+            return None;
+        }
+
+        let positions = self
+            .keyframes
+            .iter()
+            .map(|k| k.position)
+            .collect::<Vec<_>>();
+        let targets = self.keyframes.iter().map(|k| k.look_at).collect::<Vec<_>>();
+        Some((
+            CubicCardinalSpline::new_catmull_rom(positions).to_curve(),
+            CubicCardinalSpline::new_catmull_rom(targets).to_curve(),
+        ))
+    }
+}
+
+/// present while a camera path is playing
+#[derive(Resource)]
+pub(crate) struct CameraPathPlayback {
+    elapsed: f32,
+    positions: CubicCurve<Vec3>,
+    targets: CubicCurve<Vec3>,
+    /// MouseLocked from before the path, put back after it
+    locked: bool,
+    /// each player camera and whether it was active before the path, put back after it
+    player_cameras: Vec<(Entity, bool)>,
+}
+
+/// event to start playing the camera path
+#[derive(Event)]
+pub(crate) struct PlayCameraPath;
+
+/// resource to toggle the camera path gizmo preview
+#[derive(Resource, Default)]
+pub(crate) struct CameraPathDebug(pub(crate) bool);
+
+/// sensor volume that starts the camera path when the player enters it
+#[derive(Component)]
+pub(crate) struct CameraPathTrigger;
+
+/// camera used for camera path playback
+#[derive(Component)]
+pub(crate) struct CutsceneCamera;
+
+/// the cutscene camera, inactive until a camera path plays
+pub(crate) fn setup_cutscene_camera(mut commands: Commands) {
+    commands
+        .spawn(Camera3dBundle {
+            camera: Camera {
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(CutsceneCamera);
+}
+
+/// listen for Action::PlayCameraPath to play the camera path
+pub(crate) fn camera_path_key(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut ew_play: EventWriter<PlayCameraPath>,
+) {
+    if bindings.just_pressed(&keyboard, Action::PlayCameraPath) {
+        ew_play.send(PlayCameraPath);
+    }
+}
+
+/// play the camera path when the player enters a trigger volume
+pub(crate) fn camera_path_trigger(
+    mut er_collision: EventReader<CollisionEvent>,
+    mut ew_play: EventWriter<PlayCameraPath>,
+    player: Query<(), With<Player>>,
+    triggers: Query<(), With<CameraPathTrigger>>,
+) {
+    for ev in er_collision.read() {
+        if let CollisionEvent::Started(a, b, _) = *ev {
+            if (player.contains(a) && triggers.contains(b))
+                || (player.contains(b) && triggers.contains(a))
+            {
+                ew_play.send(PlayCameraPath);
+            }
+        }
+    }
+}
+
+/// begin camera path playback, switching from the player camera to the cutscene camera
+#[allow(clippy::type_complexity)]
+pub(crate) fn start_camera_path(
+    mut commands: Commands,
+    mut er_play: EventReader<PlayCameraPath>,
+    path: Res<CameraPath>,
+    playback: Option<Res<CameraPathPlayback>>,
+    mut locked: ResMut<MouseLocked>,
+    mut player_cameras: Query<(Entity, &mut Camera), (With<PlayerCamera>, Without<CutsceneCamera>)>,
+    mut cutscene_camera: Query<&mut Camera, With<CutsceneCamera>>,
+) {
+    if er_play.read().count() == 0 || playback.is_some() {
+        return;
+    }
+
+    let Some((positions, targets)) = path.curves() else {
+        return;
+    };
+    let Ok(mut cutscene_camera) = cutscene_camera.get_single_mut() else {
+        return;
+    };
+
+    let player_cameras = player_cameras
+        .iter_mut()
+        .map(|(entity, mut camera)| {
+            let active = camera.is_active;
+            camera.is_active = false;
+            (entity, active)
+        })
+        .collect();
+    commands.insert_resource(CameraPathPlayback {
+        elapsed: 0.0,
+        positions,
+        targets,
+        locked: locked.0,
+        player_cameras,
+    });
+    cutscene_camera.is_active = true;
+    locked.0 = false;
+}
+
+/// fly the cutscene camera along the path, then hand control back to the player, with the
+/// cameras and mouse lock as they were before it. ends early if the cutscene camera is gone
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn play_camera_path(
+    mut commands: Commands,
+    time: Res<Time>,
+    up: Res<WorldUp>,
+    path: Res<CameraPath>,
+    mut playback: ResMut<CameraPathPlayback>,
+    mut locked: ResMut<MouseLocked>,
+    mut player_cameras: Query<&mut Camera, (With<PlayerCamera>, Without<CutsceneCamera>)>,
+    mut cutscene_camera: Query<(&mut Camera, &mut Transform), With<CutsceneCamera>>,
+) {
+    if let Ok((mut camera, mut transform)) = cutscene_camera.get_single_mut() {
+        playback.elapsed += time.delta_seconds();
+        let t = (playback.elapsed / path.duration).min(1.0)
+            * playback.positions.segments().len() as f32;
+        *transform = Transform::from_translation(playback.positions.position(t))
+            .looking_at(playback.targets.position(t), *up.up);
+
+        if playback.elapsed < path.duration {
+            return;
+        }
+        camera.is_active = false;
+    }
+
+    commands.remove_resource::<CameraPathPlayback>();
+    for &(entity, active) in &playback.player_cameras {
+        if let Ok(mut camera) = player_cameras.get_mut(entity) {
+            camera.is_active = active;
+        }
+    }
+    locked.0 = playback.locked;
+}
+
+/// listen for Action::ToggleCameraPathPreview to toggle the camera path preview
+pub(crate) fn toggle_camera_path_debug(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut debug: ResMut<CameraPathDebug>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ToggleCameraPathPreview) {
+        debug.0 = !debug.0;
+    }
+}
+
+/// preview the camera path with gizmos
+pub(crate) fn draw_camera_path(mut gizmos: Gizmos, path: Res<CameraPath>) {
+    let Some((positions, _)) = path.curves() else {
+        return;
+    };
+
+    gizmos.linestrip(positions.iter_positions(64), YELLOW);
+    for keyframe in &path.keyframes {
+        gizmos.sphere(keyframe.position, Quat::IDENTITY, 0.2, ORANGE);
+        gizmos.line(keyframe.position, keyframe.look_at, ORANGE.with_alpha(0.3));
+    }
+}
+
+#[cfg(test)]
+mod playback_tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    /// the camera path systems alone, with one active and one inactive player camera, and the
+    /// mouse unlocked
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / 60.0,
+            )))
+            .init_resource::<CameraPath>()
+            .init_resource::<WorldUp>()
+            .insert_resource(MouseLocked(false))
+            .add_event::<PlayCameraPath>()
+            .add_systems(
+                Update,
+                (
+                    start_camera_path,
+                    play_camera_path.run_if(resource_exists::<CameraPathPlayback>),
+                )
+                    .chain(),
+            );
+        for is_active in [true, false] {
+            app.world_mut().spawn((
+                Camera {
+                    is_active,
+                    ..default()
+                },
+                PlayerCamera(0.0),
+            ));
+        }
+        app
+    }
+
+    fn play(app: &mut App) {
+        app.world_mut().send_event(PlayCameraPath);
+        app.update();
+    }
+
+    fn player_cameras(app: &mut App) -> Vec<bool> {
+        app.world_mut()
+            .query_filtered::<&Camera, With<PlayerCamera>>()
+            .iter(app.world())
+            .map(|camera| camera.is_active)
+            .collect()
+    }
+
+    #[test]
+    fn skips_playback_without_a_cutscene_camera() {
+        let mut app = app();
+        play(&mut app);
+        assert!(!app.world().contains_resource::<CameraPathPlayback>());
+        assert_eq!(player_cameras(&mut app), [true, false]);
+    }
+
+    #[test]
+    fn restores_the_cameras_and_mouse_lock_afterwards() {
+        let mut app = app();
+        app.world_mut()
+            .spawn((Camera::default(), Transform::default(), CutsceneCamera));
+        play(&mut app);
+        assert!(app.world().contains_resource::<CameraPathPlayback>());
+        assert_eq!(player_cameras(&mut app), [false, false]);
+
+        let frames = (CameraPath::default().duration * 60.0) as usize + 10;
+        for _ in 0..frames {
+            app.update();
+        }
+        assert!(!app.world().contains_resource::<CameraPathPlayback>());
+        assert_eq!(player_cameras(&mut app), [true, false]);
+        assert!(!app.world().resource::<MouseLocked>().0);
+    }
+
+    #[test]
+    fn ends_early_if_the_cutscene_camera_goes() {
+        let mut app = app();
+        let cutscene = app
+            .world_mut()
+            .spawn((Camera::default(), Transform::default(), CutsceneCamera))
+            .id();
+        app.world_mut().resource_mut::<MouseLocked>().0 = true;
+        play(&mut app);
+        app.world_mut().despawn(cutscene);
+        app.update();
+        assert!(!app.world().contains_resource::<CameraPathPlayback>());
+        assert_eq!(player_cameras(&mut app), [true, false]);
+        assert!(app.world().resource::<MouseLocked>().0);
+    }
+}
@@ -0,0 +1,152 @@
+//! checkpoints: trigger volumes that move where players respawn, after dying or falling past
+//! RespawnConfig's kill height, to the last one reached
+
+use bevy::prelude::*;
+
+use crate::{trigger::update_trigger_volumes, Player, TriggerEnter, WorldUp};
+
+/// a trigger volume players respawn at once they've touched it. spawn it with a Collider, a
+/// Sensor and a TriggerVolume. if it has a StandardMaterial, that changes to
+/// CheckpointConfig's `reached_color` the first time it's reached
+#[derive(Component, Clone, Default, Debug)]
+pub struct Checkpoint {
+    /// where players respawn, relative to the checkpoint: the point they stand on and the way
+    /// they face. by default they stand on its origin
+    pub respawn: Transform,
+    reached: bool,
+}
+
+impl Checkpoint {
+    pub fn new(respawn: Transform) -> Self {
+        Self {
+            respawn,
+            reached: false,
+        }
+    }
+
+    /// whether a player has touched it yet
+    pub fn reached(&self) -> bool {
+        self.reached
+    }
+}
+
+/// how checkpoints are taken
+#[derive(Resource, Clone, Debug)]
+pub struct CheckpointConfig {
+    /// let touching an earlier checkpoint again take over from a later one. without it, only a
+    /// checkpoint not reached before moves the respawn point
+    pub latest_touched_wins: bool,
+    /// what a checkpoint's material turns once it's been reached
+    pub reached_color: Color,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            latest_touched_wins: false,
+            reached_color: Color::srgb(0.2, 0.9, 0.4),
+        }
+    }
+}
+
+/// the checkpoint players respawn at, if one's been reached, or None to respawn where they
+/// first spawned
+#[derive(Resource, Clone, Copy, Default, PartialEq, Debug)]
+pub struct CurrentCheckpoint(pub Option<ReachedCheckpoint>);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ReachedCheckpoint {
+    pub checkpoint: Entity,
+    /// the checkpoint's respawn transform in world space, as it was when reached
+    pub respawn: Transform,
+}
+
+impl CurrentCheckpoint {
+    /// put a respawning player at the checkpoint, floating over it and facing its way, or at
+    /// `spawn` before one's been reached
+    pub(crate) fn place(
+        &self,
+        transform: &mut Transform,
+        spawn: Vec3,
+        float_height: f32,
+        up: &WorldUp,
+    ) {
+        let Some(reached) = self.0 else {
+            transform.translation = spawn;
+            return;
+        };
+        transform.translation = reached.respawn.translation + *up.up * float_height;
+        transform.rotation = up.rotation(up.yaw(&reached.respawn));
+    }
+}
+
+/// event sent when a player's touch makes a checkpoint the current one, e.g. for a sound or a
+/// message
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CheckpointReached {
+    pub checkpoint: Entity,
+    pub player: Entity,
+}
+
+/// checkpoints, for the respawns to use. needs TriggerPlugin
+#[derive(Clone, Default)]
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CheckpointConfig>()
+            .init_resource::<CurrentCheckpoint>()
+            .add_event::<CheckpointReached>()
+            .add_systems(FixedUpdate, reach_checkpoints.after(update_trigger_volumes));
+    }
+}
+
+/// make the checkpoints players walk into current, colouring them in the first time
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn reach_checkpoints(
+    mut commands: Commands,
+    config: Res<CheckpointConfig>,
+    mut current: ResMut<CurrentCheckpoint>,
+    mut er_enter: EventReader<TriggerEnter>,
+    mut ew_reached: EventWriter<CheckpointReached>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut checkpoints: Query<(
+        &mut Checkpoint,
+        &GlobalTransform,
+        Option<&Handle<StandardMaterial>>,
+    )>,
+    players: Query<(), With<Player>>,
+) {
+    for ev in er_enter.read() {
+        if !players.contains(ev.other) {
+            continue;
+        }
+        let Ok((mut checkpoint, transform, material)) = checkpoints.get_mut(ev.volume) else {
+            continue;
+        };
+        let is_current = current.0.is_some_and(|c| c.checkpoint == ev.volume);
+        if is_current || checkpoint.reached && !config.latest_touched_wins {
+            continue;
+        }
+
+        if !checkpoint.reached {
+            checkpoint.reached = true;
+            // a material of its own, in case the one it has is shared
+            if let (Some(materials), Some(material)) = (materials.as_mut(), material) {
+                let mut reached = materials.get(material).cloned().unwrap_or_default();
+                reached.base_color = config.reached_color.with_alpha(reached.base_color.alpha());
+                commands.entity(ev.volume).insert(materials.add(reached));
+            }
+        }
+        current.0 = Some(ReachedCheckpoint {
+            checkpoint: ev.volume,
+            respawn: transform
+                .mul_transform(checkpoint.respawn)
+                .compute_transform(),
+        });
+        ew_reached.send(CheckpointReached {
+            checkpoint: ev.volume,
+            player: ev.other,
+        });
+    }
+}
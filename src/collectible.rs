@@ -0,0 +1,188 @@
+//! collectibles: spinning pickups that add to the Score when a player touches them, put back
+//! on a ResetLevel
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier3d::prelude::*;
+
+use crate::{spawn::reset_scene, Player, SceneObject};
+
+/// a pickup that despawns when a player touches it, adding its value to the Score. see
+/// spawn_collectible
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Collectible {
+    pub value: u32,
+}
+
+impl Default for Collectible {
+    fn default() -> Self {
+        Self { value: 1 }
+    }
+}
+
+/// what the players have collected so far
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Score(pub u32);
+
+/// event sent when a player picks up a collectible, just before it's despawned
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CollectedEvent {
+    pub collectible: Entity,
+    pub player: Entity,
+    pub value: u32,
+}
+
+/// event to start the level over: every collected item is put back and the Score goes to zero,
+/// and the SceneObjects are spawned afresh
+#[derive(Event, Clone, Copy, Default, Debug)]
+pub struct ResetLevel;
+
+/// radius of a collectible's sensor, generous so even a fast player passes through it within
+/// a physics tick
+const COLLECTIBLE_RADIUS: f32 = 0.4;
+
+/// radians per second collectibles spin at
+const COLLECTIBLE_SPIN: f32 = 2.0;
+
+/// spawn a coin worth 1 at `position`, with a sensor players pick it up through. its mesh is
+/// added once it's spawned
+pub fn spawn_collectible(commands: &mut Commands, position: Vec3) -> Entity {
+    spawn(
+        commands,
+        Transform::from_translation(position),
+        Collectible::default(),
+    )
+}
+
+fn spawn(commands: &mut Commands, transform: Transform, collectible: Collectible) -> Entity {
+    commands
+        .spawn(TransformBundle::from_transform(transform))
+        .insert(VisibilityBundle::default())
+        .insert(collectible)
+        .insert(Collider::ball(COLLECTIBLE_RADIUS))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .id()
+}
+
+/// collectibles picked up since the last reset, as they were spawned
+#[derive(Resource, Default)]
+pub(crate) struct CollectedItems(Vec<(Transform, Collectible)>);
+
+/// collectibles and the Score they add to
+#[derive(Clone, Default)]
+pub struct CollectiblePlugin;
+
+impl Plugin for CollectiblePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<CollectedItems>()
+            .add_event::<CollectedEvent>()
+            .add_systems(
+                Update,
+                (
+                    dress_collectibles,
+                    spin_collectibles,
+                    collect_collectibles,
+                    reset_collectibles,
+                )
+                    .chain()
+                    .before(reset_scene),
+            );
+    }
+}
+
+/// give new collectibles the coin mesh, shared between them all
+pub(crate) fn dress_collectibles(
+    mut commands: Commands,
+    mut coin: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    added: Query<Entity, (Added<Collectible>, Without<Handle<Mesh>>)>,
+) {
+    for entity in &added {
+        let (mesh, material) = coin.get_or_insert_with(|| {
+            (
+                meshes.add(
+                    Cylinder::new(COLLECTIBLE_RADIUS * 0.75, 0.08)
+                        .mesh()
+                        .build()
+                        .rotated_by(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+                ),
+                materials.add(StandardMaterial {
+                    base_color: Color::srgb(1.0, 0.8, 0.2),
+                    metallic: 0.8,
+                    perceptual_roughness: 0.3,
+                    ..default()
+                }),
+            )
+        });
+        commands
+            .entity(entity)
+            .insert(mesh.clone())
+            .insert(material.clone());
+    }
+}
+
+/// turn collectibles round on the spot
+pub(crate) fn spin_collectibles(
+    time: Res<Time>,
+    mut collectibles: Query<&mut Transform, With<Collectible>>,
+) {
+    for mut transform in &mut collectibles {
+        transform.rotate_y(COLLECTIBLE_SPIN * time.delta_seconds());
+    }
+}
+
+/// pick up the collectibles players touch. this goes by rapier's sensor events so nothing's
+/// missed between frames, and each one counts once even if two players touch it together
+pub(crate) fn collect_collectibles(
+    mut commands: Commands,
+    mut er_collision: EventReader<CollisionEvent>,
+    mut score: ResMut<Score>,
+    mut collected: ResMut<CollectedItems>,
+    mut ew_collected: EventWriter<CollectedEvent>,
+    collectibles: Query<(&Collectible, &Transform, Has<SceneObject>)>,
+    players: Query<(), With<Player>>,
+) {
+    let mut taken = HashSet::new();
+    for ev in er_collision.read() {
+        let CollisionEvent::Started(a, b, _) = *ev else {
+            continue;
+        };
+        for (player, entity) in [(a, b), (b, a)] {
+            let Ok((&collectible, &transform, in_scene)) = collectibles.get(entity) else {
+                continue;
+            };
+            if !players.contains(player) || !taken.insert(entity) {
+                continue;
+            }
+            score.0 += collectible.value;
+            // the scene puts its own back
+            if !in_scene {
+                collected.0.push((transform, collectible));
+            }
+            ew_collected.send(CollectedEvent {
+                collectible: entity,
+                player,
+                value: collectible.value,
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// put collected items back and zero the Score on a ResetLevel
+pub(crate) fn reset_collectibles(
+    mut commands: Commands,
+    mut er_reset: EventReader<ResetLevel>,
+    mut score: ResMut<Score>,
+    mut collected: ResMut<CollectedItems>,
+) {
+    if er_reset.read().count() == 0 {
+        return;
+    }
+    for (transform, collectible) in collected.0.drain(..) {
+        spawn(&mut commands, transform, collectible);
+    }
+    score.0 = 0;
+}
@@ -0,0 +1,136 @@
+//! the crosshair: a dot or cross at the centre of the screen while the mouse is locked
+
+use bevy::prelude::*;
+
+use crate::{photo_mode::PhotoMode, MouseLocked};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CrosshairShape {
+    Dot,
+    /// four arms around a gap
+    Cross,
+}
+
+/// how the crosshair looks. it's rebuilt whenever this changes
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct CrosshairSettings {
+    pub enabled: bool,
+    pub shape: CrosshairShape,
+    /// the dot's diameter, or each arm's length, in pixels
+    pub size: f32,
+    /// the arms' thickness, in pixels
+    pub thickness: f32,
+    /// space between the centre and each arm, in pixels
+    pub gap: f32,
+    pub color: Color,
+}
+
+impl Default for CrosshairSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shape: CrosshairShape::Cross,
+            size: 8.0,
+            thickness: 2.0,
+            gap: 4.0,
+            color: Color::WHITE.with_alpha(0.8),
+        }
+    }
+}
+
+/// the crosshair's root node, a point at the centre of the screen with its parts laid out around
+/// it. hud that follows the crosshair, like prompts and hit markers, can be spawned as its
+/// children, absolutely positioned from the centre
+#[derive(Component)]
+pub struct Crosshair;
+
+/// a dot or arm of the crosshair, respawned with the settings
+#[derive(Component)]
+pub(crate) struct CrosshairPart;
+
+/// spawn the crosshair's root, hidden until the mouse is locked
+pub(crate) fn setup_crosshair(mut commands: Commands) {
+    // positioned in percent, so it stays centred as the window resizes
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                top: Val::Percent(50.0),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(Crosshair);
+}
+
+/// respawn the crosshair's parts in the current CrosshairSettings
+pub(crate) fn build_crosshair(
+    mut commands: Commands,
+    settings: Res<CrosshairSettings>,
+    root: Query<Entity, With<Crosshair>>,
+    parts: Query<Entity, With<CrosshairPart>>,
+) {
+    let Ok(root) = root.get_single() else {
+        return;
+    };
+    if !settings.is_changed() && !parts.is_empty() {
+        return;
+    }
+    for part in &parts {
+        commands.entity(part).despawn_recursive();
+    }
+
+    let (size, thickness, gap) = (settings.size, settings.thickness, settings.gap);
+    // left, top, width and height of each part, from the centre
+    let rects = match settings.shape {
+        CrosshairShape::Dot => vec![(-size * 0.5, -size * 0.5, size, size)],
+        CrosshairShape::Cross => vec![
+            (gap, -thickness * 0.5, size, thickness),
+            (-gap - size, -thickness * 0.5, size, thickness),
+            (-thickness * 0.5, gap, thickness, size),
+            (-thickness * 0.5, -gap - size, thickness, size),
+        ],
+    };
+    commands.entity(root).with_children(|parent| {
+        for (left, top, width, height) in rects {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(left),
+                        top: Val::Px(top),
+                        width: Val::Px(width),
+                        height: Val::Px(height),
+                        ..default()
+                    },
+                    background_color: settings.color.into(),
+                    border_radius: match settings.shape {
+                        CrosshairShape::Dot => BorderRadius::MAX,
+                        CrosshairShape::Cross => BorderRadius::ZERO,
+                    },
+                    ..default()
+                })
+                .insert(CrosshairPart);
+        }
+    });
+}
+
+/// show the crosshair only while the mouse is locked, as the cursor's shown otherwise, and out
+/// of photo mode
+pub(crate) fn show_crosshair(
+    locked: Res<MouseLocked>,
+    settings: Res<CrosshairSettings>,
+    photo_mode: Option<Res<PhotoMode>>,
+    mut root: Query<&mut Visibility, With<Crosshair>>,
+) {
+    let shown = if locked.0 && settings.enabled && photo_mode.is_none() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut visibility in &mut root {
+        visibility.set_if_neq(shown);
+    }
+}
@@ -0,0 +1,148 @@
+//! the debug overlay: the keyboard and mouse player's tnua controller state, printed every frame,
+//! for working out why movement feels off
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    prelude::{TnuaBuiltinWalk, TnuaController},
+    TnuaProximitySensor,
+};
+
+use crate::{
+    input::{Action, KeyBindings, Keys},
+    AirTime, Grounded, KeyboardMouseInput, Player, PlayerMovementState, WorldUp,
+};
+
+/// whether the debug overlay is shown. toggled with Action::ToggleDebugOverlay
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DebugOverlay(pub bool);
+
+/// the overlay's text, in the top-right corner
+#[derive(Component)]
+pub(crate) struct DebugOverlayText;
+
+pub(crate) fn setup_debug_overlay(mut commands: Commands) {
+    commands
+        .spawn(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(16.0),
+                top: Val::Px(16.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::BLACK.with_alpha(0.6)),
+        )
+        .insert(Visibility::Hidden)
+        .insert(DebugOverlayText);
+}
+
+/// listen for Action::ToggleDebugOverlay to show or hide the debug overlay
+pub(crate) fn toggle_debug_overlay(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ToggleDebugOverlay) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+/// print the keyboard and mouse player's controller state into the overlay while it's shown
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_debug_overlay(
+    overlay: Res<DebugOverlay>,
+    up: Res<WorldUp>,
+    player: Query<
+        (
+            Entity,
+            &TnuaController,
+            &TnuaProximitySensor,
+            &Velocity,
+            &Grounded,
+            &PlayerMovementState,
+            &AirTime,
+        ),
+        (With<Player>, With<KeyboardMouseInput>),
+    >,
+    mut text: Query<(&mut Text, &mut Visibility), With<DebugOverlayText>>,
+) {
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else {
+        return;
+    };
+    visibility.set_if_neq(if overlay.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if !overlay.0 {
+        return;
+    }
+
+    let Ok((entity, controller, sensor, velocity, grounded, state, air_time)) = player.get_single()
+    else {
+        text.sections[0].value = "no player".to_string();
+        return;
+    };
+
+    let airborne = match controller.is_airborne() {
+        Ok(airborne) => airborne.to_string(),
+        Err(_) => "no basis".to_string(),
+    };
+    let mut lines = vec![
+        format!("player {entity}"),
+        format!(
+            "grounded {} (tnua airborne {airborne}), {state:?}",
+            grounded.is_grounded()
+        ),
+        format!("air time {:.2}s", air_time.0),
+        format!("action {}", controller.action_name().unwrap_or("none")),
+        format!(
+            "velocity {} ({:.2} across)",
+            vector(velocity.linvel),
+            up.horizontal(velocity.linvel).length()
+        ),
+    ];
+    match controller.concrete_basis::<TnuaBuiltinWalk>() {
+        Some((walk, _)) => {
+            let desired = walk.desired_velocity;
+            lines.push(format!(
+                "desired {} ({:.2} across)",
+                vector(desired),
+                up.horizontal(desired).length()
+            ));
+            // how far the sensor puts the centre from where the float spring wants it
+            let error = sensor
+                .output
+                .as_ref()
+                .map_or("no ground".to_string(), |output| {
+                    format!("{:+.3}", output.proximity - walk.float_height)
+                });
+            lines.push(format!(
+                "float height {:.2}, error {error}",
+                walk.float_height
+            ));
+        }
+        None => lines.push(format!(
+            "basis {}",
+            controller.basis_name().unwrap_or("none")
+        )),
+    }
+    lines.push(match &sensor.output {
+        Some(output) => format!("ground {}", output.entity),
+        None => "ground none".to_string(),
+    });
+
+    text.sections[0].value = lines.join("\n");
+}
+
+fn vector(v: Vec3) -> String {
+    format!("[{:.2}, {:.2}, {:.2}]", v.x, v.y, v.z)
+}
@@ -0,0 +1,659 @@
+//! the demo level: the ground, a sun, and a prop or volume for each feature to try out on
+
+use std::f32::consts::PI;
+
+use bevy::{
+    color::palettes::css::{LIME, ORANGE, RED},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    camera::FadeWhenOccluding,
+    camera_path::CameraPathTrigger,
+    crate_body,
+    health::{DamageVolume, Hazard},
+    menu::menu_closed,
+    scatter, scene_file,
+    spawn::{reset_level_key, reset_scene, setup_player},
+    spawn_collectible, spawn_point, time_of_day,
+    time_scale::toggle_bullet_time,
+    Breakable, CallElevator, Checkpoint, Elevator, GroundMaterial, Heightmap, JumpPad,
+    MovingPlatform, PlatformPath, PressurePlate, ResetLevel, RotatingPlatform, ScatterConfig,
+    ScatterRng, SlidingDoor, SpawnPoint, SpawnedTerrain, TerrainConfig, TimeOfDay, TriggerEnter,
+    TriggerExit, TriggerVolume, UseButton, Waypoint, WindZone, HEAVY_CRATE_MASS, LIGHT_CRATE_MASS,
+    MEDIUM_CRATE_MASS,
+};
+
+/// marks what the scene setup spawned. on a ResetLevel these are despawned and the scene
+/// spawned afresh, so tag a level's own entities too and respawn them on ResetLevel the same way
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct SceneObject;
+
+/// what DemoScenePlugin spawns
+#[derive(Resource, Clone, Debug)]
+pub struct DemoSceneConfig {
+    /// half the width and depth of the ground
+    pub ground_size: Vec2,
+    /// spawn the pads, hazards, slab and wall as well as the ground and light
+    pub props: bool,
+    /// a `.scene.ron` under `assets/` to spawn instead of all of the above (see
+    /// `assets/demo.scene.ron` for the format)
+    pub file: Option<String>,
+}
+
+impl Default for DemoSceneConfig {
+    fn default() -> Self {
+        Self {
+            ground_size: Vec2::splat(10.0),
+            props: true,
+            file: None,
+        }
+    }
+}
+
+/// the demo level: a ground plane (or terrain, see TerrainConfig), a sun on a day-night cycle
+/// (see TimeOfDay), and something for each feature to try out on. needs MechanicsPlugins
+#[derive(Clone, Default)]
+pub struct DemoScenePlugin;
+
+impl Plugin for DemoScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DemoSceneConfig>()
+            .init_resource::<TerrainConfig>()
+            .init_resource::<ScatterConfig>()
+            .init_resource::<ScatterRng>()
+            .init_resource::<TimeOfDay>()
+            .init_resource::<AmbientLight>()
+            .init_asset::<scene_file::SceneFile>()
+            .register_asset_loader(scene_file::SceneFileLoader)
+            .add_systems(Startup, (setup_scene, time_of_day::setup_sun))
+            .add_systems(
+                Update,
+                // before setup_player, so the players respawn at the new spawn points
+                setup_scene
+                    .run_if(on_event::<ResetLevel>())
+                    .after(reset_scene)
+                    .before(setup_player),
+            )
+            .add_systems(
+                Update,
+                (
+                    time_of_day::advance_time_of_day.run_if(menu_closed),
+                    time_of_day::apply_time_of_day,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (toggle_bullet_time, reset_level_key).run_if(menu_closed),
+            )
+            .add_systems(
+                Update,
+                (
+                    scene_file::spawn_scene_file
+                        .run_if(resource_exists::<scene_file::LoadedSceneFile>)
+                        .after(setup_player)
+                        .before(spawn_point::follow_spawn_selection),
+                    log_demo_triggers,
+                ),
+            );
+    }
+}
+
+/// setup scene: a simple plane, or terrain without the props, which expect flat ground. obstacles
+/// scattered over the plane can stand in for the props (see ScatterConfig), and a scene file
+/// replaces all of it. everything's a SceneObject, spawned afresh on a ResetLevel
+#[allow(clippy::too_many_arguments)]
+fn setup_scene(
+    mut commands: Commands,
+    config: Res<DemoSceneConfig>,
+    terrain: Res<TerrainConfig>,
+    scatter: Res<ScatterConfig>,
+    mut rng: ResMut<ScatterRng>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // spawned by spawn_scene_file once it's loaded
+    if let Some(path) = &config.file {
+        let handle = asset_server.load(path.clone());
+        commands.insert_resource(scene_file::LoadedSceneFile(handle));
+        return;
+    }
+
+    if terrain.enabled {
+        commands.insert_resource(SpawnedTerrain(terrain.clone()));
+        let heightmap = Heightmap::generate(&terrain);
+        commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(heightmap.mesh()),
+                material: materials.add(StandardMaterial::from_color(Color::srgb(0.4, 0.6, 0.3))),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(RigidBody::Fixed)
+            .insert(heightmap.collider());
+    } else {
+        let ground = config.ground_size;
+        commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(Plane3d::new(Vec3::Y, ground)),
+                material: materials.add(StandardMaterial::default()),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(ground.x, 0.1, ground.y));
+    }
+
+    // where players start, in the middle
+    commands
+        .spawn(TransformBundle::default())
+        .insert(SceneObject)
+        .insert(SpawnPoint::named("start"));
+
+    if terrain.enabled {
+        return;
+    }
+    if scatter.count > 0 {
+        scatter::spawn_obstacles(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &scatter,
+            &mut rng,
+            config.ground_size,
+        );
+        return;
+    }
+    if !config.props {
+        return;
+    }
+
+    // a pad that plays the camera path when stepped on
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(2.0, 0.1, 2.0)),
+            material: materials.add(StandardMaterial::from_color(ORANGE)),
+            transform: Transform::from_xyz(6.0, 0.05, 6.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cuboid(1.0, 0.5, 1.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(CameraPathTrigger);
+
+    // a hazard that hurts the player on contact
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(2.0, 0.1, 2.0)),
+            material: materials.add(StandardMaterial::from_color(RED)),
+            transform: Transform::from_xyz(-6.0, 0.05, 6.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cuboid(1.0, 0.5, 1.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(Hazard { damage: 25.0 });
+
+    // a lava pool that hurts the player for as long as they stand in it
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(3.0, 0.1, 3.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgb(1.0, 0.3, 0.0),
+                emissive: LinearRgba::rgb(4.0, 0.8, 0.0),
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.05, -6.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cuboid(1.5, 0.5, 1.5))
+        .insert(Sensor)
+        .insert(DamageVolume { dps: 20.0 });
+
+    // a low slab to crouch under
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(3.0, 0.5, 3.0)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.5, 0.4, 0.3))),
+            transform: Transform::from_xyz(6.0, 2.15, 6.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(1.5, 0.25, 1.5));
+
+    // a wall that fades out when it blocks a third-person camera
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(4.0, 3.0, 0.5)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.6, 0.6, 0.65))),
+            transform: Transform::from_xyz(0.0, 1.5, 4.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(2.0, 1.5, 0.25))
+        .insert(FadeWhenOccluding);
+
+    // a jump pad that bounces the player straight up
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cylinder::new(1.0, 0.1)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.1, 0.7, 1.0))),
+            transform: Transform::from_xyz(-6.0, 0.05, -6.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cylinder(0.5, 1.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(JumpPad {
+            launch_speed: 15.0,
+            direction: Vec3::Y,
+            preserve_horizontal: true,
+        });
+
+    // a spinning disc that carries the player around, turning them with it, fastest at the rim
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cylinder::new(2.5, 0.3)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.6, 0.3, 0.8))),
+            transform: Transform::from_xyz(6.0, 0.15, -3.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cylinder(0.15, 2.5))
+        .insert(RotatingPlatform::new(Dir3::Y, 0.6));
+
+    // a platform ferrying the player across the gap between two ledges, waiting at each end
+    let ledge_mesh = meshes.add(Cuboid::new(2.0, 1.5, 2.0));
+    let ledge_material = materials.add(StandardMaterial::from_color(Color::srgb(0.5, 0.5, 0.55)));
+    for z in [2.0, 8.0] {
+        commands
+            .spawn(PbrBundle {
+                mesh: ledge_mesh.clone(),
+                material: ledge_material.clone(),
+                transform: Transform::from_xyz(8.5, 0.75, z),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(1.0, 0.75, 1.0));
+    }
+    // a checkpoint on the far ledge, turning green once reached
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cylinder::new(0.8, 1.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.9, 0.8, 0.2, 0.3),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            transform: Transform::from_xyz(8.5, 2.0, 8.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cylinder(0.5, 0.8))
+        .insert(Sensor)
+        .insert(TriggerVolume::tagged("checkpoint"))
+        // standing on the ledge, facing back over the gap
+        .insert(Checkpoint::new(Transform::from_xyz(0.0, -0.5, 0.0)));
+    // and a spawn point on the near one, facing the ferry
+    commands
+        .spawn(TransformBundle::from_transform(
+            Transform::from_xyz(8.5, 1.5, 2.0).with_rotation(Quat::from_rotation_y(PI)),
+        ))
+        .insert(SceneObject)
+        .insert(SpawnPoint::named("ferry").with_index(1));
+    let platform_mesh = meshes.add(Cuboid::new(2.0, 0.3, 2.0));
+    let platform_material = materials.add(StandardMaterial::from_color(Color::srgb(0.2, 0.5, 0.5)));
+    let ferry = [Vec3::new(8.5, 1.35, 4.0), Vec3::new(8.5, 1.35, 6.0)];
+    commands
+        .spawn(PbrBundle {
+            mesh: platform_mesh.clone(),
+            material: platform_material.clone(),
+            transform: Transform::from_translation(ferry[0]),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cuboid(1.0, 0.15, 1.0))
+        .insert(MovingPlatform::new(
+            PlatformPath::PingPong,
+            1.5,
+            ferry.map(|point| Waypoint::new(point).with_dwell(1.0)),
+        ));
+
+    // an elevator up to a landing, called from a button on either floor or taken up and down
+    // with the two on board
+    let elevator = commands
+        .spawn(PbrBundle {
+            mesh: platform_mesh,
+            material: platform_material,
+            transform: Transform::from_xyz(8.5, 0.15, -7.5),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cuboid(1.0, 0.15, 1.0))
+        .insert(Elevator::new(2.0, 2.5, [0.15, 4.15]).with_dwell(1.5))
+        .id();
+    let button_mesh = meshes.add(Cuboid::new(0.2, 0.2, 0.2));
+    let up_material = materials.add(StandardMaterial::from_color(LIME));
+    let down_material = materials.add(StandardMaterial::from_color(RED));
+    for (floor, y, material) in [(1, 1.0, &up_material), (0, 0.7, &down_material)] {
+        let button = commands
+            .spawn(PbrBundle {
+                mesh: button_mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.85, y, 0.85),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(Collider::cuboid(0.1, 0.1, 0.1))
+            .insert(UseButton::new([elevator]))
+            .insert(CallElevator(floor))
+            .id();
+        commands.entity(elevator).add_child(button);
+    }
+    for (floor, position) in [
+        (0, Vec3::new(7.2, 1.2, -6.2)),
+        (1, Vec3::new(7.2, 5.2, -9.0)),
+    ] {
+        commands
+            .spawn(PbrBundle {
+                mesh: button_mesh.clone(),
+                material: up_material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(Collider::cuboid(0.1, 0.1, 0.1))
+            .insert(UseButton::new([elevator]))
+            .insert(CallElevator(floor));
+    }
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(2.0, 0.3, 1.5)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.4, 0.5, 0.4))),
+            transform: Transform::from_xyz(8.5, 4.15, -9.25),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(1.0, 0.15, 0.75));
+
+    // a pad launching the player at a thin pane, too fast not to pass through it without ccd
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cylinder::new(1.0, 0.1)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(1.0, 0.8, 0.1))),
+            transform: Transform::from_xyz(-8.5, 0.05, 3.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cylinder(0.5, 1.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(JumpPad {
+            launch_speed: 80.0,
+            direction: Vec3::new(0.0, 0.1, 1.0),
+            preserve_horizontal: false,
+        });
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(4.0, 3.0, 0.05)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.6, 0.8, 1.0, 0.4),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            transform: Transform::from_xyz(-8.5, 1.5, 9.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(2.0, 1.5, 0.025));
+
+    // an overhang just low enough to bonk your head on when jumping
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(3.0, 0.3, 3.0)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.4, 0.5, 0.4))),
+            transform: Transform::from_xyz(3.0, 4.15, 1.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(1.5, 0.15, 1.5));
+
+    // a trigger volume that logs players going in and out, see log_demo_triggers
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(2.0, 2.0, 2.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.3, 1.0, 0.4, 0.2),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            transform: Transform::from_xyz(-8.5, 1.0, -3.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cuboid(1.0, 1.0, 1.0))
+        .insert(Sensor)
+        .insert(TriggerVolume::tagged("demo"));
+
+    // a doorway in a wall, with a sliding door opened by a button beside it or a pressure plate
+    // behind it
+    let wall_material = materials.add(StandardMaterial::from_color(Color::srgb(0.55, 0.5, 0.45)));
+    let pillar_mesh = meshes.add(Cuboid::new(1.0, 2.5, 0.3));
+    for x in [-4.5, -1.5] {
+        commands
+            .spawn(PbrBundle {
+                mesh: pillar_mesh.clone(),
+                material: wall_material.clone(),
+                transform: Transform::from_xyz(x, 1.25, -8.5),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(0.5, 1.25, 0.15));
+    }
+    // it rises to open, and won't come down on anyone underneath
+    let door = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(2.0, 2.5, 0.2)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.45, 0.3, 0.2))),
+            transform: Transform::from_xyz(-3.0, 1.25, -8.5),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cuboid(1.0, 1.25, 0.1))
+        .insert(SlidingDoor::new(Vec3::Y * 2.4, 1.0))
+        .id();
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(0.3, 0.3, 0.1)),
+            material: materials.add(StandardMaterial::from_color(RED)),
+            transform: Transform::from_xyz(-1.5, 1.2, -8.3),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cuboid(0.15, 0.15, 0.05))
+        .insert(UseButton::new([door]));
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.2, 0.1, 1.0)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.3, 0.3, 0.35))),
+            transform: Transform::from_xyz(-3.0, 0.05, -9.4),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(Collider::cuboid(0.6, 0.5, 0.5))
+        .insert(Sensor)
+        .insert(TriggerVolume::tagged("pressure plate"))
+        .insert(PressurePlate::new([door]));
+
+    // a tall wall along the far edge to wall run on: jump from beside it and hold towards it
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(12.0, 4.0, 0.3)),
+            material: wall_material.clone(),
+            transform: Transform::from_xyz(0.0, 2.0, 10.15),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(6.0, 2.0, 0.15));
+
+    // coins: a row across the ice, one over the ferry's gap and one high over the jump pad
+    let row = [-4.0, -3.25, -2.5, -1.75, -1.0].map(|x| Vec3::new(x, 0.9, 8.0));
+    for position in row
+        .into_iter()
+        .chain([Vec3::new(8.5, 2.3, 5.0), Vec3::new(-6.0, 6.0, -6.0)])
+    {
+        let coin = spawn_collectible(&mut commands, position);
+        commands.entity(coin).insert(SceneObject);
+    }
+
+    // a sheet of ice to slide around on
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(4.0, 0.1, 3.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgb(0.75, 0.9, 1.0),
+                perceptual_roughness: 0.1,
+                ..default()
+            }),
+            transform: Transform::from_xyz(-2.5, 0.05, 8.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(2.0, 0.05, 1.5))
+        .insert(GroundMaterial::Ice);
+
+    // crates of each weight: a stack of light ones to push over, a medium one that slows the
+    // player down and a heavy one that won't budge
+    let crate_mesh = meshes.add(Cuboid::from_length(0.8));
+    let crate_material = materials.add(StandardMaterial::from_color(Color::srgb(0.7, 0.5, 0.3)));
+    for i in 0..3 {
+        commands
+            .spawn(PbrBundle {
+                mesh: crate_mesh.clone(),
+                material: crate_material.clone(),
+                transform: Transform::from_xyz(-4.0, 0.4 + 0.8 * i as f32, -2.0),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(crate_body(0.8, LIGHT_CRATE_MASS));
+    }
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::from_length(1.0)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.5, 0.35, 0.2))),
+            transform: Transform::from_xyz(-4.0, 0.5, 0.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(crate_body(1.0, MEDIUM_CRATE_MASS));
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::from_length(1.2)),
+            material: materials.add(StandardMaterial::from_color(Color::srgb(0.3, 0.3, 0.35))),
+            transform: Transform::from_xyz(-4.0, 0.6, 2.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(crate_body(1.2, HEAVY_CRATE_MASS));
+
+    // breakables: a crate that shatters under a slam, a long fall or a crate flung into it, and
+    // a pillar that breaks if it's knocked over
+    commands
+        .spawn(PbrBundle {
+            mesh: crate_mesh.clone(),
+            material: crate_material.clone(),
+            transform: Transform::from_xyz(-6.5, 0.4, -0.5),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(crate_body(0.8, LIGHT_CRATE_MASS))
+        .insert(Breakable::new(30.0));
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Cuboid::new(0.5, 2.0, 0.5)),
+            material: ledge_material.clone(),
+            transform: Transform::from_xyz(-6.5, 1.0, 1.0),
+            ..default()
+        })
+        .insert(SceneObject)
+        .insert(RigidBody::Dynamic)
+        .insert(Collider::cuboid(0.25, 1.0, 0.25))
+        .insert(ColliderMassProperties::Mass(10.0))
+        .insert(Breakable::new(20.0));
+
+    // a gusty breeze over the light crates, enough to shift the top of the stack now and then
+    commands
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            -4.0, 1.5, -2.0,
+        )))
+        .insert(SceneObject)
+        .insert(Collider::cuboid(1.5, 1.5, 1.5))
+        .insert(Sensor)
+        .insert(WindZone::new(Dir3::X, 3.0).with_turbulence(0.8));
+
+    // two ledges with a high wind between them: out of it standing on the near ledge, a jump
+    // straight up lifts the player into it and it carries them over to the far one
+    for z in [-1.6, -6.2] {
+        commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(Cuboid::new(1.6, 1.0, 1.2)),
+                material: ledge_material.clone(),
+                transform: Transform::from_xyz(2.5, 0.5, z),
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(0.8, 0.5, 0.6));
+    }
+    commands
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            2.5, 5.5, -3.9,
+        )))
+        .insert(SceneObject)
+        .insert(Collider::cuboid(0.8, 1.5, 2.9))
+        .insert(Sensor)
+        .insert(WindZone::new(Dir3::NEG_Z, 5.0));
+}
+
+/// log what goes in and out of the demo scene's tagged trigger volume
+fn log_demo_triggers(
+    mut er_enter: EventReader<TriggerEnter>,
+    mut er_exit: EventReader<TriggerExit>,
+    volumes: Query<&TriggerVolume>,
+) {
+    let is_demo = |volume| {
+        volumes
+            .get(volume)
+            .is_ok_and(|volume| volume.tag.as_deref() == Some("demo"))
+    };
+    for ev in er_enter.read().filter(|ev| is_demo(ev.volume)) {
+        info!("{:?} entered the demo trigger volume", ev.other);
+    }
+    for ev in er_exit.read().filter(|ev| is_demo(ev.volume)) {
+        info!("{:?} left the demo trigger volume", ev.other);
+    }
+}
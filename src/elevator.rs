@@ -0,0 +1,199 @@
+//! elevators: kinematic platforms that travel between floors when called, from a button on
+//! each floor or one on board
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{interact::activate_doors, Activate, Player};
+
+/// moves a RigidBody::KinematicPositionBased up and down between floor heights, picking up
+/// speed and slowing to a stop at each. calls while it's busy queue up and are answered in
+/// turn. like a MovingPlatform it needs a Velocity for players to ride it. one coming down on a
+/// player waits above them until they're out of the way
+#[derive(Component, Clone, Debug)]
+pub struct Elevator {
+    /// world heights its transform stops at
+    pub floors: Vec<f32>,
+    /// top speed, in units per second
+    pub speed: f32,
+    /// how quickly it gets up to speed and slows down again, in units per second squared
+    pub acceleration: f32,
+    /// seconds it waits at a floor before leaving for the next call
+    pub dwell: f32,
+    /// the floor it's at, or last left
+    floor: usize,
+    /// floors called, in the order they'll be visited; the first is where it's heading
+    calls: VecDeque<usize>,
+    /// up along world Y
+    velocity: f32,
+    /// seconds left before it leaves the floor it's at
+    waiting: f32,
+}
+
+impl Elevator {
+    /// an elevator waiting at floor 0, which is usually where it's spawned
+    pub fn new(speed: f32, acceleration: f32, floors: impl IntoIterator<Item = f32>) -> Self {
+        Self {
+            floors: floors.into_iter().collect(),
+            speed,
+            acceleration,
+            dwell: 1.0,
+            floor: 0,
+            calls: VecDeque::new(),
+            velocity: 0.0,
+            waiting: 0.0,
+        }
+    }
+
+    pub fn with_dwell(mut self, dwell: f32) -> Self {
+        self.dwell = dwell;
+        self
+    }
+
+    /// queue a trip to `floor`, unless it's already called, or it's there and not going
+    /// anywhere
+    pub fn call(&mut self, floor: usize) {
+        let here = self.calls.is_empty() && self.floor == floor;
+        if floor < self.floors.len() && !here && !self.calls.contains(&floor) {
+            self.calls.push_back(floor);
+        }
+    }
+
+    /// the floor it's at, or last left
+    pub fn floor(&self) -> usize {
+        self.floor
+    }
+
+    /// whether it's on its way somewhere, or waiting to leave
+    pub fn is_busy(&self) -> bool {
+        !self.calls.is_empty()
+    }
+
+    /// where it'll be and how fast it'll be going up after another `seconds` on from
+    /// `height`, or None if it isn't going anywhere yet
+    fn plan(&self, height: f32, seconds: f32) -> Option<(f32, f32)> {
+        let &next = self.calls.front()?;
+        if self.waiting > 0.0 {
+            return None;
+        }
+
+        let to_go = self.floors[next] - height;
+        // as fast as it can go while still able to stop at the floor
+        let stopping = (2.0 * self.acceleration * to_go.abs()).sqrt();
+        let speed = (self.velocity.abs() + self.acceleration * seconds)
+            .min(self.speed)
+            .min(stopping);
+        if speed * seconds >= to_go.abs() {
+            return Some((self.floors[next], 0.0));
+        }
+        Some((
+            height + speed * to_go.signum() * seconds,
+            speed * to_go.signum(),
+        ))
+    }
+
+    /// stop at the floor it was heading for
+    fn arrive(&mut self) {
+        if let Some(floor) = self.calls.pop_front() {
+            self.floor = floor;
+        }
+        self.waiting = self.dwell;
+    }
+}
+
+/// a UseButton (or PressurePlate) linked to an Elevator calls it to this floor. without one,
+/// an activation sends it on to the next floor up, round to the bottom from the top
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CallElevator(pub usize);
+
+/// elevators and the buttons calling them. needs InteractPlugin
+#[derive(Clone, Default)]
+pub struct ElevatorPlugin;
+
+impl Plugin for ElevatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, move_elevators.before(PhysicsSet::SyncBackend))
+            .add_systems(FixedUpdate, call_elevators.after(activate_doors));
+    }
+}
+
+/// gap an elevator keeps above a player it's coming down on
+const ELEVATOR_MARGIN: f32 = 0.05;
+
+/// call elevators to the floors their buttons ask for
+pub(crate) fn call_elevators(
+    mut er_activate: EventReader<Activate>,
+    mut elevators: Query<&mut Elevator>,
+    calls: Query<&CallElevator>,
+) {
+    for ev in er_activate.read() {
+        let Ok(mut elevator) = elevators.get_mut(ev.target) else {
+            continue;
+        };
+        let floor = match calls.get(ev.source) {
+            Ok(call) => call.0,
+            Err(_) => {
+                let last = elevator.calls.back().copied().unwrap_or(elevator.floor);
+                (last + 1) % elevator.floors.len().max(1)
+            }
+        };
+        elevator.call(floor);
+    }
+}
+
+/// move the elevators towards their calls. one about to come down on a player stops where it
+/// is and picks up again once they're clear. like move_platforms, this runs before rapier
+/// syncs so it takes the new transform as the elevator's next position
+pub(crate) fn move_elevators(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut elevators: Query<(Entity, &mut Elevator, &mut Transform, &Collider)>,
+    players: Query<(), With<Player>>,
+) {
+    for (entity, mut elevator, mut transform, collider) in &mut elevators {
+        elevator.waiting = (elevator.waiting - time.delta_seconds()).max(0.0);
+        let Some((height, velocity)) = elevator.plan(transform.translation.y, time.delta_seconds())
+        else {
+            continue;
+        };
+
+        let drop = transform.translation.y - height;
+        if drop > 0.0 {
+            let blocked_by_player = |entity| players.contains(entity);
+            let filter = QueryFilter::new()
+                .exclude_sensors()
+                .exclude_collider(entity)
+                .predicate(&blocked_by_player);
+            let options = ShapeCastOptions {
+                max_time_of_impact: drop,
+                target_distance: ELEVATOR_MARGIN,
+                // a player standing on it doesn't stop it
+                stop_at_penetration: false,
+                compute_impact_geometry_on_penetration: false,
+            };
+            let blocked = rapier_context
+                .cast_shape(
+                    transform.translation,
+                    transform.rotation,
+                    Vec3::NEG_Y,
+                    collider,
+                    options,
+                    filter,
+                )
+                .is_some();
+            if blocked {
+                // and set off again from a standstill
+                elevator.velocity = 0.0;
+                continue;
+            }
+        }
+
+        transform.translation.y = height;
+        elevator.velocity = velocity;
+        if elevator.calls.front().map(|&floor| elevator.floors[floor]) == Some(height) {
+            elevator.arrive();
+        }
+    }
+}
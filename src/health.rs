@@ -0,0 +1,356 @@
+//! player health: damage from falls, hazards and damage volumes, regeneration and death, and
+//! the health bar and score hud
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{DespawnPlayer, KeyboardMouseInput, Player, PlayerLanded, Score};
+
+/// event sent when a player takes damage
+#[derive(Event)]
+pub struct PlayerDamaged {
+    pub player: Entity,
+    pub amount: f32,
+}
+
+/// event sent when a player's health reaches zero
+#[derive(Event)]
+pub struct PlayerDied {
+    pub player: Entity,
+}
+
+/// player health
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn full(max: f32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// health and regeneration configuration
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HealthConfig {
+    pub max: f32,
+    /// health regenerated per second, zero to disable
+    pub regen_rate: f32,
+    /// seconds after taking damage before regeneration starts
+    pub regen_delay: f32,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max: 100.0,
+            regen_rate: 5.0,
+            regen_delay: 3.0,
+        }
+    }
+}
+
+/// seconds since a player last took damage
+#[derive(Component, Default)]
+pub(crate) struct TimeSinceDamage(f32);
+
+/// sensor volume that damages the player once on entry
+#[derive(Component)]
+pub(crate) struct Hazard {
+    pub(crate) damage: f32,
+}
+
+/// sensor volume that damages the player continuously while inside (lava, acid)
+#[derive(Component)]
+pub(crate) struct DamageVolume {
+    /// damage per second; overlapping volumes stack
+    pub(crate) dps: f32,
+}
+
+/// fill of the health bar hud
+#[derive(Component)]
+pub(crate) struct HealthBar;
+
+/// hud text counting up the Score
+#[derive(Component)]
+pub(crate) struct ScoreText;
+
+/// health bar fill color at rest
+const HEALTH_BAR_COLOR: Color = Color::srgb(0.8, 0.1, 0.1);
+
+/// how strongly the health bar is flashing after damage, fading from 1 to 0
+#[derive(Resource, Default)]
+pub(crate) struct DamageFlash(f32);
+
+/// fall damage curve, applied to landing impact speeds
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FallDamageConfig {
+    pub enabled: bool,
+    /// impact speed below which landings are harmless
+    pub safe_speed: f32,
+    /// damage per unit of impact speed above the safe speed
+    pub scale: f32,
+    /// damage cap for a single landing
+    pub max_damage: f32,
+}
+
+impl Default for FallDamageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            safe_speed: 15.0,
+            scale: 5.0,
+            max_damage: 100.0,
+        }
+    }
+}
+
+impl FallDamageConfig {
+    /// damage for a landing at `impact_speed`, zero if at or below the safe speed
+    pub fn damage(&self, impact_speed: f32) -> f32 {
+        ((impact_speed - self.safe_speed) * self.scale).clamp(0.0, self.max_damage)
+    }
+}
+
+/// turn hard landings into PlayerDamaged events
+pub(crate) fn fall_damage(
+    config: Res<FallDamageConfig>,
+    mut er_landed: EventReader<PlayerLanded>,
+    mut ew_damaged: EventWriter<PlayerDamaged>,
+) {
+    // a slam lands hard on purpose
+    for ev in er_landed.read().filter(|ev| !ev.slam) {
+        let amount = config.damage(ev.impact_speed);
+        if config.enabled && amount > 0.0 {
+            ew_damaged.send(PlayerDamaged {
+                player: ev.player,
+                amount,
+            });
+        }
+    }
+}
+
+/// damage players for every damage volume they currently overlap
+pub(crate) fn damage_volumes(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut ew_damaged: EventWriter<PlayerDamaged>,
+    players: Query<Entity, With<Player>>,
+    volumes: Query<&DamageVolume>,
+) {
+    for player in &players {
+        let dps = rapier_context
+            .intersection_pairs_with(player)
+            .filter(|&(_, _, intersecting)| intersecting)
+            .filter_map(|(a, b, _)| volumes.get(if a == player { b } else { a }).ok())
+            .map(|volume| volume.dps)
+            .sum::<f32>();
+
+        if dps > 0.0 {
+            ew_damaged.send(PlayerDamaged {
+                player,
+                amount: dps * time.delta_seconds(),
+            });
+        }
+    }
+}
+
+/// damage the player when entering a hazard volume
+pub(crate) fn hazard_damage(
+    mut er_collision: EventReader<CollisionEvent>,
+    mut ew_damaged: EventWriter<PlayerDamaged>,
+    player: Query<(), With<Player>>,
+    hazards: Query<&Hazard>,
+) {
+    for ev in er_collision.read() {
+        let CollisionEvent::Started(a, b, _) = *ev else {
+            continue;
+        };
+
+        let (player, hazard) = if player.contains(a) {
+            (a, hazards.get(b))
+        } else if player.contains(b) {
+            (b, hazards.get(a))
+        } else {
+            continue;
+        };
+
+        if let Ok(hazard) = hazard {
+            ew_damaged.send(PlayerDamaged {
+                player,
+                amount: hazard.damage,
+            });
+        }
+    }
+}
+
+/// subtract damage from the player's health, sending PlayerDied when it runs out
+pub(crate) fn apply_damage(
+    mut er_damaged: EventReader<PlayerDamaged>,
+    mut ew_died: EventWriter<PlayerDied>,
+    mut query: Query<(&mut Health, &mut TimeSinceDamage), With<Player>>,
+) {
+    for ev in er_damaged.read() {
+        let Ok((mut health, mut since_damage)) = query.get_mut(ev.player) else {
+            continue;
+        };
+
+        if health.current <= 0.0 {
+            continue;
+        }
+
+        health.current = (health.current - ev.amount).max(0.0);
+        since_damage.0 = 0.0;
+        if health.current <= 0.0 {
+            ew_died.send(PlayerDied { player: ev.player });
+        }
+    }
+}
+
+/// regenerate health once the player hasn't been damaged for a while
+pub(crate) fn regenerate_health(
+    time: Res<Time>,
+    config: Res<HealthConfig>,
+    mut query: Query<(&mut Health, &mut TimeSinceDamage), With<Player>>,
+) {
+    for (mut health, mut since_damage) in &mut query {
+        since_damage.0 += time.delta_seconds();
+        if config.regen_rate <= 0.0 || since_damage.0 < config.regen_delay {
+            continue;
+        }
+
+        if health.current > 0.0 && health.current < health.max {
+            health.current =
+                (health.current + config.regen_rate * time.delta_seconds()).min(health.max);
+        }
+    }
+}
+
+/// despawn players whose health runs out, to respawn the same way as players who fall
+pub(crate) fn despawn_dead_players(
+    mut er_died: EventReader<PlayerDied>,
+    mut ew_despawn: EventWriter<DespawnPlayer>,
+) {
+    for ev in er_died.read() {
+        ew_despawn.send(DespawnPlayer { player: ev.player });
+    }
+}
+
+/// spawn the hud: a health bar in the bottom-left corner, and the Score above it
+pub(crate) fn setup_hud(mut commands: Commands) {
+    commands
+        .spawn(
+            TextBundle::from_section("Coins: 0", default()).with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.0),
+                bottom: Val::Px(40.0),
+                ..default()
+            }),
+        )
+        .insert(ScoreText);
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.0),
+                bottom: Val::Px(16.0),
+                width: Val::Px(200.0),
+                height: Val::Px(16.0),
+                ..default()
+            },
+            background_color: Color::BLACK.with_alpha(0.5).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: HEALTH_BAR_COLOR.into(),
+                    ..default()
+                })
+                .insert(HealthBar);
+        });
+}
+
+/// flash the health bar white when the keyboard and mouse player takes damage
+pub(crate) fn flash_health_bar(
+    time: Res<Time>,
+    mut flash: ResMut<DamageFlash>,
+    mut er_damaged: EventReader<PlayerDamaged>,
+    player: Query<(), (With<Player>, With<KeyboardMouseInput>)>,
+    mut bar: Query<&mut BackgroundColor, With<HealthBar>>,
+) {
+    const FADE_SPEED: f32 = 4.0;
+
+    if er_damaged.read().any(|ev| player.contains(ev.player)) {
+        flash.0 = 1.0;
+    } else if flash.0 > 0.0 {
+        flash.0 = (flash.0 - FADE_SPEED * time.delta_seconds()).max(0.0);
+    } else {
+        return;
+    }
+
+    for mut color in &mut bar {
+        *color = HEALTH_BAR_COLOR.mix(&Color::WHITE, flash.0).into();
+    }
+}
+
+/// show the Score whenever it changes
+pub(crate) fn update_score_text(score: Res<Score>, mut text: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!("Coins: {}", score.0);
+    }
+}
+
+/// resize the health bar fill to the keyboard and mouse player's health
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_health_bar(
+    health: Query<&Health, (With<Player>, With<KeyboardMouseInput>, Changed<Health>)>,
+    mut bar: Query<&mut Style, With<HealthBar>>,
+) {
+    let Ok(health) = health.get_single() else {
+        return;
+    };
+
+    for mut style in &mut bar {
+        style.width = Val::Percent(health.current / health.max * 100.0);
+    }
+}
+
+#[cfg(test)]
+mod fall_damage_tests {
+    use super::*;
+
+    #[test]
+    fn harmless_up_to_the_safe_speed() {
+        let config = FallDamageConfig::default();
+        for speed in [0.0, 1.0, config.safe_speed * 0.5, config.safe_speed] {
+            assert_eq!(config.damage(speed), 0.0, "at {speed}");
+        }
+    }
+
+    #[test]
+    fn grows_with_impact_speed_up_to_the_cap() {
+        let config = FallDamageConfig::default();
+        let mut last = 0.0;
+        for step in 1..=200 {
+            let speed = config.safe_speed + step as f32 * 0.25;
+            let damage = config.damage(speed);
+            assert!(damage >= last, "{damage} at {speed} below {last}");
+            assert!(damage <= config.max_damage, "{damage} at {speed}");
+            last = damage;
+        }
+        assert!(config.damage(config.safe_speed + 1.0) > 0.0);
+        assert_eq!(last, config.max_damage);
+    }
+}
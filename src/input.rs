@@ -0,0 +1,1800 @@
+//! input: key bindings, gamepads and touch, gathered into a snapshot each physics tick, and
+//! recording and replaying it
+
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        keyboard::{Key, KeyboardFocusLost, KeyboardInput},
+        mouse::MouseMotion,
+        ButtonState,
+    },
+    prelude::*,
+    utils::HashMap,
+    window::PrimaryWindow,
+};
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::TnuaUserControlsSystemSet;
+#[cfg(feature = "leafwing")]
+use leafwing_input_manager::prelude::{
+    ActionState, Actionlike, GamepadStick, InputMap, KeyboardVirtualDPad, MouseMove,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{
+        CameraYaw, CursorSuspended, LookConfig, SplitScreen, Zoom, ZoomConfig, MAX_LOCAL_PLAYERS,
+    },
+    camera_path::CameraPathPlayback,
+    menu::menu_closed,
+    photo_mode::PhotoMode,
+    CameraLookTarget, LookAssistConfig, MouseLocked, Player, PlayerCamera, TimeScale, WorldUp,
+};
+
+/// mouse motion held back by LookConfig::smooth
+#[derive(Default)]
+pub(crate) struct MouseSmoothing {
+    /// recent frames' deltas, already divided by the averaging length
+    pub(crate) history: VecDeque<Vec2>,
+    /// motion not yet released by the exponential filter
+    pub(crate) pending: Vec2,
+}
+
+impl MouseSmoothing {
+    pub(crate) fn reset(&mut self) {
+        self.history.clear();
+        self.pending = Vec2::ZERO;
+    }
+}
+
+/// logical input actions, bound to keys through KeyBindings
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub(crate) enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Sprint,
+    Crouch,
+    /// use whatever's in front of the player
+    Interact,
+    Dash,
+    LeanLeft,
+    LeanRight,
+    /// open, close, or back out of menus
+    Pause,
+    CycleCameraRig,
+    ToggleControlMode,
+    PlayCameraPath,
+    ToggleCameraPathPreview,
+    LookAtOrigin,
+    TogglePhotoMode,
+    Screenshot,
+    PhotoRollLeft,
+    PhotoRollRight,
+    PhotoFovDown,
+    PhotoFovUp,
+    /// start or stop recording input to RECORDING_PATH
+    ToggleRecording,
+    /// replay RECORDING_PATH, or stop a replay in progress
+    PlayRecording,
+    /// freeze or unfreeze the day-night cycle
+    PauseTimeOfDay,
+    /// run the day-night cycle faster while held
+    FastForwardTime,
+    /// drop TimeScale to BULLET_TIME_SCALE, or back to normal speed
+    ToggleBulletTime,
+    /// send ResetLevel, putting the scene and players back as they started
+    ResetLevel,
+    /// show or hide the MovementGizmos
+    ToggleMovementGizmos,
+    /// show or hide the speedometer
+    ToggleSpeedometer,
+    /// show or hide the DebugOverlay
+    ToggleDebugOverlay,
+}
+
+impl Action {
+    pub(crate) const ALL: [Self; 32] = [
+        Self::MoveForward,
+        Self::MoveBack,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Jump,
+        Self::Sprint,
+        Self::Crouch,
+        Self::Interact,
+        Self::Dash,
+        Self::LeanLeft,
+        Self::LeanRight,
+        Self::Pause,
+        Self::CycleCameraRig,
+        Self::ToggleControlMode,
+        Self::PlayCameraPath,
+        Self::ToggleCameraPathPreview,
+        Self::LookAtOrigin,
+        Self::TogglePhotoMode,
+        Self::Screenshot,
+        Self::PhotoRollLeft,
+        Self::PhotoRollRight,
+        Self::PhotoFovDown,
+        Self::PhotoFovUp,
+        Self::ToggleRecording,
+        Self::PlayRecording,
+        Self::PauseTimeOfDay,
+        Self::FastForwardTime,
+        Self::ToggleBulletTime,
+        Self::ResetLevel,
+        Self::ToggleMovementGizmos,
+        Self::ToggleSpeedometer,
+        Self::ToggleDebugOverlay,
+    ];
+
+    /// player movement actions; holding any of them means the keyboard is in use
+    #[cfg(not(feature = "leafwing"))]
+    const MOVEMENT: [Self; 9] = [
+        Self::MoveForward,
+        Self::MoveBack,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Jump,
+        Self::Sprint,
+        Self::Crouch,
+        Self::Interact,
+        Self::Dash,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::MoveForward => "Forward",
+            Self::MoveBack => "Back",
+            Self::MoveLeft => "Left",
+            Self::MoveRight => "Right",
+            Self::Jump => "Jump",
+            Self::Sprint => "Sprint",
+            Self::Crouch => "Crouch",
+            Self::Interact => "Interact",
+            Self::Dash => "Dash",
+            Self::LeanLeft => "Lean left",
+            Self::LeanRight => "Lean right",
+            Self::Pause => "Pause",
+            Self::CycleCameraRig => "Cycle camera rig",
+            Self::ToggleControlMode => "Toggle control mode",
+            Self::PlayCameraPath => "Play camera path",
+            Self::ToggleCameraPathPreview => "Toggle camera path preview",
+            Self::LookAtOrigin => "Look at origin",
+            Self::TogglePhotoMode => "Toggle photo mode",
+            Self::Screenshot => "Screenshot",
+            Self::PhotoRollLeft => "Photo roll left",
+            Self::PhotoRollRight => "Photo roll right",
+            Self::PhotoFovDown => "Photo FOV down",
+            Self::PhotoFovUp => "Photo FOV up",
+            Self::ToggleRecording => "Toggle input recording",
+            Self::PlayRecording => "Play input recording",
+            Self::PauseTimeOfDay => "Pause time of day",
+            Self::FastForwardTime => "Fast-forward time",
+            Self::ToggleBulletTime => "Toggle bullet time",
+            Self::ResetLevel => "Reset level",
+            Self::ToggleMovementGizmos => "Toggle movement gizmos",
+            Self::ToggleSpeedometer => "Toggle speedometer",
+            Self::ToggleDebugOverlay => "Toggle debug overlay",
+        }
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Self::MoveForward => vec![KeyCode::KeyW, KeyCode::ArrowUp],
+            Self::MoveBack => vec![KeyCode::KeyS, KeyCode::ArrowDown],
+            Self::MoveLeft => vec![KeyCode::KeyA, KeyCode::ArrowLeft],
+            Self::MoveRight => vec![KeyCode::KeyD, KeyCode::ArrowRight],
+            Self::Jump => vec![KeyCode::Space],
+            Self::Sprint => vec![KeyCode::ShiftLeft],
+            Self::Crouch => vec![KeyCode::ControlLeft],
+            Self::Interact => vec![KeyCode::KeyF],
+            Self::Dash => vec![KeyCode::AltLeft],
+            Self::LeanLeft => vec![KeyCode::KeyQ],
+            Self::LeanRight => vec![KeyCode::KeyE],
+            Self::Pause => vec![KeyCode::Escape],
+            Self::CycleCameraRig => vec![KeyCode::KeyV],
+            Self::ToggleControlMode => vec![KeyCode::KeyT],
+            Self::PlayCameraPath => vec![KeyCode::KeyC],
+            Self::ToggleCameraPathPreview => vec![KeyCode::F2],
+            Self::LookAtOrigin => vec![KeyCode::KeyL],
+            Self::TogglePhotoMode => vec![KeyCode::KeyP],
+            Self::Screenshot => vec![KeyCode::F12],
+            Self::PhotoRollLeft => vec![KeyCode::KeyQ],
+            Self::PhotoRollRight => vec![KeyCode::KeyE],
+            Self::PhotoFovDown => vec![KeyCode::Minus],
+            Self::PhotoFovUp => vec![KeyCode::Equal],
+            Self::ToggleRecording => vec![KeyCode::F5],
+            Self::PlayRecording => vec![KeyCode::F6],
+            Self::PauseTimeOfDay => vec![KeyCode::F7],
+            Self::FastForwardTime => vec![KeyCode::F8],
+            Self::ToggleBulletTime => vec![KeyCode::KeyB],
+            Self::ResetLevel => vec![KeyCode::KeyR],
+            Self::ToggleMovementGizmos => vec![KeyCode::F4],
+            Self::ToggleSpeedometer => vec![KeyCode::F9],
+            Self::ToggleDebugOverlay => vec![KeyCode::F3],
+        }
+    }
+
+    /// how the action's keys are matched by default: mnemonic keys follow their printed letter,
+    /// everything else stays where it is on the keyboard
+    fn default_matching(self) -> KeyMatch {
+        match self {
+            Self::CycleCameraRig
+            | Self::ToggleControlMode
+            | Self::PlayCameraPath
+            | Self::LookAtOrigin
+            | Self::TogglePhotoMode
+            | Self::ToggleBulletTime
+            | Self::ResetLevel => KeyMatch::Logical,
+            _ => KeyMatch::Physical,
+        }
+    }
+
+    /// whether both actions can be read at the same time, so they can't share a key. actions
+    /// only used in photo mode don't overlap with those only used outside it
+    fn overlaps(self, other: Self) -> bool {
+        let photo_only = |action| {
+            matches!(
+                action,
+                Self::PhotoRollLeft
+                    | Self::PhotoRollRight
+                    | Self::PhotoFovDown
+                    | Self::PhotoFovUp
+                    | Self::Screenshot
+            )
+        };
+        let gameplay_only = |action| matches!(action, Self::LeanLeft | Self::LeanRight);
+
+        !(photo_only(self) && gameplay_only(other) || gameplay_only(self) && photo_only(other))
+    }
+}
+
+/// how an action's keys are matched against the keyboard
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub(crate) enum KeyMatch {
+    /// by position, so WASD stays an inverted T on any layout
+    #[default]
+    Physical,
+    /// by the character the key types, named by where that character is on a US layout, so
+    /// e.g. KeyP is whichever key types a P
+    Logical,
+}
+
+/// keys by the character they type rather than their position, named by where that character
+/// is on a US layout. keys that don't type a letter, digit or punctuation mark keep their position
+#[derive(Resource, Default)]
+pub(crate) struct LogicalKeys {
+    input: ButtonInput<KeyCode>,
+    /// the logical key each held physical key pressed, so releasing it releases the same one
+    /// even if modifiers changed meanwhile
+    held: HashMap<KeyCode, KeyCode>,
+}
+
+/// the US layout key that types `key`, if it's a single letter, digit or punctuation mark
+fn logical_key_code(key: &Key) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KeyA,
+        KeyCode::KeyB,
+        KeyCode::KeyC,
+        KeyCode::KeyD,
+        KeyCode::KeyE,
+        KeyCode::KeyF,
+        KeyCode::KeyG,
+        KeyCode::KeyH,
+        KeyCode::KeyI,
+        KeyCode::KeyJ,
+        KeyCode::KeyK,
+        KeyCode::KeyL,
+        KeyCode::KeyM,
+        KeyCode::KeyN,
+        KeyCode::KeyO,
+        KeyCode::KeyP,
+        KeyCode::KeyQ,
+        KeyCode::KeyR,
+        KeyCode::KeyS,
+        KeyCode::KeyT,
+        KeyCode::KeyU,
+        KeyCode::KeyV,
+        KeyCode::KeyW,
+        KeyCode::KeyX,
+        KeyCode::KeyY,
+        KeyCode::KeyZ,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    let Key::Character(text) = key else {
+        return None;
+    };
+    let mut chars = text.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    Some(match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => LETTERS[(c as u8 - b'a') as usize],
+        c @ '0'..='9' => DIGITS[(c as u8 - b'0') as usize],
+        '-' => KeyCode::Minus,
+        '=' => KeyCode::Equal,
+        '[' => KeyCode::BracketLeft,
+        ']' => KeyCode::BracketRight,
+        '\\' => KeyCode::Backslash,
+        ';' => KeyCode::Semicolon,
+        '\'' => KeyCode::Quote,
+        ',' => KeyCode::Comma,
+        '.' => KeyCode::Period,
+        '/' => KeyCode::Slash,
+        '`' => KeyCode::Backquote,
+        _ => return None,
+    })
+}
+
+/// mirror keyboard input into LogicalKeys
+pub(crate) fn update_logical_keys(
+    mut logical: ResMut<LogicalKeys>,
+    mut er_keyboard: EventReader<KeyboardInput>,
+    mut er_focus_lost: EventReader<KeyboardFocusLost>,
+) {
+    let logical = &mut *logical;
+    logical.input.clear();
+    for ev in er_keyboard.read() {
+        match ev.state {
+            ButtonState::Pressed => {
+                let key = logical_key_code(&ev.logical_key).unwrap_or(ev.key_code);
+                logical.held.insert(ev.key_code, key);
+                logical.input.press(key);
+            }
+            ButtonState::Released => {
+                if let Some(key) = logical.held.remove(&ev.key_code) {
+                    logical.input.release(key);
+                }
+            }
+        }
+    }
+
+    if er_focus_lost.read().count() > 0 {
+        logical.input.release_all();
+        logical.held.clear();
+    }
+}
+
+/// keyboard state for reading KeyBindings, by both key position and typed character
+#[derive(SystemParam)]
+pub(crate) struct Keys<'w> {
+    physical: Res<'w, ButtonInput<KeyCode>>,
+    logical: Res<'w, LogicalKeys>,
+}
+
+impl Keys<'_> {
+    pub(crate) fn input(&self, matching: KeyMatch) -> &ButtonInput<KeyCode> {
+        match matching {
+            KeyMatch::Physical => &self.physical,
+            KeyMatch::Logical => &self.logical.input,
+        }
+    }
+}
+
+/// a key that couldn't be bound because another action already uses it
+#[derive(Debug)]
+pub(crate) struct BindingConflict {
+    pub(crate) key: KeyCode,
+    pub(crate) bound_to: Action,
+}
+
+impl std::fmt::Display for BindingConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is already bound to {}",
+            self.key,
+            self.bound_to.name()
+        )
+    }
+}
+
+/// keys bound to each action; an action may have several keys, but a key only one of any
+/// actions that are read at the same time
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KeyBindings {
+    bindings: BTreeMap<Action, Vec<KeyCode>>,
+    /// whether each action's keys are matched by position or by what they type
+    matching: BTreeMap<Action, KeyMatch>,
+    pub(crate) sprint_mode: PressMode,
+    pub(crate) crouch_mode: PressMode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+            matching: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_matching()))
+                .collect(),
+            sprint_mode: PressMode::Hold,
+            crouch_mode: PressMode::Hold,
+        }
+    }
+}
+
+/// whether an action stays on while its button is held, or flips on each press
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub(crate) enum PressMode {
+    #[default]
+    Hold,
+    /// press once to turn on, and again (or on an auto-cancel) to turn off
+    Toggle,
+}
+
+/// hold or toggle state of an action, fed whether its button is held each tick
+#[derive(Default)]
+pub(crate) struct PressToggle {
+    pub(crate) on: bool,
+    was_pressed: bool,
+}
+
+impl PressToggle {
+    /// whether the action is on this tick
+    pub(crate) fn update(&mut self, mode: PressMode, pressed: bool) -> bool {
+        match mode {
+            PressMode::Hold => self.on = pressed,
+            PressMode::Toggle if pressed && !self.was_pressed => self.on = !self.on,
+            PressMode::Toggle => {}
+        }
+        self.was_pressed = pressed;
+        self.on
+    }
+}
+
+impl KeyBindings {
+    pub(crate) fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn matching(&self, action: Action) -> KeyMatch {
+        self.matching
+            .get(&action)
+            .copied()
+            .unwrap_or(action.default_matching())
+    }
+
+    pub(crate) fn pressed(&self, keys: &Keys, action: Action) -> bool {
+        keys.input(self.matching(action))
+            .any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub(crate) fn just_pressed(&self, keys: &Keys, action: Action) -> bool {
+        keys.input(self.matching(action))
+            .any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    /// an action overlapping `action` that `key` is already bound to, if any
+    fn conflict(&self, action: Action, key: KeyCode) -> Option<BindingConflict> {
+        self.bindings
+            .iter()
+            .find(|&(&other, keys)| {
+                other != action && action.overlaps(other) && keys.contains(&key)
+            })
+            .map(|(&bound_to, _)| BindingConflict { key, bound_to })
+    }
+
+    /// bind `key` as the only key for `action`
+    pub(crate) fn set(&mut self, action: Action, key: KeyCode) -> Result<(), BindingConflict> {
+        if let Some(conflict) = self.conflict(action, key) {
+            return Err(conflict);
+        }
+        self.bindings.insert(action, vec![key]);
+        Ok(())
+    }
+
+    /// bind `key` to `action` alongside its existing keys
+    fn add(&mut self, action: Action, key: KeyCode) -> Result<(), BindingConflict> {
+        if let Some(conflict) = self.conflict(action, key) {
+            return Err(conflict);
+        }
+        let keys = self.bindings.entry(action).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// remove `key` from `action`'s keys
+    pub(crate) fn unbind(&mut self, action: Action, key: KeyCode) {
+        if let Some(keys) = self.bindings.get_mut(&action) {
+            keys.retain(|&bound| bound != key);
+        }
+    }
+
+    /// restore every action's default keys and matching, keeping the press modes
+    pub(crate) fn reset_keys(&mut self) {
+        let default = Self::default();
+        self.bindings = default.bindings;
+        self.matching = default.matching;
+    }
+
+    /// bind default keys for actions missing from a saved file (e.g. added since), skipping
+    /// any that would conflict
+    pub(crate) fn fill_defaults(&mut self) {
+        for action in Action::ALL {
+            self.matching
+                .entry(action)
+                .or_insert(action.default_matching());
+            if self.bindings.contains_key(&action) {
+                continue;
+            }
+            self.bindings.insert(action, Vec::new());
+            for key in action.default_keys() {
+                if let Err(conflict) = self.add(action, key) {
+                    warn!("not binding {}: {conflict}", action.name());
+                }
+            }
+        }
+    }
+}
+
+/// marks a player, and its cameras and viewmodels, as driven by keyboard and mouse
+#[derive(Component, Clone, Copy)]
+pub struct KeyboardMouseInput;
+
+/// marks a player, and its cameras and viewmodels, as driven by the nth connected gamepad
+#[derive(Component, Clone, Copy)]
+pub struct GamepadInput(pub usize);
+
+impl GamepadInput {
+    pub(crate) fn gamepad(&self, gamepads: &Gamepads) -> Option<Gamepad> {
+        gamepads.iter().nth(self.0)
+    }
+}
+
+/// how stick deflection maps to look or movement speed
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum StickCurve {
+    Linear,
+    /// squares the deflection, for finer control near the center
+    Squared,
+}
+
+impl StickCurve {
+    /// reshape a stick's deflection, keeping its direction
+    fn apply(self, stick: Vec2) -> Vec2 {
+        match self {
+            Self::Linear => stick,
+            Self::Squared => stick * stick.length(),
+        }
+    }
+
+    pub(crate) fn toggled(self) -> Self {
+        match self {
+            Self::Linear => Self::Squared,
+            Self::Squared => Self::Linear,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Squared => "squared",
+        }
+    }
+}
+
+/// gamepad stick tuning
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct GamepadConfig {
+    /// radial deadzone of the movement stick, from 0 to 1; input is rescaled past it so
+    /// movement still ramps smoothly from zero
+    deadzone: f32,
+    /// how movement stick tilt maps to speed, up to walk speed at full tilt
+    pub(crate) move_curve: StickCurve,
+    /// radial deadzone of the look stick
+    look_deadzone: f32,
+    /// look speed at full right stick deflection, in radians per second. separate from the
+    /// mouse sensitivity, since stick input is a rate rather than a distance
+    pub(crate) look_sensitivity: f32,
+    pub(crate) look_curve: StickCurve,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            move_curve: StickCurve::Linear,
+            look_deadzone: 0.1,
+            look_sensitivity: 3.0,
+            look_curve: StickCurve::Linear,
+        }
+    }
+}
+
+/// connected gamepads and their assignment to players, for systems reading stick input
+#[derive(SystemParam)]
+pub(crate) struct GamepadSticks<'w> {
+    gamepads: Res<'w, Gamepads>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    config: Res<'w, GamepadConfig>,
+    split: Res<'w, SplitScreen>,
+}
+
+impl GamepadSticks<'_> {
+    /// the first gamepad not taken by a split-screen player, which the keyboard and mouse
+    /// player can use too
+    fn spare(&self) -> Option<Gamepad> {
+        let gamepad_players = self.split.players.clamp(1, MAX_LOCAL_PLAYERS) - 1;
+        self.gamepads.iter().nth(gamepad_players)
+    }
+
+    /// this frame's look delta in radians from the right stick, before invert-y
+    fn look_delta(&self, gamepad: Gamepad, dt: f32) -> Vec2 {
+        let axis = |axis_type| {
+            self.axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        self.config.stick_look(
+            Vec2::new(
+                axis(GamepadAxisType::RightStickX),
+                axis(GamepadAxisType::RightStickY),
+            ),
+            dt,
+        )
+    }
+}
+
+impl GamepadConfig {
+    /// turn a raw right stick position (y up) into a look delta in radians, before invert-y
+    fn stick_look(&self, stick: Vec2, dt: f32) -> Vec2 {
+        let stick = radial_deadzone(Vec2::new(stick.x, -stick.y), self.look_deadzone);
+        self.look_curve.apply(stick) * self.look_sensitivity * dt
+    }
+
+    /// turn a raw movement stick position (y forward) into a movement direction relative to the
+    /// player, with length up to 1 scaling the speed
+    fn stick_movement(&self, stick: Vec2) -> Vec3 {
+        let stick = self.move_curve.apply(radial_deadzone(stick, self.deadzone));
+        Vec3::new(stick.x, 0.0, -stick.y)
+    }
+}
+
+#[cfg(test)]
+mod stick_tests {
+    use super::*;
+
+    fn config(move_curve: StickCurve) -> GamepadConfig {
+        GamepadConfig {
+            move_curve,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn nothing_inside_the_deadzone() {
+        for curve in [StickCurve::Linear, StickCurve::Squared] {
+            let config = config(curve);
+            for magnitude in [0.0, 0.05, config.deadzone * 0.99, config.deadzone] {
+                for direction in [Vec2::X, Vec2::Y, Vec2::NEG_ONE.normalize()] {
+                    let movement = config.stick_movement(direction * magnitude);
+                    assert_eq!(movement, Vec3::ZERO, "at {magnitude} {direction}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ramps_from_zero_past_the_deadzone_along_the_curve() {
+        let linear = config(StickCurve::Linear);
+        let squared = config(StickCurve::Squared);
+        let deadzone = linear.deadzone;
+        let mut last = 0.0;
+        for step in 1..=20 {
+            let magnitude = deadzone + (1.0 - deadzone) * step as f32 / 20.0;
+            let rescaled = (magnitude - deadzone) / (1.0 - deadzone);
+            let stick = Vec2::new(0.6, 0.8) * magnitude;
+
+            let movement = linear.stick_movement(stick);
+            assert!(
+                (movement.length() - rescaled).abs() < 1e-5,
+                "at {magnitude}"
+            );
+            assert!(movement.length() > last, "at {magnitude}");
+            last = movement.length();
+            // keeping the stick's direction, y forward
+            assert!(movement.normalize().distance(Vec3::new(0.6, 0.0, -0.8)) < 1e-5);
+
+            let squared = squared.stick_movement(stick);
+            assert!((squared.length() - rescaled * rescaled).abs() < 1e-5);
+            assert!(squared.normalize().distance(movement.normalize()) < 1e-5);
+        }
+        // just past the deadzone, squared creeps in slower than linear
+        let gentle = Vec2::Y * (deadzone + 0.1);
+        assert!(squared.stick_movement(gentle).length() < linear.stick_movement(gentle).length());
+    }
+
+    #[test]
+    fn full_tilt_and_digital_keys_move_at_full_magnitude() {
+        for curve in [StickCurve::Linear, StickCurve::Squared] {
+            let config = config(curve);
+            // keys read as the stick pinned to an edge, with diagonals clamped back to length 1
+            for keys in [Vec2::X, Vec2::NEG_Y, Vec2::ONE, Vec2::new(-1.0, 1.0)] {
+                let movement = config.stick_movement(keys.clamp_length_max(1.0));
+                assert!(
+                    (movement.length() - 1.0).abs() < 1e-5,
+                    "{keys} gave {movement}"
+                );
+            }
+            // and an overshooting stick is capped there too
+            let movement = config.stick_movement(Vec2::new(1.0, 1.0) * 1.2);
+            assert!(movement.length() <= 1.0 + 1e-5);
+        }
+        assert_eq!(
+            config(StickCurve::Linear).stick_movement(Vec2::Y),
+            Vec3::NEG_Z
+        );
+    }
+}
+
+/// player input actions read through leafwing-input-manager
+#[cfg(feature = "leafwing")]
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
+pub(crate) enum PlayerAction {
+    #[actionlike(DualAxis)]
+    Move,
+    /// mouse look, a distance per frame
+    #[actionlike(DualAxis)]
+    Look,
+    /// right stick look, a rate scaled by delta time
+    #[actionlike(DualAxis)]
+    StickLook,
+    Jump,
+    Sprint,
+    Crouch,
+    Interact,
+    Dash,
+}
+
+#[cfg(feature = "leafwing")]
+impl PlayerAction {
+    /// keyboard and mouse plus any gamepad, matching the direct-input defaults
+    pub(crate) fn default_input_map() -> InputMap<Self> {
+        InputMap::default()
+            .with_dual_axis(Self::Move, KeyboardVirtualDPad::WASD)
+            .with_dual_axis(Self::Move, KeyboardVirtualDPad::ARROW_KEYS)
+            .with_dual_axis(Self::Move, GamepadStick::LEFT)
+            .with_dual_axis(Self::Look, MouseMove::default())
+            .with_dual_axis(Self::StickLook, GamepadStick::RIGHT)
+            .with(Self::Jump, KeyCode::Space)
+            .with(Self::Jump, GamepadButtonType::South)
+            .with(Self::Sprint, KeyCode::ShiftLeft)
+            .with(Self::Sprint, GamepadButtonType::LeftThumb)
+            .with(Self::Crouch, KeyCode::ControlLeft)
+            .with(Self::Crouch, GamepadButtonType::East)
+            .with(Self::Interact, KeyCode::KeyF)
+            .with(Self::Interact, GamepadButtonType::West)
+            .with(Self::Dash, KeyCode::AltLeft)
+            .with(Self::Dash, GamepadButtonType::RightTrigger)
+    }
+}
+
+/// apply a radial deadzone to a stick, rescaling the rest of its range to 0..1
+fn radial_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+
+    let scaled = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    stick / length * scaled
+}
+
+/// touch control tuning
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TouchConfig {
+    /// radians of rotation per logical pixel dragged on the right half of the screen.
+    /// separate from the mouse sensitivity, since touch deltas differ wildly from mouse counts
+    pub(crate) look_sensitivity: f32,
+    /// logical pixels the joystick knob travels for full speed
+    joystick_radius: f32,
+}
+
+impl Default for TouchConfig {
+    fn default() -> Self {
+        Self {
+            look_sensitivity: 0.008,
+            joystick_radius: 60.0,
+        }
+    }
+}
+
+/// size of the touch jump button, in logical pixels
+const TOUCH_JUMP_SIZE: f32 = 96.0;
+
+/// gap between the touch jump button and the bottom-right corner of the window
+const TOUCH_JUMP_MARGIN: f32 = 32.0;
+
+/// touch input for the keyboard and mouse player, gathered each frame
+#[derive(Resource)]
+pub(crate) struct TouchControls {
+    /// whether the touch ui is shown; set by PlayerControllerPlugin or the first touch
+    pub(crate) enabled: bool,
+    /// whether touch produced input more recently than the keyboard or mouse
+    pub(crate) active: bool,
+    /// finger driving the joystick, and where it touched down
+    pub(crate) joystick: Option<(u64, Vec2)>,
+    pub(crate) look_finger: Option<u64>,
+    pub(crate) jump_finger: Option<u64>,
+    /// joystick deflection, x right and y forward, with length up to 1
+    pub(crate) movement: Vec2,
+    /// look dragged since the last physics tick in radians, before invert-y; gather_input
+    /// takes it
+    pub(crate) look: Vec2,
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            active: false,
+            joystick: None,
+            look_finger: None,
+            jump_finger: None,
+            movement: Vec2::ZERO,
+            look: Vec2::ZERO,
+        }
+    }
+}
+
+/// the touch jump button's area in window coordinates
+fn touch_jump_rect(window_size: Vec2) -> Rect {
+    let max = window_size - Vec2::splat(TOUCH_JUMP_MARGIN);
+    Rect::from_corners(max - Vec2::splat(TOUCH_JUMP_SIZE), max)
+}
+
+/// marks the touch joystick's base, which appears where the finger lands
+#[derive(Component)]
+pub(crate) struct TouchJoystick;
+
+/// marks the touch joystick's knob
+#[derive(Component)]
+pub(crate) struct TouchJoystickKnob;
+
+/// marks the touch jump button
+#[derive(Component)]
+pub(crate) struct TouchJumpButton;
+
+/// one tick of movement input, gathered from whichever source is active. players with neither
+/// KeyboardMouseInput nor GamepadInput are driven by their own MovementIntent component instead,
+/// e.g. from AI, or else by their InputSnapshot
+#[derive(Component, Clone, Default, Debug)]
+pub struct MovementIntent {
+    /// direction relative to the player, with length up to 1 scaling the speed
+    pub direction: Vec3,
+    pub jump: bool,
+    pub sprint: bool,
+    pub crouch: bool,
+    pub interact: bool,
+    pub dash: bool,
+}
+
+impl MovementIntent {
+    /// read bound keys, or None when none are pressed so another source can take over
+    #[cfg(not(feature = "leafwing"))]
+    fn from_keyboard(keyboard: &Keys, bindings: &KeyBindings) -> Option<Self> {
+        let pressed = |action| bindings.pressed(keyboard, action);
+        if !Action::MOVEMENT.into_iter().any(pressed) {
+            return None;
+        }
+
+        let mut direction = Vec3::ZERO;
+        if pressed(Action::MoveForward) {
+            direction -= Vec3::Z;
+        }
+        if pressed(Action::MoveBack) {
+            direction += Vec3::Z;
+        }
+        if pressed(Action::MoveLeft) {
+            direction -= Vec3::X;
+        }
+        if pressed(Action::MoveRight) {
+            direction += Vec3::X;
+        }
+
+        Some(Self {
+            direction: direction.normalize_or_zero(),
+            jump: pressed(Action::Jump),
+            sprint: pressed(Action::Sprint),
+            crouch: pressed(Action::Crouch),
+            interact: pressed(Action::Interact),
+            dash: pressed(Action::Dash),
+        })
+    }
+
+    /// read the move, jump, sprint and crouch actions, with the movement stick's deadzone and
+    /// curve applied. digital keys map to full magnitude
+    #[cfg(feature = "leafwing")]
+    fn from_actions(actions: &ActionState<PlayerAction>, config: &GamepadConfig) -> Self {
+        let stick = actions.axis_pair(&PlayerAction::Move).clamp_length_max(1.0);
+
+        Self {
+            direction: config.stick_movement(stick),
+            jump: actions.pressed(&PlayerAction::Jump),
+            sprint: actions.pressed(&PlayerAction::Sprint),
+            crouch: actions.pressed(&PlayerAction::Crouch),
+            interact: actions.pressed(&PlayerAction::Interact),
+            dash: actions.pressed(&PlayerAction::Dash),
+        }
+    }
+
+    /// read the touch joystick and jump button, or None while the keyboard and mouse were used
+    /// last
+    fn from_touch(controls: &TouchControls) -> Option<Self> {
+        controls.active.then(|| Self {
+            direction: Vec3::new(controls.movement.x, 0.0, -controls.movement.y),
+            jump: controls.jump_finger.is_some(),
+            ..default()
+        })
+    }
+
+    /// read the left stick, south button (jump), left stick click (sprint), east button
+    /// (crouch), west button (interact) and right trigger (dash)
+    fn from_gamepad(
+        gamepad: Gamepad,
+        axes: &Axis<GamepadAxis>,
+        buttons: &ButtonInput<GamepadButton>,
+        config: &GamepadConfig,
+    ) -> Self {
+        let axis = |axis_type| {
+            axes.get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0)
+        };
+        let stick = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+
+        Self {
+            direction: config.stick_movement(stick),
+            jump: buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::South)),
+            sprint: buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::LeftThumb)),
+            crouch: buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::East)),
+            interact: buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::West)),
+            dash: buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::RightTrigger)),
+        }
+    }
+}
+
+/// one physics tick of a player's input, captured before anything acts on it: translate_input
+/// turns it into the tick's movement and player_rotation turns the player by its look.
+/// gather_input fills it in at the start of each tick for players driven by a device. for the
+/// rest, set it from your own systems (e.g. from the network) every tick, before
+/// TnuaUserControlsSystemSet in FixedUpdate; it's acted on as it stands, look included, until
+/// it's set again. recording these and setting them again in the same order replays the input
+/// exactly
+#[derive(Component, Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InputSnapshot {
+    /// movement axes, x right and y forward, with length up to 1 scaling the speed
+    pub movement: Vec2,
+    pub jump: bool,
+    pub sprint: bool,
+    pub crouch: bool,
+    pub interact: bool,
+    pub dash: bool,
+    /// look since the last tick in radians, x turning right and y looking down, after invert-y
+    pub look: Vec2,
+}
+
+impl InputSnapshot {
+    pub fn new(intent: &MovementIntent, look: Vec2) -> Self {
+        Self {
+            movement: Vec2::new(intent.direction.x, -intent.direction.z),
+            jump: intent.jump,
+            sprint: intent.sprint,
+            crouch: intent.crouch,
+            interact: intent.interact,
+            dash: intent.dash,
+            look,
+        }
+    }
+
+    /// the movement it asks for
+    pub fn intent(&self) -> MovementIntent {
+        MovementIntent {
+            direction: Vec3::new(self.movement.x, 0.0, -self.movement.y),
+            jump: self.jump,
+            sprint: self.sprint,
+            crouch: self.crouch,
+            interact: self.interact,
+            dash: self.dash,
+        }
+    }
+}
+
+/// a button-like player action, sent as ActionStarted and ActionEnded events when it's pressed
+/// and released
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum InputAction {
+    Jump,
+    Sprint,
+    Crouch,
+    Interact,
+    Dash,
+}
+
+impl InputAction {
+    pub const ALL: [Self; 5] = [
+        Self::Jump,
+        Self::Sprint,
+        Self::Crouch,
+        Self::Interact,
+        Self::Dash,
+    ];
+
+    /// whether the action is held in `intent`
+    pub fn held(self, intent: &MovementIntent) -> bool {
+        match self {
+            Self::Jump => intent.jump,
+            Self::Sprint => intent.sprint,
+            Self::Crouch => intent.crouch,
+            Self::Interact => intent.interact,
+            Self::Dash => intent.dash,
+        }
+    }
+}
+
+/// event sent on the physics tick a player's action is pressed, from whichever input drives
+/// them
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActionStarted {
+    pub player: Entity,
+    pub action: InputAction,
+}
+
+/// event sent on the physics tick a player's action is released
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActionEnded {
+    pub player: Entity,
+    pub action: InputAction,
+}
+
+/// the input each player acts on this tick, gathered by translate_input
+#[derive(Component, Clone, Default)]
+pub(crate) struct ResolvedIntent(pub(crate) MovementIntent);
+
+/// where input recordings are saved to and replayed from
+const RECORDING_PATH: &str = "recording.ron";
+
+/// the keyboard and mouse player's physical state at either end of a recording
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct PlayerSnapshot {
+    position: [f32; 3],
+    velocity: [f32; 3],
+    /// body yaw, in radians
+    yaw: f32,
+    /// camera pitch, in radians
+    pitch: f32,
+}
+
+impl PlayerSnapshot {
+    fn new(up: &WorldUp, transform: &Transform, velocity: &Velocity, pitch: f32) -> Self {
+        Self {
+            position: transform.translation.to_array(),
+            velocity: velocity.linvel.to_array(),
+            yaw: up.yaw(transform),
+            pitch,
+        }
+    }
+}
+
+/// recorded input, replayable from the same starting state
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct InputRecording {
+    start: PlayerSnapshot,
+    /// the player's InputSnapshot each fixed tick
+    frames: Vec<InputSnapshot>,
+    /// where recording stopped, to compare replays against
+    end: PlayerSnapshot,
+}
+
+impl InputRecording {
+    fn load(path: &str) -> Option<Self> {
+        let text = std::fs::read_to_string(path)
+            .inspect_err(|err| warn!("could not read {path}: {err}"))
+            .ok()?;
+        ron::from_str(&text)
+            .inspect_err(|err| warn!("could not read {path}: {err}"))
+            .ok()
+    }
+
+    fn save(&self, path: &str) {
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| err.to_string())
+            .and_then(|text| std::fs::write(path, text).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => info!("saved {} ticks of input to {path}", self.frames.len()),
+            Err(err) => warn!("could not save {path}: {err}"),
+        }
+    }
+}
+
+/// records the keyboard and mouse player's InputSnapshot each fixed tick, or writes recorded
+/// ones back in place of live devices
+#[derive(Resource, Default)]
+pub(crate) enum InputRecorder {
+    #[default]
+    Idle,
+    Recording(InputRecording),
+    Playing {
+        recording: InputRecording,
+        /// index of the next snapshot to replay
+        next: usize,
+    },
+}
+
+impl InputRecorder {
+    fn is_playing(&self) -> bool {
+        matches!(self, Self::Playing { .. })
+    }
+
+    /// add the snapshot the tick acted on to the recording
+    fn record_tick(&mut self, snapshot: &InputSnapshot) {
+        if let Self::Recording(recording) = self {
+            recording.frames.push(*snapshot);
+        }
+    }
+}
+
+/// recording the keyboard and mouse player's input and replaying it, with
+/// Action::ToggleRecording and Action::PlayRecording
+#[derive(Clone, Default)]
+pub struct InputRecorderPlugin;
+
+impl Plugin for InputRecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputRecorder>()
+            .add_systems(
+                FixedUpdate,
+                step_input_playback
+                    .in_set(TnuaUserControlsSystemSet)
+                    .after(gather_input)
+                    .before(translate_input)
+                    .run_if(not(resource_exists::<PhotoMode>))
+                    .run_if(menu_closed),
+            )
+            .add_systems(Update, input_recorder_keys.run_if(menu_closed));
+    }
+}
+
+/// the keyboard and mouse player's leafwing mouse look since the last physics tick. leafwing's
+/// action state only holds the latest frame's, so it's added up each frame for gather_input
+#[cfg(feature = "leafwing")]
+#[derive(Resource, Default)]
+pub(crate) struct LeafwingLook(Vec2);
+
+#[cfg(feature = "leafwing")]
+pub(crate) fn accumulate_leafwing_look(
+    actions: Query<&ActionState<PlayerAction>, With<KeyboardMouseInput>>,
+    mut pending: ResMut<LeafwingLook>,
+) {
+    if let Ok(actions) = actions.get_single() {
+        pending.0 += actions.axis_pair(&PlayerAction::Look);
+    }
+}
+
+/// point the keyboard and mouse player's input map at the spare gamepad, so it never reads a
+/// gamepad owned by a split-screen player
+#[cfg(feature = "leafwing")]
+pub(crate) fn assign_spare_gamepad(
+    sticks: GamepadSticks,
+    mut maps: Query<&mut InputMap<PlayerAction>, With<KeyboardMouseInput>>,
+) {
+    // leafwing falls back to the first gamepad when none is set, so while every gamepad is
+    // taken, park the map on an id that never connects
+    let gamepad = sticks.spare().unwrap_or(Gamepad::new(usize::MAX));
+    for mut map in &mut maps {
+        if map.gamepad() != Some(gamepad) {
+            map.set_gamepad(gamepad);
+        }
+    }
+}
+
+/// what decides whether and how fast mouse look turns the keyboard and mouse player. it's all
+/// PlayerCameraPlugin's, so without it the mouse doesn't look
+#[derive(SystemParam)]
+pub(crate) struct MouseLook<'w> {
+    locked: Option<Res<'w, MouseLocked>>,
+    suspended: Option<Res<'w, CursorSuspended>>,
+    zoom: Option<Res<'w, Zoom>>,
+    zoom_config: Option<Res<'w, ZoomConfig>>,
+    look_target: Option<Res<'w, CameraLookTarget>>,
+    look_assist: Option<Res<'w, LookAssistConfig>>,
+}
+
+impl MouseLook<'_> {
+    /// whether the mouse is locked to looking around
+    fn active(&self) -> bool {
+        self.locked.as_ref().is_some_and(|locked| locked.0)
+            && !self.suspended.as_ref().is_some_and(|suspended| suspended.0)
+    }
+
+    fn sensitivity_scale(&self, look: &LookConfig) -> f32 {
+        match (&self.zoom, &self.zoom_config) {
+            (Some(zoom), Some(config)) => zoom.sensitivity_scale(look, config),
+            _ => 1.0,
+        }
+    }
+
+    /// whether CameraLookTarget has the view to itself
+    fn held_by_target(&self) -> bool {
+        let allow_input = self
+            .look_assist
+            .as_ref()
+            .is_some_and(|assist| assist.allow_input);
+        self.look_target
+            .as_ref()
+            .is_some_and(|target| target.0.is_some())
+            && !allow_input
+    }
+}
+
+/// capture the InputSnapshot of each player driven by a device for the physics tick, with the
+/// look since the last tick, so one snapshot is always one tick's input however many ticks a
+/// frame runs
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn gather_input(
+    #[cfg(not(feature = "leafwing"))] keyboard: Keys,
+    #[cfg(not(feature = "leafwing"))] bindings: Res<KeyBindings>,
+    #[cfg(not(feature = "leafwing"))] mut er_motion: EventReader<MouseMotion>,
+    #[cfg(feature = "leafwing")] actions: Query<
+        &ActionState<PlayerAction>,
+        With<KeyboardMouseInput>,
+    >,
+    #[cfg(feature = "leafwing")] mut leafwing_look: ResMut<LeafwingLook>,
+    time: Res<Time>,
+    scale: Res<TimeScale>,
+    sticks: GamepadSticks,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    mut touch: ResMut<TouchControls>,
+    look: Res<LookConfig>,
+    mouse_look: MouseLook,
+    recorder: Option<Res<InputRecorder>>,
+    mut smoothing: Local<MouseSmoothing>,
+    mut query: Query<
+        (
+            &mut InputSnapshot,
+            Option<&GamepadInput>,
+            Has<KeyboardMouseInput>,
+        ),
+        With<Player>,
+    >,
+) {
+    #[cfg(not(feature = "leafwing"))]
+    let spare_gamepad = sticks.spare();
+    let gamepad =
+        |gamepad| MovementIntent::from_gamepad(gamepad, &sticks.axes, &buttons, &sticks.config);
+    // the fixed timestep shrinks with TimeScale, but look keeps real time
+    let dt = time.delta_seconds() / scale.clamped();
+
+    #[cfg(not(feature = "leafwing"))]
+    let mouse = er_motion.read().map(|ev| ev.delta).sum::<Vec2>();
+    #[cfg(feature = "leafwing")]
+    let mouse = std::mem::take(&mut leafwing_look.0);
+    let mouse = if mouse_look.active() {
+        look.mouse_delta(look.smooth(mouse, &mut smoothing, dt))
+    } else {
+        smoothing.reset();
+        Vec2::ZERO
+    };
+    #[cfg(not(feature = "leafwing"))]
+    let spare_stick = spare_gamepad.map_or(Vec2::ZERO, |gamepad| sticks.look_delta(gamepad, dt));
+    #[cfg(feature = "leafwing")]
+    let spare_stick = actions.get_single().map_or(Vec2::ZERO, |actions| {
+        let stick = actions.axis_pair(&PlayerAction::StickLook);
+        sticks.config.stick_look(stick, dt)
+    });
+    let touch_look = std::mem::take(&mut touch.look);
+    let invert = Vec2::new(1.0, if look.invert_y { -1.0 } else { 1.0 });
+
+    for (mut snapshot, gamepad_input, keyboard_mouse) in &mut query {
+        let (intent, delta) = if let Some(input) = gamepad_input {
+            match input.gamepad(&sticks.gamepads) {
+                Some(pad) => (gamepad(pad), sticks.look_delta(pad, dt)),
+                None => default(),
+            }
+        } else if !keyboard_mouse {
+            // set by their own systems
+            continue;
+        } else if recorder
+            .as_ref()
+            .is_some_and(|recorder| recorder.is_playing())
+        {
+            // step_input_playback writes the recorded snapshot instead
+            continue;
+        } else {
+            let intent = if let Some(intent) = MovementIntent::from_touch(&touch) {
+                intent
+            } else {
+                // the keyboard wins while any bound key is held, otherwise fall back to the
+                // first gamepad not taken by a split-screen player
+                #[cfg(not(feature = "leafwing"))]
+                let intent = MovementIntent::from_keyboard(&keyboard, &bindings)
+                    .or_else(|| spare_gamepad.map(gamepad));
+                // leafwing merges keyboard and gamepad input itself
+                #[cfg(feature = "leafwing")]
+                let intent = actions
+                    .get_single()
+                    .ok()
+                    .map(|actions| MovementIntent::from_actions(actions, &sticks.config));
+                intent.unwrap_or_default()
+            };
+            // the mouse player may also use the spare gamepad's right stick; both add into the
+            // same delta, so they share the pitch clamp and invert-y
+            let delta = if mouse_look.held_by_target() {
+                Vec2::ZERO
+            } else {
+                (mouse + spare_stick + touch_look) * mouse_look.sensitivity_scale(&look)
+            };
+            (intent, delta)
+        };
+        *snapshot = InputSnapshot::new(&intent, delta * invert);
+    }
+}
+
+/// resolve each player's input for the tick from their InputSnapshot or their own
+/// MovementIntent, and send ActionStarted and ActionEnded for the actions pressed and released
+/// since the last tick
+#[allow(clippy::type_complexity)]
+pub(crate) fn translate_input(
+    mut recorder: Option<ResMut<InputRecorder>>,
+    playback: Option<Res<CameraPathPlayback>>,
+    mut ew_started: EventWriter<ActionStarted>,
+    mut ew_ended: EventWriter<ActionEnded>,
+    mut query: Query<
+        (
+            Entity,
+            &mut ResolvedIntent,
+            &InputSnapshot,
+            Has<GamepadInput>,
+            Has<KeyboardMouseInput>,
+            Option<&MovementIntent>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (player, mut resolved, snapshot, gamepad, keyboard_mouse, own_intent) in &mut query {
+        let own_intent = own_intent.filter(|_| !gamepad && !keyboard_mouse);
+        // ignore input while a camera path is playing, so players stand still
+        let intent = if playback.is_some() {
+            MovementIntent::default()
+        } else if let Some(intent) = own_intent {
+            intent.clone()
+        } else {
+            snapshot.intent()
+        };
+        if let (true, Some(recorder)) = (keyboard_mouse, recorder.as_mut()) {
+            recorder.record_tick(snapshot);
+        }
+
+        for action in InputAction::ALL {
+            match (action.held(&resolved.0), action.held(&intent)) {
+                (false, true) => {
+                    ew_started.send(ActionStarted { player, action });
+                }
+                (true, false) => {
+                    ew_ended.send(ActionEnded { player, action });
+                }
+                _ => {}
+            }
+        }
+        resolved.0 = intent;
+    }
+}
+
+/// listen for Action::ToggleRecording and Action::PlayRecording to start and stop recording
+/// or replaying the keyboard and mouse player's input
+#[allow(clippy::type_complexity)]
+fn input_recorder_keys(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut recorder: ResMut<InputRecorder>,
+    up: Res<WorldUp>,
+    player: Query<(&Transform, &Velocity), (With<Player>, With<KeyboardMouseInput>)>,
+    camera: Query<&PlayerCamera, With<KeyboardMouseInput>>,
+) {
+    let (Ok((transform, velocity)), Ok(camera)) = (player.get_single(), camera.get_single()) else {
+        return;
+    };
+    let snapshot = || PlayerSnapshot::new(&up, transform, velocity, camera.0);
+
+    if bindings.just_pressed(&keyboard, Action::ToggleRecording) {
+        match std::mem::take(&mut *recorder) {
+            InputRecorder::Idle => {
+                info!("recording input");
+                *recorder = InputRecorder::Recording(InputRecording {
+                    start: snapshot(),
+                    ..default()
+                });
+            }
+            InputRecorder::Recording(mut recording) => {
+                recording.end = snapshot();
+                recording.save(RECORDING_PATH);
+            }
+            playing => *recorder = playing,
+        }
+    } else if bindings.just_pressed(&keyboard, Action::PlayRecording) {
+        match *recorder {
+            InputRecorder::Idle => {
+                if let Some(recording) = InputRecording::load(RECORDING_PATH) {
+                    info!("replaying {} ticks of input", recording.frames.len());
+                    *recorder = InputRecorder::Playing { recording, next: 0 };
+                }
+            }
+            InputRecorder::Playing { .. } => *recorder = InputRecorder::Idle,
+            InputRecorder::Recording(_) => {}
+        }
+    }
+}
+
+/// step a replay by one fixed tick: restore the recording's start state on the first tick,
+/// then write each recorded InputSnapshot over the keyboard and mouse player's live one, for
+/// translate_input and player_rotation to act on as if it had just been gathered
+#[allow(clippy::type_complexity)]
+fn step_input_playback(
+    mut recorder: ResMut<InputRecorder>,
+    up: Res<WorldUp>,
+    mut player: Query<
+        (&mut Transform, &mut Velocity, &mut InputSnapshot),
+        (With<Player>, With<KeyboardMouseInput>),
+    >,
+    mut camera: Query<(&mut PlayerCamera, &mut CameraYaw), With<KeyboardMouseInput>>,
+) {
+    let InputRecorder::Playing { recording, next } = &mut *recorder else {
+        return;
+    };
+    let (Ok((mut transform, mut velocity, mut snapshot)), Ok((mut camera, mut yaw))) =
+        (player.get_single_mut(), camera.get_single_mut())
+    else {
+        return;
+    };
+
+    if *next == 0 {
+        let start = &recording.start;
+        transform.translation = Vec3::from_array(start.position);
+        transform.rotation = up.rotation(start.yaw);
+        *velocity = Velocity::linear(Vec3::from_array(start.velocity));
+        camera.0 = start.pitch;
+        yaw.0 = 0.0;
+    }
+
+    let Some(&frame) = recording.frames.get(*next) else {
+        let end = Vec3::from_array(recording.end.position);
+        info!(
+            "replay finished {:.4} units from where recording stopped",
+            transform.translation.distance(end)
+        );
+        *snapshot = default();
+        *recorder = InputRecorder::Idle;
+        return;
+    };
+    *next += 1;
+    *snapshot = frame;
+}
+
+#[cfg(test)]
+mod input_recorder_tests {
+    use std::time::Duration;
+
+    use bevy::{
+        ecs::system::RunSystemOnce, input::InputPlugin, scene::ScenePlugin,
+        state::app::StatesPlugin, time::TimeUpdateStrategy,
+    };
+
+    use super::*;
+    use crate::{PlayerControllerPlugin, PlayerEntity, PHYSICS_HZ};
+
+    /// a headless player settled on a ground slab, one physics tick per update
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+            InputPlugin,
+            StatesPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / PHYSICS_HZ,
+        )))
+        .add_plugins((PlayerControllerPlugin::default(), InputRecorderPlugin))
+        .add_systems(Startup, |mut commands: Commands| {
+            commands
+                .spawn(TransformBundle::default())
+                .insert(RigidBody::Fixed)
+                .insert(Collider::cuboid(20.0, 0.1, 20.0));
+        });
+        tick(&mut app, 180);
+        app
+    }
+
+    fn tick(app: &mut App, ticks: usize) {
+        for _ in 0..ticks {
+            app.update();
+        }
+    }
+
+    fn keys(app: &mut App) -> Mut<'_, ButtonInput<KeyCode>> {
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>()
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn player_state(app: &mut App) -> PlayerSnapshot {
+        app.world_mut().run_system_once(
+            |up: Res<WorldUp>,
+             player: Query<(&Transform, &Velocity), (With<Player>, With<KeyboardMouseInput>)>,
+             camera: Query<&PlayerCamera, With<KeyboardMouseInput>>| {
+                let (transform, velocity) = player.single();
+                PlayerSnapshot::new(&up, transform, velocity, camera.single().0)
+            },
+        )
+    }
+
+    #[test]
+    fn records_a_snapshot_a_tick_and_replays_to_the_same_place_after_a_round_trip() {
+        let mut app = app();
+        let start = player_state(&mut app);
+        *app.world_mut().resource_mut::<InputRecorder>() =
+            InputRecorder::Recording(InputRecording { start, ..default() });
+
+        // a run with a turn and a jump, then standing still again
+        keys(&mut app).press(KeyCode::KeyW);
+        tick(&mut app, 20);
+        keys(&mut app).press(KeyCode::Space);
+        keys(&mut app).press(KeyCode::KeyD);
+        tick(&mut app, 10);
+        keys(&mut app).release(KeyCode::Space);
+        tick(&mut app, 30);
+        keys(&mut app).release_all();
+        tick(&mut app, 60);
+
+        let InputRecorder::Recording(mut recording) =
+            std::mem::take(&mut *app.world_mut().resource_mut::<InputRecorder>())
+        else {
+            panic!("stopped recording");
+        };
+        recording.end = player_state(&mut app);
+        assert_eq!(recording.frames.len(), 120);
+        assert!(recording.frames[25].jump);
+        assert!(recording.frames[25].movement.x > 0.0);
+        let end = Vec3::from_array(recording.end.position);
+        let from = Vec3::from_array(recording.start.position);
+        assert!(end.distance(from) > 3.0, "only ran to {end}");
+
+        let path = std::env::temp_dir().join(format!("recording-{}.ron", std::process::id()));
+        let path = path.to_str().unwrap();
+        recording.save(path);
+        let loaded = InputRecording::load(path).expect("recording didn't load back");
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.frames, recording.frames);
+        assert_eq!(loaded.start.position, recording.start.position);
+        assert_eq!(loaded.end.position, recording.end.position);
+
+        // replayed from somewhere else, live keys are ignored and the player retraces the run
+        let player = app.world().resource::<PlayerEntity>().0;
+        app.world_mut()
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation += Vec3::new(4.0, 0.0, 4.0);
+        keys(&mut app).press(KeyCode::KeyS);
+        *app.world_mut().resource_mut::<InputRecorder>() = InputRecorder::Playing {
+            recording: loaded,
+            next: 0,
+        };
+        tick(&mut app, 120);
+        assert!(app.world().resource::<InputRecorder>().is_playing());
+        tick(&mut app, 1);
+        assert!(!app.world().resource::<InputRecorder>().is_playing());
+
+        // the float spring picks up a hair differently after being put back at the start
+        let replayed = app.world().get::<Transform>(player).unwrap().translation;
+        assert!(
+            replayed.distance(end) < 0.01,
+            "replayed to {replayed}, recorded {end}"
+        );
+    }
+}
+
+/// track fingers on the joystick (left half), look (right half) and jump regions of the screen
+pub(crate) fn update_touch_controls(
+    touches: Res<Touches>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut er_motion: EventReader<MouseMotion>,
+    config: Res<TouchConfig>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut controls: ResMut<TouchControls>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let size = Vec2::new(window.width(), window.height());
+
+    for touch in touches.iter_just_pressed() {
+        controls.enabled = true;
+        let position = touch.position();
+        if touch_jump_rect(size).contains(position) {
+            if controls.jump_finger.is_none() {
+                controls.jump_finger = Some(touch.id());
+            }
+        } else if position.x < size.x / 2.0 {
+            if controls.joystick.is_none() {
+                controls.joystick = Some((touch.id(), position));
+            }
+        } else if controls.look_finger.is_none() {
+            controls.look_finger = Some(touch.id());
+        }
+    }
+
+    for touch in touches
+        .iter_just_released()
+        .chain(touches.iter_just_canceled())
+    {
+        let finger = Some(touch.id());
+        if controls.joystick.map(|(id, _)| id) == finger {
+            controls.joystick = None;
+        }
+        if controls.look_finger == finger {
+            controls.look_finger = None;
+        }
+        if controls.jump_finger == finger {
+            controls.jump_finger = None;
+        }
+    }
+
+    controls.movement = controls
+        .joystick
+        .and_then(|(id, anchor)| touches.get_pressed(id).map(|touch| (touch, anchor)))
+        .map_or(Vec2::ZERO, |(touch, anchor)| {
+            // window y points down, joystick y forward
+            let offset = (touch.position() - anchor) / config.joystick_radius;
+            Vec2::new(offset.x, -offset.y).clamp_length_max(1.0)
+        });
+    let look = controls
+        .look_finger
+        .and_then(|id| touches.get_pressed(id))
+        .map_or(Vec2::ZERO, |touch| touch.delta() * config.look_sensitivity);
+    controls.look += look;
+
+    // whichever of touch or keyboard and mouse produced input last takes over. mouse input
+    // is ignored while fingers are down, since some platforms emulate it from touches
+    let mouse_used = er_motion.read().count() > 0 || mouse_buttons.get_just_pressed().len() > 0;
+    if touches.any_just_pressed() {
+        controls.active = true;
+    } else if keyboard.get_just_pressed().len() > 0
+        || (mouse_used && touches.iter().next().is_none())
+    {
+        controls.active = false;
+    }
+}
+
+/// spawn the touch joystick and jump button once touch controls are enabled
+pub(crate) fn spawn_touch_ui(
+    mut commands: Commands,
+    controls: Res<TouchControls>,
+    config: Res<TouchConfig>,
+    mut spawned: Local<bool>,
+) {
+    if *spawned || !controls.enabled {
+        return;
+    }
+    *spawned = true;
+
+    let radius = config.joystick_radius;
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Px(radius * 2.0),
+                height: Val::Px(radius * 2.0),
+                ..default()
+            },
+            background_color: Color::WHITE.with_alpha(0.15).into(),
+            border_radius: BorderRadius::MAX,
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(TouchJoystick)
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(radius),
+                        height: Val::Px(radius),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.with_alpha(0.4).into(),
+                    border_radius: BorderRadius::MAX,
+                    ..default()
+                })
+                .insert(TouchJoystickKnob);
+        });
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(TOUCH_JUMP_MARGIN),
+                bottom: Val::Px(TOUCH_JUMP_MARGIN),
+                width: Val::Px(TOUCH_JUMP_SIZE),
+                height: Val::Px(TOUCH_JUMP_SIZE),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::WHITE.with_alpha(0.15).into(),
+            border_radius: BorderRadius::MAX,
+            ..default()
+        })
+        .insert(TouchJumpButton)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Jump", default()));
+        });
+}
+
+/// move the joystick to its finger and highlight the jump button while held
+pub(crate) fn update_touch_ui(
+    controls: Res<TouchControls>,
+    config: Res<TouchConfig>,
+    mut joystick: Query<(&mut Style, &mut Visibility), With<TouchJoystick>>,
+    mut knob: Query<&mut Style, (With<TouchJoystickKnob>, Without<TouchJoystick>)>,
+    mut jump: Query<&mut BackgroundColor, With<TouchJumpButton>>,
+) {
+    let radius = config.joystick_radius;
+    for (mut style, mut visibility) in &mut joystick {
+        match controls.joystick {
+            Some((_, anchor)) => {
+                style.left = Val::Px(anchor.x - radius);
+                style.top = Val::Px(anchor.y - radius);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    // the knob is half the base's size, centered at rest
+    for mut style in &mut knob {
+        style.left = Val::Px(radius / 2.0 + controls.movement.x * radius);
+        style.top = Val::Px(radius / 2.0 - controls.movement.y * radius);
+    }
+
+    let alpha = if controls.jump_finger.is_some() {
+        0.4
+    } else {
+        0.15
+    };
+    for mut color in &mut jump {
+        *color = Color::WHITE.with_alpha(alpha).into();
+    }
+}
@@ -0,0 +1,272 @@
+//! buttons, pressure plates and the sliding doors they open. links between them are entity
+//! references set up where they're spawned
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    trigger::update_trigger_volumes, ActionStarted, CameraRig, InputAction, Player, PlayerCamera,
+    TriggerEnter, TriggerExit,
+};
+
+/// what an Activate does to its target
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Activation {
+    /// switch it over, e.g. a door opening if it's closed or closing if it's open
+    Toggle,
+    On,
+    Off,
+}
+
+/// event sent by a UseButton or PressurePlate (the `source`) to each entity linked to it
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Activate {
+    pub target: Entity,
+    pub source: Entity,
+    pub activation: Activation,
+}
+
+/// a solid collider that toggles its targets when a player looks at it from within reach and
+/// presses Action::Interact
+#[derive(Component, Clone, Default, Debug)]
+pub struct UseButton {
+    pub targets: Vec<Entity>,
+}
+
+impl UseButton {
+    pub fn new(targets: impl IntoIterator<Item = Entity>) -> Self {
+        Self {
+            targets: targets.into_iter().collect(),
+        }
+    }
+}
+
+/// a trigger volume that turns its targets on while anything that sets off trigger volumes is
+/// in it, and off once they've all left. spawn it with a Collider, a Sensor and a TriggerVolume
+#[derive(Component, Clone, Default, Debug)]
+pub struct PressurePlate {
+    pub targets: Vec<Entity>,
+    /// how many are on it
+    occupants: usize,
+}
+
+impl PressurePlate {
+    pub fn new(targets: impl IntoIterator<Item = Entity>) -> Self {
+        Self {
+            targets: targets.into_iter().collect(),
+            occupants: 0,
+        }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.occupants > 0
+    }
+}
+
+/// a RigidBody::KinematicPositionBased that slides `offset` from where it's spawned to open
+/// when activated, and back to close. it eases in and out, turns back smoothly if activated
+/// again on the way, and reopens rather than closing on a player in the doorway
+#[derive(Component, Clone, Debug)]
+pub struct SlidingDoor {
+    /// from closed to open
+    pub offset: Vec3,
+    /// seconds to open or close all the way
+    pub duration: f32,
+    /// whether it's opening (or open), rather than closing (or closed)
+    pub open: bool,
+    /// where it's closed, from its transform when it first moves
+    closed: Option<Vec3>,
+    /// how far open it is, from 0 to 1 before easing
+    progress: f32,
+}
+
+impl SlidingDoor {
+    /// a closed door
+    pub fn new(offset: Vec3, duration: f32) -> Self {
+        Self {
+            offset,
+            duration,
+            open: false,
+            closed: None,
+            progress: 0.0,
+        }
+    }
+
+    /// how far open it is, from 0 to 1
+    pub fn openness(&self) -> f32 {
+        smoothstep(self.progress)
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// buttons, pressure plates and sliding doors. needs TriggerPlugin
+#[derive(Clone, Default)]
+pub struct InteractPlugin;
+
+impl Plugin for InteractPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Activate>()
+            .add_systems(FixedUpdate, slide_doors.before(PhysicsSet::SyncBackend))
+            .add_systems(
+                FixedUpdate,
+                (use_buttons, press_plates, activate_doors)
+                    .chain()
+                    .after(update_trigger_volumes),
+            );
+    }
+}
+
+/// furthest a player can be from a UseButton and still press it
+const INTERACT_REACH: f32 = 2.5;
+
+/// gap a closing door keeps from a player in its way
+const DOOR_MARGIN: f32 = 0.05;
+
+/// press the UseButtons players interact with. they aim along their camera from eye height, or
+/// straight ahead without one
+#[allow(clippy::type_complexity)]
+pub(crate) fn use_buttons(
+    mut er_started: EventReader<ActionStarted>,
+    rapier_context: Res<RapierContext>,
+    rig: Res<CameraRig>,
+    players: Query<(&Transform, Option<&Children>), With<Player>>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    buttons: Query<&UseButton>,
+    mut ew_activate: EventWriter<Activate>,
+) {
+    for ev in er_started.read() {
+        if ev.action != InputAction::Interact {
+            continue;
+        }
+        let Ok((transform, children)) = players.get(ev.player) else {
+            continue;
+        };
+        let camera = children
+            .into_iter()
+            .flatten()
+            .find_map(|&child| cameras.get(child).ok());
+        let aim = camera.map_or(transform.forward(), |camera| camera.forward());
+        let eye = transform.translation + transform.up() * rig.height;
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(ev.player);
+        let Some((hit, _)) = rapier_context.cast_ray(eye, *aim, INTERACT_REACH, true, filter)
+        else {
+            continue;
+        };
+        let Ok(button) = buttons.get(hit) else {
+            continue;
+        };
+        for &target in &button.targets {
+            ew_activate.send(Activate {
+                target,
+                source: hit,
+                activation: Activation::Toggle,
+            });
+        }
+    }
+}
+
+/// turn pressure plates' targets on as the first thing steps on and off as the last one leaves
+pub(crate) fn press_plates(
+    mut er_enter: EventReader<TriggerEnter>,
+    mut er_exit: EventReader<TriggerExit>,
+    mut plates: Query<&mut PressurePlate>,
+    mut ew_activate: EventWriter<Activate>,
+) {
+    let entered = er_enter.read().map(|ev| (ev.volume, true));
+    let exited = er_exit.read().map(|ev| (ev.volume, false));
+    for (volume, on) in exited.chain(entered) {
+        let Ok(mut plate) = plates.get_mut(volume) else {
+            continue;
+        };
+        let was_pressed = plate.is_pressed();
+        if on {
+            plate.occupants += 1;
+        } else {
+            plate.occupants = plate.occupants.saturating_sub(1);
+        }
+        if plate.is_pressed() == was_pressed {
+            continue;
+        }
+        let activation = if on { Activation::On } else { Activation::Off };
+        for &target in &plate.targets {
+            ew_activate.send(Activate {
+                target,
+                source: volume,
+                activation,
+            });
+        }
+    }
+}
+
+/// open and close the doors that are activated
+pub(crate) fn activate_doors(
+    mut er_activate: EventReader<Activate>,
+    mut doors: Query<&mut SlidingDoor>,
+) {
+    for ev in er_activate.read() {
+        let Ok(mut door) = doors.get_mut(ev.target) else {
+            continue;
+        };
+        door.open = match ev.activation {
+            Activation::Toggle => !door.open,
+            Activation::On => true,
+            Activation::Off => false,
+        };
+    }
+}
+
+/// slide doors towards open or closed. a door about to close on a player opens again instead,
+/// and one opening into a player waits, so neither shoves them. like moving platforms, this
+/// runs before rapier syncs so it takes the new transform as the door's next position
+pub(crate) fn slide_doors(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut doors: Query<(Entity, &mut SlidingDoor, &mut Transform, &Collider)>,
+    players: Query<(), With<Player>>,
+) {
+    for (entity, mut door, mut transform, collider) in &mut doors {
+        let closed = *door.closed.get_or_insert(transform.translation);
+        let target = if door.open { 1.0 } else { 0.0 };
+        let step = time.delta_seconds() / door.duration.max(f32::EPSILON);
+        let mut progress = door.progress + (target - door.progress).clamp(-step, step);
+        if progress == door.progress {
+            continue;
+        }
+
+        let next = closed + door.offset * smoothstep(progress);
+        let blocked_by_player = |entity| players.contains(entity);
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_collider(entity)
+            .predicate(&blocked_by_player);
+        let options = ShapeCastOptions {
+            max_time_of_impact: 1.0,
+            target_distance: DOOR_MARGIN,
+            // a player already touching it doesn't stop it sliding away from them
+            stop_at_penetration: false,
+            compute_impact_geometry_on_penetration: false,
+        };
+        let blocked = rapier_context
+            .cast_shape(
+                transform.translation,
+                transform.rotation,
+                next - transform.translation,
+                collider,
+                options,
+                filter,
+            )
+            .is_some();
+        if blocked {
+            door.open = true;
+            // and start back next tick, rather than moving into them this one
+            progress = door.progress;
+        }
+        door.progress = progress;
+        transform.translation = closed + door.offset * door.openness();
+    }
+}
@@ -0,0 +1,151 @@
+//! glTF levels, with fixed colliders built from their meshes
+
+use bevy::{asset::LoadState, gltf::GltfAssetLabel, prelude::*, scene::SceneInstance};
+use bevy_rapier3d::prelude::*;
+
+use crate::{spawn_point, SpawnPoint, SpawnSelection};
+
+/// which glTF level to load
+#[derive(Resource, Clone, Default)]
+pub struct LevelConfig {
+    /// path of a `.glb` or `.gltf` under `assets/`, whose first scene is the level
+    pub scene: Option<String>,
+}
+
+/// load a glTF level and build fixed colliders from its meshes once it spawns. a node named
+/// `spawn` becomes a spawn point by that name, selected once the level's loaded
+#[derive(Clone, Default)]
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelConfig>()
+            .add_systems(Startup, setup_level)
+            .add_systems(
+                Update,
+                build_level_colliders.before(spawn_point::follow_spawn_selection),
+            );
+    }
+}
+
+/// how a level mesh node gets its collider, picked by a marker in its name or an ancestor's
+#[derive(Clone, Copy)]
+enum LevelCollider {
+    /// the exact triangles; the default, fine for static geometry of any shape
+    TriMesh,
+    /// `-hull`: a single convex hull around the mesh
+    Hull,
+    /// `-convex`: split into convex pieces, slower to build but solid rather than hollow
+    Convex,
+}
+
+impl LevelCollider {
+    /// the marker in `name`, if any
+    fn from_name(name: &str) -> Option<Self> {
+        if name.contains("-trimesh") {
+            Some(Self::TriMesh)
+        } else if name.contains("-hull") {
+            Some(Self::Hull)
+        } else if name.contains("-convex") {
+            Some(Self::Convex)
+        } else {
+            None
+        }
+    }
+
+    fn shape(self) -> ComputedColliderShape {
+        match self {
+            Self::TriMesh => ComputedColliderShape::TriMesh,
+            Self::Hull => ComputedColliderShape::ConvexHull,
+            Self::Convex => ComputedColliderShape::ConvexDecomposition(default()),
+        }
+    }
+}
+
+/// the level scene, until its colliders are built
+#[derive(Component)]
+struct Level;
+
+/// spawn the level scene, if one is configured
+fn setup_level(mut commands: Commands, config: Res<LevelConfig>, asset_server: Res<AssetServer>) {
+    let Some(path) = &config.scene else {
+        return;
+    };
+    commands
+        .spawn(SceneBundle {
+            scene: asset_server.load(GltfAssetLabel::Scene(0).from_asset(path.clone())),
+            ..default()
+        })
+        .insert(Level);
+}
+
+/// once the level scene has spawned, give every mesh node in it a fixed collider built from its
+/// mesh, skipping `nocollide` subtrees, and make its `spawn` node a spawn point if it has one
+#[allow(clippy::type_complexity)]
+fn build_level_colliders(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scene_spawner: Res<SceneSpawner>,
+    meshes: Res<Assets<Mesh>>,
+    mut selection: ResMut<SpawnSelection>,
+    levels: Query<(Entity, &Handle<Scene>, Option<&SceneInstance>), With<Level>>,
+    nodes: Query<(
+        Option<&Name>,
+        Option<&Handle<Mesh>>,
+        &GlobalTransform,
+        Option<&Children>,
+    )>,
+) {
+    for (level, scene, instance) in &levels {
+        if let LoadState::Failed(err) = asset_server.load_state(scene) {
+            warn!("could not load the level: {err}");
+            commands.entity(level).remove::<Level>();
+            continue;
+        }
+        if !instance.is_some_and(|instance| scene_spawner.instance_is_ready(**instance)) {
+            continue;
+        }
+        commands.entity(level).remove::<Level>();
+
+        let mut spawn = None;
+        let mut colliders = 0;
+        let mut stack = vec![(level, LevelCollider::TriMesh)];
+        while let Some((entity, inherited)) = stack.pop() {
+            let Ok((name, mesh, transform, children)) = nodes.get(entity) else {
+                continue;
+            };
+            let name = name.map_or("", Name::as_str);
+            if name.to_lowercase().contains("nocollide") {
+                continue;
+            }
+            if name.eq_ignore_ascii_case(spawn_point::LEVEL_SPAWN) {
+                spawn = Some(transform.compute_transform());
+            }
+            let kind = LevelCollider::from_name(name).unwrap_or(inherited);
+
+            if let Some(mesh) = mesh.and_then(|mesh| meshes.get(mesh)) {
+                match Collider::from_bevy_mesh(mesh, &kind.shape()) {
+                    Some(collider) => {
+                        commands
+                            .entity(entity)
+                            .insert(RigidBody::Fixed)
+                            .insert(collider);
+                        colliders += 1;
+                    }
+                    None => warn!("level mesh {name:?} can't be made into a collider"),
+                }
+            }
+            stack.extend(children.into_iter().flatten().map(|&child| (child, kind)));
+        }
+        info!("built {colliders} level colliders");
+
+        // top-level, as spawn points are, and kept through a ResetLevel as the level is
+        if let Some(spawn) = spawn {
+            commands.spawn((
+                TransformBundle::from_transform(spawn),
+                SpawnPoint::named(spawn_point::LEVEL_SPAWN),
+            ));
+            spawn_point::select_level_spawn(&mut selection);
+        }
+    }
+}
@@ -0,0 +1,654 @@
+use bevy::{app::PluginGroupBuilder, input::InputSystem, prelude::*, time::TimeSystem};
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    control_helpers::TnuaCrouchEnforcerPlugin,
+    prelude::{TnuaControllerPlugin, TnuaPipelineStages},
+    TnuaUserControlsSystemSet,
+};
+use bevy_tnua_rapier3d::TnuaRapier3dPlugin;
+#[cfg(feature = "leafwing")]
+use leafwing_input_manager::plugin::InputManagerSystem;
+#[cfg(feature = "leafwing")]
+use leafwing_input_manager::prelude::InputManagerPlugin;
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::window::PresentMode;
+#[cfg(target_arch = "wasm32")]
+use camera::{
+    click_to_lock, listen_for_pointer_lock, sync_pointer_lock, PointerLockEvents,
+    PointerLockPending,
+};
+#[cfg(feature = "leafwing")]
+use input::{accumulate_leafwing_look, assign_spare_gamepad, LeafwingLook, PlayerAction};
+#[cfg(not(target_arch = "wasm32"))]
+use settings::watch_settings;
+
+use camera::{
+    apply_control_mode, apply_first_person_body, apply_fov, assign_viewmodel_layers, assist_look,
+    click_to_regrab, cycle_camera_preset, ease_look_at, fade_occluders, look_at_key, mouse_lock,
+    player_rotation, position_camera, recenter_camera, shake_cameras, start_look_at,
+    toggle_control_mode, toggle_viewmodel, top_down_camera, update_lean, update_viewports,
+    update_zoom, viewmodel_sway, window_focus, CameraRecenter, CameraShake, ControlMode,
+    CursorSuspended, LeanConfig, LookConfig, OcclusionFadeConfig, SplitScreen, Zoom, ZoomConfig,
+};
+use camera_path::{
+    camera_path_key, camera_path_trigger, draw_camera_path, play_camera_path,
+    setup_cutscene_camera, start_camera_path, toggle_camera_path_debug, CameraPath,
+    CameraPathDebug, CameraPathPlayback, PlayCameraPath,
+};
+use health::{
+    apply_damage, damage_volumes, despawn_dead_players, fall_damage, flash_health_bar,
+    hazard_damage, regenerate_health, setup_hud, update_health_bar, update_score_text, DamageFlash,
+};
+use input::{
+    gather_input, spawn_touch_ui, translate_input, update_logical_keys, update_touch_controls,
+    update_touch_ui, Action, GamepadConfig, KeyBindings, Keys, LogicalKeys, TouchConfig,
+    TouchControls,
+};
+use menu::{
+    button_colors, cancel_rebind, capture_rebind, menu_buttons, menu_closed, pause_game,
+    resume_game, show_conflict_prompt, spawn_controls_menu, spawn_pause_menu, spawn_settings_menu,
+    toggle_menu, update_setting_labels, MenuState, RebindConflict, Rebinding,
+};
+use movement::{
+    apply_world_up, bonk_ceilings, detect_landing, jump_pads, measure_platform_velocity,
+    ride_platforms, sense_crouch_headroom, swap_crouch_shape, update_grounded, update_locomotion,
+    update_movement_state, update_player, upright_springs,
+};
+use photo_mode::{photo_camera, photo_capture, toggle_photo_mode, PhotoMode, PhotoModeSettings};
+use player_model::{
+    attach_animation_players, load_player_models, play_locomotion_animations, spawn_player_models,
+};
+use settings::{apply_settings, load_settings, SavedSettings, SettingsFile, SettingsLoader};
+use spawn::{
+    apply_player_shape, despawn_players, forget_despawned_player, kill_fallen_players,
+    reset_players, reset_scene, respawn_players, setup_player, spawn_players, PendingRespawns,
+};
+use time_scale::apply_time_scale;
+
+mod breakable;
+mod camera;
+mod camera_path;
+mod checkpoint;
+mod collectible;
+mod crosshair;
+mod debug_overlay;
+mod demo_scene;
+mod elevator;
+mod health;
+mod input;
+mod interact;
+mod level;
+mod menu;
+mod movement;
+mod movement_gizmos;
+mod obstacle_course;
+mod photo_mode;
+mod platform;
+mod player_model;
+mod push;
+mod scatter;
+mod scene_file;
+mod settings;
+mod sky;
+mod slam;
+mod spawn;
+mod spawn_point;
+mod speed_fov;
+mod speedometer;
+mod surface;
+mod terrain;
+mod time_of_day;
+mod time_scale;
+mod trigger;
+mod wall_run;
+mod wind;
+
+pub use breakable::{Breakable, BreakablePlugin, PropDestroyed, DEBRIS_GROUP};
+pub use camera::{
+    CameraLookTarget, CameraPreset, CameraRig, CursorSettings, FirstPersonBody, LookAssistConfig,
+    LookAt, LookTarget, MouseLocked, PlayerCamera, RegrabMode,
+};
+pub use checkpoint::{
+    Checkpoint, CheckpointConfig, CheckpointPlugin, CheckpointReached, CurrentCheckpoint,
+    ReachedCheckpoint,
+};
+pub use collectible::{
+    spawn_collectible, CollectedEvent, Collectible, CollectiblePlugin, ResetLevel, Score,
+};
+pub use crosshair::{Crosshair, CrosshairSettings, CrosshairShape};
+pub use debug_overlay::DebugOverlay;
+pub use demo_scene::{DemoSceneConfig, DemoScenePlugin, SceneObject};
+pub use elevator::{CallElevator, Elevator, ElevatorPlugin};
+pub use health::{FallDamageConfig, Health, HealthConfig, PlayerDamaged, PlayerDied};
+pub use input::{
+    ActionEnded, ActionStarted, GamepadInput, InputAction, InputRecorderPlugin, InputSnapshot,
+    KeyboardMouseInput, MovementIntent,
+};
+pub use interact::{Activate, Activation, InteractPlugin, PressurePlate, SlidingDoor, UseButton};
+pub use level::{LevelConfig, LevelPlugin};
+pub use movement::{
+    Abilities, Ability, AbilityContext, AirTime, CeilingBonk, CrouchAbility, FaceMovement,
+    Grounded, JumpAbility, JumpConfig, JumpDirection, JumpPad, LocomotionChanged, LocomotionConfig,
+    LocomotionState, MovementConfig, MovementSpace, MovementStateChanged, PlayerLanded,
+    PlayerMovementState, PlayerSettings, Upright, WorldUp,
+};
+pub use movement_gizmos::MovementGizmos;
+pub use obstacle_course::{ObstacleCourseConfig, ObstacleCoursePlugin};
+pub use platform::{MovingPlatform, PlatformPath, PlatformPlugin, RotatingPlatform, Waypoint};
+pub use player_model::{PlayerModelConfig, PlayerVisual};
+pub use push::{
+    crate_body, CRATE_ANGULAR_DAMPING, CRATE_FRICTION, CRATE_LINEAR_DAMPING, HEAVY_CRATE_MASS,
+    LIGHT_CRATE_MASS, MEDIUM_CRATE_MASS,
+};
+pub use scatter::{scatter_layout, Obstacle, ObstacleKind, ScatterConfig, ScatterRng};
+pub use settings::SettingsSourcePlugin;
+pub use sky::SkyboxConfig;
+pub use slam::{GroundSlam, GroundSlamAbility, GroundSlamConfig, GroundSlamPlugin, SlamShockwave};
+pub use spawn::{
+    spawn_player, DespawnPlayer, PlayerCameraOptions, PlayerInput, PlayerSpawnOptions,
+    RespawnConfig, SpawnPlayer, SpawnedPlayer,
+};
+pub use spawn_point::{SpawnPoint, SpawnPoints, SpawnSelection};
+pub use speed_fov::SpeedFovConfig;
+pub use speedometer::SpeedometerConfig;
+pub use surface::{GroundMaterial, SurfaceFriction};
+use terrain::SpawnedTerrain;
+pub use terrain::{Heightmap, TerrainConfig};
+pub use time_of_day::{Daylight, TimeOfDay};
+pub use time_scale::TimeScale;
+pub use trigger::{TriggerActivator, TriggerEnter, TriggerExit, TriggerPlugin, TriggerVolume};
+pub use wall_run::{WallContact, WallRunAbility, WallRunConfig};
+pub use wind::{WindPlugin, WindZone};
+
+/// rate of the fixed schedule that movement, tnua and rapier run in
+const PHYSICS_HZ: f64 = 60.0;
+
+/// the player, physics and movement: everything needed to move the player around, without a
+/// window or renderer, so it also runs headless
+#[derive(Clone)]
+pub struct PlayerControllerPlugin {
+    /// spawn the local players on startup. turn off to spawn your own with spawn_player
+    pub spawn_players: bool,
+    /// give the players spawned on startup a camera each. turn off to bring your own camera
+    pub player_cameras: bool,
+    /// local players, each with their own split-screen viewport. the first plays with keyboard
+    /// and mouse, the rest with gamepads
+    pub local_players: usize,
+    /// start in the top-down control mode rather than mouse look
+    pub top_down: bool,
+    /// show the touch controls before the first touch
+    pub touch_controls: bool,
+}
+
+impl Default for PlayerControllerPlugin {
+    fn default() -> Self {
+        Self {
+            spawn_players: true,
+            player_cameras: true,
+            local_players: 1,
+            top_down: false,
+            touch_controls: false,
+        }
+    }
+}
+
+/// whether setup_player attaches cameras, from PlayerControllerPlugin
+#[derive(Resource)]
+struct SpawnPlayerCameras(bool);
+
+impl Plugin for PlayerControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRig>()
+            .init_resource::<LookConfig>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<MovementConfig>()
+            .init_resource::<WorldUp>()
+            .init_resource::<TimeScale>()
+            .init_resource::<SurfaceFriction>()
+            .init_resource::<JumpConfig>()
+            .init_resource::<WallRunConfig>()
+            .add_event::<PlayerLanded>()
+            .init_resource::<PlayerSettings>()
+            .init_resource::<LocomotionConfig>()
+            .add_event::<LocomotionChanged>()
+            .add_event::<MovementStateChanged>()
+            .add_event::<ActionStarted>()
+            .add_event::<ActionEnded>()
+            .add_event::<ResetLevel>()
+            .add_systems(Update, reset_scene)
+            .add_event::<CeilingBonk>()
+            .register_type::<PlayerSettings>()
+            .init_resource::<HealthConfig>()
+            .insert_resource(SplitScreen {
+                players: self.local_players,
+            })
+            .insert_resource(if self.top_down {
+                ControlMode::TopDown
+            } else {
+                ControlMode::MouseLook
+            })
+            .init_resource::<GamepadConfig>()
+            .init_resource::<CameraRecenter>()
+            .insert_resource(TouchControls {
+                enabled: self.touch_controls,
+                ..default()
+            })
+            .init_resource::<LogicalKeys>()
+            .insert_resource(SpawnPlayerCameras(self.player_cameras))
+            // physics-affecting logic runs in FixedUpdate: gather_input captures each player's
+            // input for the tick, update_player feeds tnua, tnua drives rapier, all at
+            // PHYSICS_HZ. camera, cursor and ui systems stay in Update so they respond every
+            // frame.
+            .insert_resource(Time::<Fixed>::from_hz(PHYSICS_HZ))
+            // rapier steps exactly once a tick by the same dt, never by frame time, so replays
+            // are deterministic
+            .insert_resource(RapierConfiguration {
+                timestep_mode: TimestepMode::Fixed {
+                    dt: 1.0 / PHYSICS_HZ as f32,
+                    substeps: 1,
+                },
+                ..RapierConfiguration::new(1.0)
+            })
+            .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+            .add_plugins(TnuaControllerPlugin::new(FixedUpdate))
+            .add_plugins(TnuaRapier3dPlugin::new(FixedUpdate))
+            .add_plugins(TnuaCrouchEnforcerPlugin::new(FixedUpdate))
+            .add_event::<DespawnPlayer>()
+            .add_event::<SpawnPlayer>()
+            .init_resource::<RespawnConfig>()
+            .init_resource::<PendingRespawns>()
+            .init_resource::<SpawnSelection>()
+            .add_systems(Update, spawn_point::follow_spawn_selection)
+            .add_systems(
+                Update,
+                (
+                    apply_player_shape,
+                    (
+                        kill_fallen_players,
+                        despawn_players,
+                        respawn_players,
+                        spawn_players,
+                        forget_despawned_player,
+                    )
+                        .chain(),
+                ),
+            )
+            .add_systems(PreUpdate, update_logical_keys.after(InputSystem))
+            .add_systems(First, apply_time_scale.before(TimeSystem))
+            .add_systems(
+                FixedUpdate,
+                (gather_input, translate_input, update_player)
+                    .chain()
+                    .in_set(TnuaUserControlsSystemSet)
+                    .run_if(not(resource_exists::<PhotoMode>))
+                    .run_if(menu_closed),
+            )
+            .add_systems(
+                FixedUpdate,
+                apply_world_up.before(TnuaUserControlsSystemSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (bonk_ceilings, wall_run::detect_walls)
+                    .in_set(TnuaUserControlsSystemSet)
+                    .before(update_player)
+                    .run_if(menu_closed),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    jump_pads,
+                    ride_platforms,
+                    upright_springs,
+                    (push::track_push_contacts, push::push_bodies).chain(),
+                )
+                    .in_set(TnuaUserControlsSystemSet)
+                    .after(update_player)
+                    .run_if(menu_closed),
+            )
+            .add_systems(
+                FixedUpdate,
+                (push::sense_past_bodies_beside, measure_platform_velocity)
+                    .chain()
+                    .after(TnuaPipelineStages::Sensors)
+                    .before(TnuaPipelineStages::Logic),
+            )
+            .add_systems(
+                FixedUpdate,
+                (update_grounded, update_movement_state, update_locomotion)
+                    .chain()
+                    .after(TnuaPipelineStages::Logic),
+            )
+            .add_systems(
+                FixedUpdate,
+                sense_crouch_headroom
+                    .after(TnuaPipelineStages::Sensors)
+                    .before(TnuaPipelineStages::SubservientSensors),
+            )
+            .add_systems(
+                FixedUpdate,
+                swap_crouch_shape
+                    .after(TnuaPipelineStages::Logic)
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(FixedUpdate, detect_landing.after(update_movement_state));
+        if self.spawn_players {
+            // after Startup, so the scene's spawn points are there
+            app.add_systems(PostStartup, setup_player).add_systems(
+                Update,
+                (reset_players, setup_player.run_if(on_event::<ResetLevel>()))
+                    .chain()
+                    .after(reset_scene),
+            );
+        }
+
+        #[cfg(feature = "leafwing")]
+        app.add_plugins(InputManagerPlugin::<PlayerAction>::default())
+            .init_resource::<LeafwingLook>()
+            .add_systems(
+                PreUpdate,
+                accumulate_leafwing_look.after(InputManagerSystem::Update),
+            )
+            .add_systems(Update, assign_spare_gamepad);
+    }
+}
+
+/// mouse look and the player cameras: the camera rig, zoom, lean, control modes, split-screen
+/// viewports, viewmodels and mouse locking. needs PlayerControllerPlugin
+#[derive(Clone, Default)]
+pub struct PlayerCameraPlugin {
+    pub cursor: CursorSettings,
+}
+
+impl Plugin for PlayerCameraPlugin {
+    fn build(&self, app: &mut App) {
+        // on the web the lock waits for the first click instead, see PointerLockPending
+        app.insert_resource(MouseLocked(
+            self.cursor.initially_locked && !cfg!(target_arch = "wasm32"),
+        ))
+        .insert_resource(self.cursor.clone())
+        .init_resource::<CursorSuspended>()
+        .init_resource::<ZoomConfig>()
+        .init_resource::<Zoom>()
+        .init_resource::<SpeedFovConfig>()
+        .init_resource::<FirstPersonBody>()
+        .add_event::<LookAt>()
+        .init_resource::<CameraLookTarget>()
+        .init_resource::<LookAssistConfig>()
+        .init_resource::<OcclusionFadeConfig>()
+        .init_resource::<LeanConfig>()
+        .init_resource::<SkyboxConfig>()
+        .add_systems(Startup, sky::setup_sky)
+        .add_systems(Update, (sky::update_sky, sky::attach_skybox).chain())
+        // look turns the player a tick's snapshot at a time, before the tick moves them
+        .add_systems(
+            FixedUpdate,
+            player_rotation
+                .in_set(TnuaUserControlsSystemSet)
+                .after(translate_input)
+                .before(update_player)
+                .run_if(resource_equals(ControlMode::MouseLook))
+                .run_if(not(resource_exists::<CameraPathPlayback>))
+                .run_if(not(resource_exists::<PhotoMode>))
+                .run_if(menu_closed),
+        )
+        .add_systems(
+            Update,
+            (
+                (look_at_key, start_look_at, ease_look_at, assist_look).chain(),
+                update_zoom,
+                recenter_camera,
+                update_lean,
+                shake_cameras,
+                position_camera,
+            )
+                .chain()
+                .run_if(resource_equals(ControlMode::MouseLook))
+                .run_if(not(resource_exists::<PhotoMode>))
+                .run_if(menu_closed),
+        )
+        .add_systems(
+            Update,
+            (
+                toggle_control_mode
+                    .run_if(not(resource_exists::<PhotoMode>))
+                    .run_if(not(resource_exists::<CameraPathPlayback>))
+                    .run_if(menu_closed),
+                apply_control_mode,
+                top_down_camera.run_if(resource_equals(ControlMode::TopDown)),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (speed_fov::ease_speed_fov, apply_fov)
+                .chain()
+                .run_if(not(resource_exists::<PhotoMode>)),
+        )
+        .add_systems(Update, update_viewports)
+        .add_systems(Update, fade_occluders.after(position_camera))
+        .add_systems(
+            Update,
+            (assign_viewmodel_layers, viewmodel_sway, toggle_viewmodel),
+        )
+        .add_systems(Update, apply_first_person_body.after(cycle_camera_preset))
+        .add_systems(Update, cycle_camera_preset.run_if(menu_closed))
+        .add_systems(Update, (window_focus, click_to_regrab, mouse_lock).chain());
+
+        #[cfg(target_arch = "wasm32")]
+        app.insert_resource(PointerLockPending(self.cursor.initially_locked))
+            .init_resource::<PointerLockEvents>()
+            .add_systems(Startup, listen_for_pointer_lock)
+            .add_systems(
+                Update,
+                (sync_pointer_lock, click_to_lock)
+                    .chain()
+                    .before(mouse_lock),
+            );
+    }
+}
+
+/// the level mechanics: trigger volumes, checkpoints, collectibles, buttons and doors,
+/// elevators, breakables, wind zones, moving platforms and the ground slam. needs
+/// PlayerControllerPlugin
+pub struct MechanicsPlugins;
+
+impl PluginGroup for MechanicsPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(TriggerPlugin)
+            .add(CheckpointPlugin)
+            .add(CollectiblePlugin)
+            .add(InteractPlugin)
+            .add(ElevatorPlugin)
+            .add(BreakablePlugin)
+            .add(WindPlugin)
+            .add(PlatformPlugin)
+            .add(GroundSlamPlugin)
+    }
+}
+
+/// everything else in the starter: settings, menus, the hud and health, photo mode, camera
+/// paths, input recording, touch controls and player models
+pub struct StarterPlugin;
+
+impl Plugin for StarterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputRecorderPlugin)
+            .init_resource::<CameraPath>()
+            .init_resource::<CameraPathDebug>()
+            .init_resource::<MovementGizmos>()
+            .add_event::<PlayCameraPath>()
+            .init_resource::<PhotoModeSettings>()
+            .init_resource::<Rebinding>()
+            .init_resource::<RebindConflict>()
+            .add_event::<PlayerDamaged>()
+            .init_resource::<FallDamageConfig>()
+            .add_event::<PlayerDied>()
+            .init_resource::<DamageFlash>()
+            .init_resource::<TouchConfig>()
+            .init_state::<MenuState>()
+            .enable_state_scoped_entities::<MenuState>()
+            .init_asset::<SavedSettings>()
+            .register_asset_loader(SettingsLoader)
+            .init_resource::<SettingsFile>()
+            .add_systems(Startup, load_settings)
+            .add_systems(Update, apply_settings)
+            .init_resource::<PlayerModelConfig>()
+            .add_systems(
+                Update,
+                (
+                    load_player_models,
+                    spawn_player_models,
+                    attach_animation_players,
+                    play_locomotion_animations,
+                )
+                    .chain(),
+            )
+            .init_resource::<CrosshairSettings>()
+            .init_resource::<SpeedometerConfig>()
+            .init_resource::<DebugOverlay>()
+            .add_systems(
+                Startup,
+                (
+                    setup_cutscene_camera,
+                    setup_hud,
+                    crosshair::setup_crosshair,
+                    speedometer::setup_speedometer,
+                    debug_overlay::setup_debug_overlay,
+                ),
+            )
+            .add_systems(
+                Update,
+                (crosshair::build_crosshair, crosshair::show_crosshair).before(photo_capture),
+            )
+            .add_systems(FixedUpdate, fall_damage.after(detect_landing))
+            .add_systems(
+                Update,
+                (
+                    hazard_damage,
+                    damage_volumes.run_if(menu_closed),
+                    apply_damage,
+                    flash_health_bar,
+                    regenerate_health.run_if(menu_closed),
+                    despawn_dead_players.before(despawn_players),
+                    update_health_bar,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, update_score_text)
+            .add_systems(
+                Update,
+                (
+                    speedometer::toggle_speedometer.run_if(menu_closed),
+                    speedometer::update_speedometer,
+                )
+                    .chain()
+                    .before(photo_capture),
+            )
+            .add_systems(
+                Update,
+                (
+                    debug_overlay::toggle_debug_overlay.run_if(menu_closed),
+                    debug_overlay::update_debug_overlay,
+                )
+                    .chain()
+                    .before(photo_capture),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        camera_path_key.run_if(menu_closed),
+                        camera_path_trigger,
+                        start_camera_path,
+                    )
+                        .chain(),
+                    play_camera_path
+                        .run_if(resource_exists::<CameraPathPlayback>)
+                        .run_if(menu_closed),
+                    toggle_camera_path_debug.run_if(menu_closed),
+                    draw_camera_path.run_if(|debug: Res<CameraPathDebug>| debug.0),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    movement_gizmos::toggle_movement_gizmos.run_if(menu_closed),
+                    (
+                        movement_gizmos::draw_feet,
+                        movement_gizmos::draw_ground_sensors,
+                        movement_gizmos::draw_desired_velocity,
+                    )
+                        .run_if(resource_exists::<GizmoConfigStore>)
+                        .run_if(|gizmos: Res<MovementGizmos>| gizmos.0),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_photo_mode
+                        .run_if(not(resource_exists::<CameraPathPlayback>))
+                        .run_if(menu_closed),
+                    (photo_camera, photo_capture).run_if(resource_exists::<PhotoMode>),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (update_touch_controls, spawn_touch_ui, update_touch_ui).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_menu
+                        .run_if(not(resource_exists::<PhotoMode>))
+                        .run_if(not(resource_exists::<CameraPathPlayback>)),
+                    capture_rebind.run_if(in_state(MenuState::Controls)),
+                    (
+                        menu_buttons,
+                        button_colors,
+                        update_setting_labels,
+                        show_conflict_prompt,
+                    )
+                        .run_if(not(in_state(MenuState::Closed))),
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(MenuState::Closed), pause_game)
+            .add_systems(OnEnter(MenuState::Closed), resume_game)
+            .add_systems(OnEnter(MenuState::Pause), spawn_pause_menu)
+            .add_systems(OnEnter(MenuState::Settings), spawn_settings_menu)
+            .add_systems(OnEnter(MenuState::Controls), spawn_controls_menu)
+            .add_systems(OnExit(MenuState::Controls), cancel_rebind);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, watch_settings.before(apply_settings));
+    }
+}
+
+/// the starter's window
+pub fn window_plugin() -> WindowPlugin {
+    WindowPlugin {
+        primary_window: Some(Window {
+            // optional: disable v-sync (browsers always present with v-sync)
+            #[cfg(not(target_arch = "wasm32"))]
+            present_mode: PresentMode::Immediate,
+            // fill the page on the web
+            fit_canvas_to_parent: true,
+            ..default()
+        }),
+        ..default()
+    }
+}
+
+#[derive(Component)]
+pub struct Player;
+
+/// the keyboard and mouse player, for systems that want it without a `With<Player>` query.
+/// inserted when the player spawns and removed when it's despawned, so after dying or falling
+/// it's back with the respawned player's new entity
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerEntity(pub Entity);
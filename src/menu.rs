@@ -0,0 +1,665 @@
+//! the pause menu, with its settings and controls screens, pausing the game while it's open
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    camera::{CameraRecenter, LookConfig},
+    input::{Action, BindingConflict, GamepadConfig, KeyBindings, Keys, TouchConfig},
+    movement::{FaceMovement, JumpConfig, MovementConfig, MovementSpace},
+    settings::SavedSettings,
+    MouseLocked, PlayerSettings, SkyboxConfig,
+};
+
+/// which menu, if any, is open
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum MenuState {
+    #[default]
+    Closed,
+    Pause,
+    Settings,
+    Controls,
+}
+
+/// no menu's open, or there's no menu at all without StarterPlugin
+pub(crate) fn menu_closed(state: Option<Res<State<MenuState>>>) -> bool {
+    state.is_none_or(|state| *state.get() == MenuState::Closed)
+}
+
+/// mouse lock state to restore when the menu closes
+#[derive(Resource)]
+pub(crate) struct LockedBeforeMenu(bool);
+
+/// set while the controls menu waits for a key to bind to an action
+#[derive(Resource, Default)]
+pub(crate) struct Rebinding(Option<Action>);
+
+/// a rebind refused because its key belongs to another action, waiting for the player to
+/// unbind the other action or cancel
+#[derive(Resource, Default)]
+pub(crate) struct RebindConflict(Option<(Action, BindingConflict)>);
+
+/// marks the controls menu row prompting to resolve a RebindConflict
+#[derive(Component)]
+pub(crate) struct ConflictPrompt;
+
+/// menu buttons and what they do
+#[derive(Component, Clone, Copy)]
+pub(crate) enum MenuButton {
+    Resume,
+    Settings,
+    Controls,
+    Quit,
+    SensitivityDown,
+    SensitivityUp,
+    VerticalRatioDown,
+    VerticalRatioUp,
+    RawInput,
+    InvertY,
+    FovDown,
+    FovUp,
+    Recenter,
+    FaceMovement,
+    FreeLook,
+    MovementSpace,
+    StickSensitivityDown,
+    StickSensitivityUp,
+    StickCurve,
+    MoveStickCurve,
+    TouchSensitivityDown,
+    TouchSensitivityUp,
+    Rebind(Action),
+    /// unbind the conflicting action's key and finish the rebind
+    ResolveConflict,
+    CancelConflict,
+    ResetBindings,
+    Save,
+    Back,
+}
+
+/// settings menu text showing a setting's current value
+#[derive(Component, Clone, Copy)]
+pub(crate) enum SettingLabel {
+    Sensitivity,
+    VerticalRatio,
+    RawInput,
+    InvertY,
+    Fov,
+    Recenter,
+    FaceMovement,
+    FreeLook,
+    MovementSpace,
+    StickSensitivity,
+    StickCurve,
+    MoveStickCurve,
+    TouchSensitivity,
+    Key(Action),
+    Conflict,
+}
+
+/// listen for Action::Pause to open, close, or back out of menus
+pub(crate) fn toggle_menu(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    rebinding: Res<Rebinding>,
+    mut conflict: ResMut<RebindConflict>,
+    state: Res<State<MenuState>>,
+    mut next_state: ResMut<NextState<MenuState>>,
+) {
+    // the pause key cancels a rebind or conflict prompt instead
+    if !bindings.just_pressed(&keyboard, Action::Pause) || rebinding.0.is_some() {
+        return;
+    }
+    if conflict.0.is_some() {
+        conflict.0 = None;
+        return;
+    }
+
+    next_state.set(match state.get() {
+        MenuState::Closed => MenuState::Pause,
+        MenuState::Pause => MenuState::Closed,
+        MenuState::Settings | MenuState::Controls => MenuState::Pause,
+    });
+}
+
+/// pause physics and release the mouse while a menu is open
+pub(crate) fn pause_game(
+    mut commands: Commands,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut locked: ResMut<MouseLocked>,
+) {
+    rapier_config.physics_pipeline_active = false;
+    commands.insert_resource(LockedBeforeMenu(locked.0));
+    locked.0 = false;
+}
+
+/// resume physics and restore the mouse lock when the menu closes
+pub(crate) fn resume_game(
+    mut commands: Commands,
+    before: Option<Res<LockedBeforeMenu>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut locked: ResMut<MouseLocked>,
+) {
+    // the initial state is also entered on startup, before any menu was opened
+    let Some(before) = before else {
+        return;
+    };
+
+    rapier_config.physics_pipeline_active = true;
+    locked.0 = before.0;
+    commands.remove_resource::<LockedBeforeMenu>();
+}
+
+/// full-screen menu root, despawned when leaving `state`
+fn menu_root(state: MenuState) -> impl Bundle {
+    (
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            background_color: Color::BLACK.with_alpha(0.6).into(),
+            ..default()
+        },
+        StateScoped(state),
+    )
+}
+
+fn spawn_button(parent: &mut ChildBuilder, text: &str, button: MenuButton) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(16.0), Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+            ..default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(text, default()));
+        });
+}
+
+/// a row with a setting's current value followed by its buttons
+fn spawn_setting_row(
+    parent: &mut ChildBuilder,
+    label: SettingLabel,
+    buttons: &[(&str, MenuButton)],
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section("", default()).with_style(Style {
+                    width: Val::Px(260.0),
+                    ..default()
+                }))
+                .insert(label);
+            for (text, button) in buttons {
+                spawn_button(parent, text, *button);
+            }
+        });
+}
+
+pub(crate) fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuState::Pause))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+            spawn_button(parent, "Resume", MenuButton::Resume);
+            spawn_button(parent, "Settings", MenuButton::Settings);
+            spawn_button(parent, "Controls", MenuButton::Controls);
+            spawn_button(parent, "Quit", MenuButton::Quit);
+        });
+}
+
+pub(crate) fn spawn_settings_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuState::Settings))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+            spawn_setting_row(
+                parent,
+                SettingLabel::Sensitivity,
+                &[
+                    ("-", MenuButton::SensitivityDown),
+                    ("+", MenuButton::SensitivityUp),
+                ],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::VerticalRatio,
+                &[
+                    ("-", MenuButton::VerticalRatioDown),
+                    ("+", MenuButton::VerticalRatioUp),
+                ],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::RawInput,
+                &[("Toggle", MenuButton::RawInput)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::InvertY,
+                &[("Toggle", MenuButton::InvertY)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::Fov,
+                &[("-", MenuButton::FovDown), ("+", MenuButton::FovUp)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::Recenter,
+                &[("Toggle", MenuButton::Recenter)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::FaceMovement,
+                &[("Toggle", MenuButton::FaceMovement)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::FreeLook,
+                &[("Toggle", MenuButton::FreeLook)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::MovementSpace,
+                &[("Toggle", MenuButton::MovementSpace)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::StickSensitivity,
+                &[
+                    ("-", MenuButton::StickSensitivityDown),
+                    ("+", MenuButton::StickSensitivityUp),
+                ],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::StickCurve,
+                &[("Toggle", MenuButton::StickCurve)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::MoveStickCurve,
+                &[("Toggle", MenuButton::MoveStickCurve)],
+            );
+            spawn_setting_row(
+                parent,
+                SettingLabel::TouchSensitivity,
+                &[
+                    ("-", MenuButton::TouchSensitivityDown),
+                    ("+", MenuButton::TouchSensitivityUp),
+                ],
+            );
+            spawn_button(parent, "Save", MenuButton::Save);
+            spawn_button(parent, "Back", MenuButton::Back);
+        });
+}
+
+pub(crate) fn spawn_controls_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuState::Controls))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+            // hidden until a rebind hits a key another action uses
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::None,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(ConflictPrompt)
+                .with_children(|parent| {
+                    spawn_setting_row(
+                        parent,
+                        SettingLabel::Conflict,
+                        &[
+                            ("Unbind", MenuButton::ResolveConflict),
+                            ("Cancel", MenuButton::CancelConflict),
+                        ],
+                    );
+                });
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Grid,
+                        grid_template_columns: vec![GridTrack::auto(), GridTrack::auto()],
+                        column_gap: Val::Px(32.0),
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for action in Action::ALL {
+                        spawn_setting_row(
+                            parent,
+                            SettingLabel::Key(action),
+                            &[("Rebind", MenuButton::Rebind(action))],
+                        );
+                    }
+                });
+            spawn_button(parent, "Reset to defaults", MenuButton::ResetBindings);
+            spawn_button(parent, "Save", MenuButton::Save);
+            spawn_button(parent, "Back", MenuButton::Back);
+        });
+}
+
+/// handle menu button presses, applying setting changes immediately
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn menu_buttons(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<MenuState>>,
+    mut look: ResMut<LookConfig>,
+    mut rebinding: ResMut<Rebinding>,
+    mut conflict: ResMut<RebindConflict>,
+    mut bindings: ResMut<KeyBindings>,
+    mut movement: ResMut<MovementConfig>,
+    mut recenter: ResMut<CameraRecenter>,
+    mut gamepad: ResMut<GamepadConfig>,
+    mut touch: ResMut<TouchConfig>,
+    jump: Res<JumpConfig>,
+    player: Res<PlayerSettings>,
+    sky: Res<SkyboxConfig>,
+    mut ew_exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match *button {
+            MenuButton::Resume => next_state.set(MenuState::Closed),
+            MenuButton::Settings => next_state.set(MenuState::Settings),
+            MenuButton::Controls => next_state.set(MenuState::Controls),
+            MenuButton::Quit => {
+                ew_exit.send(AppExit::Success);
+            }
+            MenuButton::SensitivityDown => {
+                look.sensitivity = (look.sensitivity - 0.0005).max(0.0005);
+            }
+            MenuButton::SensitivityUp => {
+                look.sensitivity = (look.sensitivity + 0.0005).min(0.05);
+            }
+            MenuButton::VerticalRatioDown => {
+                look.vertical_ratio = (look.vertical_ratio - 0.1).max(0.1);
+            }
+            MenuButton::VerticalRatioUp => {
+                look.vertical_ratio = (look.vertical_ratio + 0.1).min(2.0);
+            }
+            MenuButton::RawInput => look.raw_input = !look.raw_input,
+            MenuButton::InvertY => look.invert_y = !look.invert_y,
+            MenuButton::Recenter => recenter.enabled = !recenter.enabled,
+            MenuButton::FaceMovement => {
+                movement.face_movement = match movement.face_movement {
+                    FaceMovement::Off => FaceMovement::Input,
+                    FaceMovement::Input => FaceMovement::Velocity,
+                    FaceMovement::Velocity => FaceMovement::Off,
+                };
+            }
+            MenuButton::FreeLook => look.free_look = !look.free_look,
+            MenuButton::MovementSpace => {
+                movement.space = match movement.space {
+                    MovementSpace::CameraRelative => MovementSpace::WorldAxes,
+                    MovementSpace::WorldAxes => MovementSpace::CameraRelative,
+                };
+            }
+            MenuButton::StickSensitivityDown => {
+                gamepad.look_sensitivity = (gamepad.look_sensitivity - 0.25).max(0.25);
+            }
+            MenuButton::StickSensitivityUp => {
+                gamepad.look_sensitivity = (gamepad.look_sensitivity + 0.25).min(10.0);
+            }
+            MenuButton::StickCurve => gamepad.look_curve = gamepad.look_curve.toggled(),
+            MenuButton::MoveStickCurve => gamepad.move_curve = gamepad.move_curve.toggled(),
+            MenuButton::TouchSensitivityDown => {
+                touch.look_sensitivity = (touch.look_sensitivity - 0.001).max(0.001);
+            }
+            MenuButton::TouchSensitivityUp => {
+                touch.look_sensitivity = (touch.look_sensitivity + 0.001).min(0.05);
+            }
+            MenuButton::FovDown => look.fov = (look.fov - 5.0).max(30.0),
+            MenuButton::FovUp => look.fov = (look.fov + 5.0).min(150.0),
+            MenuButton::Rebind(action) => {
+                conflict.0 = None;
+                rebinding.0 = Some(action);
+            }
+            MenuButton::ResolveConflict => {
+                if let Some((action, pending)) = conflict.0.take() {
+                    bindings.unbind(pending.bound_to, pending.key);
+                    // the key may be bound to yet another overlapping action
+                    if let Err(err) = bindings.set(action, pending.key) {
+                        conflict.0 = Some((action, err));
+                    }
+                }
+            }
+            MenuButton::CancelConflict => conflict.0 = None,
+            MenuButton::ResetBindings => {
+                conflict.0 = None;
+                bindings.reset_keys();
+            }
+            MenuButton::Save => SavedSettings {
+                look: look.clone(),
+                bindings: bindings.clone(),
+                movement: movement.clone(),
+                recenter: recenter.clone(),
+                gamepad: gamepad.clone(),
+                touch: touch.clone(),
+                player: player.clone(),
+                jump: jump.clone(),
+                sky: sky.clone(),
+            }
+            .save(),
+            MenuButton::Back => next_state.set(MenuState::Pause),
+        }
+    }
+}
+
+/// bind the next pressed key to the action being rebound; the pause key cancels
+pub(crate) fn capture_rebind(
+    keyboard: Keys,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<KeyBindings>,
+    mut conflict: ResMut<RebindConflict>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+
+    // record the key in the same terms the action reads it in
+    let input = keyboard.input(bindings.matching(action));
+    if let Some(&key) = input.get_just_pressed().next() {
+        // the pause key cancels the capture
+        if !bindings.just_pressed(&keyboard, Action::Pause) {
+            if let Err(err) = bindings.set(action, key) {
+                conflict.0 = Some((action, err));
+            }
+        }
+        rebinding.0 = None;
+    }
+}
+
+/// drop any rebind in progress when leaving the controls menu
+pub(crate) fn cancel_rebind(
+    mut rebinding: ResMut<Rebinding>,
+    mut conflict: ResMut<RebindConflict>,
+) {
+    rebinding.0 = None;
+    conflict.0 = None;
+}
+
+/// show the conflict prompt only while a rebind conflict is waiting
+pub(crate) fn show_conflict_prompt(
+    conflict: Res<RebindConflict>,
+    mut prompt: Query<&mut Style, With<ConflictPrompt>>,
+) {
+    for mut style in &mut prompt {
+        style.display = if conflict.0.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+/// highlight hovered and pressed buttons
+#[allow(clippy::type_complexity)]
+pub(crate) fn button_colors(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in &mut buttons {
+        *color = match interaction {
+            Interaction::Pressed => Color::srgb(0.4, 0.4, 0.4),
+            Interaction::Hovered => Color::srgb(0.3, 0.3, 0.3),
+            Interaction::None => Color::srgb(0.2, 0.2, 0.2),
+        }
+        .into();
+    }
+}
+
+/// keep setting labels in sync with the current values
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_setting_labels(
+    look: Res<LookConfig>,
+    bindings: Res<KeyBindings>,
+    recenter: Res<CameraRecenter>,
+    gamepad: Res<GamepadConfig>,
+    touch: Res<TouchConfig>,
+    movement: Res<MovementConfig>,
+    rebinding: Res<Rebinding>,
+    conflict: Res<RebindConflict>,
+    mut labels: Query<(Ref<SettingLabel>, &mut Text)>,
+) {
+    /// color of the labels of both actions in a rebind conflict
+    const CONFLICT_COLOR: Color = Color::srgb(1.0, 0.4, 0.3);
+
+    let changed = look.is_changed()
+        || conflict.is_changed()
+        || movement.is_changed()
+        || bindings.is_changed()
+        || recenter.is_changed()
+        || gamepad.is_changed()
+        || touch.is_changed()
+        || rebinding.is_changed();
+
+    for (label, mut text) in &mut labels {
+        if !changed && !label.is_added() {
+            continue;
+        }
+
+        text.sections[0].value = match *label {
+            SettingLabel::Sensitivity => format!("Sensitivity: {:.4}", look.sensitivity),
+            SettingLabel::VerticalRatio => {
+                format!("Vertical sensitivity: {:.0}%", look.vertical_ratio * 100.0)
+            }
+            SettingLabel::RawInput => format!(
+                "Mouse input: {}",
+                if look.raw_input { "raw" } else { "smoothed" }
+            ),
+            SettingLabel::InvertY => {
+                format!("Invert Y: {}", if look.invert_y { "on" } else { "off" })
+            }
+            SettingLabel::Fov => format!("FOV: {:.0}", look.fov),
+            SettingLabel::Recenter => format!(
+                "Third-person orbit camera: {}",
+                if recenter.enabled { "on" } else { "off" }
+            ),
+            SettingLabel::FaceMovement => format!(
+                "Body faces movement: {}",
+                match movement.face_movement {
+                    FaceMovement::Off => "off",
+                    FaceMovement::Input => "input",
+                    FaceMovement::Velocity => "velocity",
+                }
+            ),
+            SettingLabel::FreeLook => format!(
+                "First-person free look: {}",
+                if look.free_look { "on" } else { "off" }
+            ),
+            SettingLabel::MovementSpace => format!(
+                "Movement: {}",
+                match movement.space {
+                    MovementSpace::CameraRelative => "camera-relative",
+                    MovementSpace::WorldAxes => "world axes",
+                }
+            ),
+            SettingLabel::StickSensitivity => {
+                format!("Stick sensitivity: {:.2}", gamepad.look_sensitivity)
+            }
+            SettingLabel::StickCurve => format!("Stick curve: {}", gamepad.look_curve.name()),
+            SettingLabel::MoveStickCurve => {
+                format!("Movement stick curve: {}", gamepad.move_curve.name())
+            }
+            SettingLabel::TouchSensitivity => {
+                format!("Touch sensitivity: {:.3}", touch.look_sensitivity)
+            }
+            SettingLabel::Key(action) if rebinding.0 == Some(action) => {
+                format!("{}: press a key...", action.name())
+            }
+            SettingLabel::Key(action) => {
+                let keys = bindings
+                    .keys(action)
+                    .iter()
+                    .map(|key| format!("{key:?}"))
+                    .collect::<Vec<_>>();
+                format!("{}: {}", action.name(), keys.join(" / "))
+            }
+            SettingLabel::Conflict => match &conflict.0 {
+                Some((action, pending)) => {
+                    format!("{pending}; unbind it to use it for {}?", action.name())
+                }
+                None => String::new(),
+            },
+        };
+
+        let conflicting = match (*label, &conflict.0) {
+            (SettingLabel::Key(action), Some((rebinding, pending))) => {
+                action == *rebinding || action == pending.bound_to
+            }
+            _ => false,
+        };
+        text.sections[0].style.color = if conflicting {
+            CONFLICT_COLOR
+        } else {
+            Color::WHITE
+        };
+    }
+}
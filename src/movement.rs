@@ -0,0 +1,1879 @@
+//! movement: player settings, the tnua-driven walk, jumps, crouching and the other abilities,
+//! grounding and locomotion states, and which way is up
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    builtins::{TnuaBuiltinCrouch, TnuaBuiltinKnockback},
+    control_helpers::TnuaCrouchEnforcer,
+    prelude::{TnuaBuiltinJump, TnuaBuiltinWalk, TnuaController},
+    subservient_sensors::TnuaSubservientSensor,
+    TnuaProximitySensor, TnuaProximitySensorOutput,
+};
+use bevy_tnua_rapier3d::TnuaRapier3dSensorShape;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{wrap_angle, CameraYaw, ControlMode, LookControls},
+    input::{KeyBindings, PressMode, PressToggle, ResolvedIntent},
+    wall_run, GroundMaterial, GroundSlam, GroundSlamAbility, GroundSlamConfig, MovementIntent,
+    Player, SurfaceFriction, WallContact, WallRunAbility, WallRunConfig,
+};
+
+/// what mouse look movement input is relative to
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum MovementSpace {
+    /// forward follows the player's facing
+    #[default]
+    CameraRelative,
+    /// forward is always world -Z, for fixed or isometric cameras
+    WorldAxes,
+}
+
+/// what the body turns to face as it moves, wherever the camera doesn't turn it: moving in
+/// world axes, top-down, and under the orbit camera
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FaceMovement {
+    /// the body only turns with the camera
+    Off,
+    /// the input direction, turning as soon as a key is pressed
+    Input,
+    /// the horizontal velocity, following the actual path through acceleration and collisions
+    Velocity,
+}
+
+/// how a player's capsule stays standing
+#[derive(Reflect, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Upright {
+    /// rotation locked on every axis except by setting the transform, so nothing can tip it
+    Locked,
+    /// free to tip over on impacts, with a spring pulling it back upright. stiffness is the
+    /// angular acceleration per radian of tilt, and damping per radian per second of tipping
+    Spring { stiffness: f32, damping: f32 },
+}
+
+/// per-player body and movement values, read every tick so changing them at runtime (e.g. from
+/// an inspector) takes effect immediately. the resource is what new players spawn with, and is
+/// saved as `player` in settings
+#[derive(Component, Resource, Reflect, Clone, Serialize, Deserialize)]
+#[reflect(Component, Resource)]
+#[serde(default)]
+pub struct PlayerSettings {
+    /// top horizontal speed, in units per second
+    pub speed: f32,
+    /// gap the float spring holds between the bottom of the capsule and the ground. the float
+    /// height follows from it and the capsule's size, see `float_height`
+    pub ground_clearance: f32,
+    pub jump_height: f32,
+    pub capsule_radius: f32,
+    /// length of the capsule between its two hemispheres
+    pub capsule_length: f32,
+    /// full height of the capsule while crouched, hemispheres included. the crouch lowers the
+    /// float height to match, see `crouch_float_offset`
+    pub crouch_height: f32,
+    /// height the first player spawns at; further players spawn alongside
+    pub spawn_height: f32,
+    /// degrees the players spawn turned left from facing -z
+    pub spawn_yaw: f32,
+    /// degrees the cameras spawn looking up
+    pub spawn_pitch: f32,
+    /// whether the capsule is locked upright or can tip over and spring back
+    pub upright: Upright,
+    /// continuous collision detection, so a fast player (a dash, a jump pad, a long fall) can't
+    /// pass through thin colliders between physics ticks. costs an extra sweep per tick
+    /// whenever the player moves quickly, so it's off by default
+    pub ccd: bool,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            speed: 10.0,
+            ground_clearance: 0.25,
+            jump_height: 4.0,
+            capsule_radius: 0.5,
+            capsule_length: 1.0,
+            crouch_height: 1.2,
+            spawn_height: 10.0,
+            spawn_yaw: 0.0,
+            spawn_pitch: 0.0,
+            upright: Upright::Locked,
+            ccd: false,
+        }
+    }
+}
+
+impl PlayerSettings {
+    pub(crate) fn locked_axes(&self) -> LockedAxes {
+        match self.upright {
+            Upright::Locked => LockedAxes::ROTATION_LOCKED,
+            Upright::Spring { .. } => LockedAxes::empty(),
+        }
+    }
+
+    /// height the character's centre floats above the ground: the capsule's half height, with
+    /// its feet `ground_clearance` clear of the ground
+    pub fn float_height(&self) -> f32 {
+        self.capsule_half_height() + self.ground_clearance
+    }
+
+    /// from the centre to the bottom of the capsule
+    pub fn capsule_half_height(&self) -> f32 {
+        self.capsule_radius + self.capsule_length * 0.5
+    }
+
+    /// from the centre to the bottom of the crouched capsule, no shorter than a ball and no
+    /// taller than standing
+    pub fn crouch_half_height(&self) -> f32 {
+        (self.crouch_height * 0.5).clamp(self.capsule_radius, self.capsule_half_height())
+    }
+
+    /// how far crouching lowers the float height: the crouched capsule floats at its own half
+    /// height plus `ground_clearance`, keeping its feet as far off the ground as standing
+    pub fn crouch_float_offset(&self) -> f32 {
+        self.crouch_half_height() + self.ground_clearance - self.float_height()
+    }
+
+    pub(crate) fn mesh(&self) -> Capsule3d {
+        Capsule3d::new(self.capsule_radius, self.capsule_length)
+    }
+
+    pub(crate) fn collider(&self) -> Collider {
+        let half = Vec3::Y * self.capsule_length * 0.5;
+        Collider::capsule(-half, half, self.capsule_radius)
+    }
+
+    pub(crate) fn crouch_mesh(&self) -> Capsule3d {
+        Capsule3d::new(
+            self.capsule_radius,
+            (self.crouch_half_height() - self.capsule_radius) * 2.0,
+        )
+    }
+
+    /// the shorter capsule swapped in while crouched, centred where the standing one is
+    pub(crate) fn crouch_collider(&self) -> Collider {
+        let half = Vec3::Y * (self.crouch_half_height() - self.capsule_radius);
+        Collider::capsule(-half, half, self.capsule_radius)
+    }
+
+    /// keeps the crouch fed while there's no headroom to stand up, casting a disc up from the
+    /// top of the crouched capsule (see sense_crouch_headroom)
+    pub(crate) fn crouch_enforcer(&self) -> TnuaCrouchEnforcer {
+        let radius = self.capsule_radius - 0.01;
+        TnuaCrouchEnforcer::new(Vec3::Y * self.crouch_half_height(), move |sensor| {
+            sensor.insert(TnuaRapier3dSensorShape(Collider::cylinder(0.0, radius)));
+        })
+    }
+
+    /// tnua's ground sensor, just inside the capsule so it doesn't catch walls
+    pub(crate) fn sensor(&self) -> TnuaRapier3dSensorShape {
+        TnuaRapier3dSensorShape(Collider::cylinder(0.0, self.capsule_radius - 0.01))
+    }
+}
+
+/// movement tuning fed into the tnua walk basis and jump action
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementConfig {
+    /// horizontal acceleration on the ground, in units per second squared. around 20 feels
+    /// floaty, 60 is tnua's default and 150+ is near-instant; braking and turning get up to
+    /// twice this
+    pub acceleration: f32,
+    /// horizontal acceleration in the air. 0 disables air control, 20 is tnua's default and
+    /// values up to `acceleration` give full air control
+    pub air_acceleration: f32,
+    /// top speed multiplier while sprinting
+    pub sprint_multiplier: f32,
+    /// seconds to ramp between walk and sprint speed, both up and down; 0 snaps
+    pub sprint_ramp: f32,
+    /// fixed ticks of forward input needed before sprint engages, so tapping sprint while
+    /// strafing or standing still doesn't give a burst of speed
+    pub sprint_forward_ticks: u32,
+    /// fixed ticks without forward input before a toggled sprint turns itself off
+    pub sprint_cancel_ticks: u32,
+    /// top speed multiplier while crouched
+    pub crouch_speed_multiplier: f32,
+    pub space: MovementSpace,
+    pub face_movement: FaceMovement,
+    /// how quickly the body turns to face its movement, as an exponential rate per second
+    pub turn_speed: f32,
+    /// seconds tnua has to report a grounded player airborne before it counts, so running over
+    /// small bumps doesn't flicker. jumps count straight away
+    pub ground_debounce: f32,
+    /// the most force the player shoves dynamic bodies it walks into with, as newtons per unit
+    /// of speed towards them. 0 leaves it to the collision alone
+    pub push_strength: f32,
+    /// heaviest body, in kilograms, the player can push. lighter bodies are pushed at nearly
+    /// walking speed, slowing the closer they are to it; at or over it the player stops
+    /// against them
+    pub push_max_mass: f32,
+    /// fraction of upward speed lost when the head hits a ceiling; 1 stops dead
+    pub ceiling_damping: f32,
+    /// furthest drop below the float height that a grounded player is pulled down onto, so
+    /// walking down stairs and off kerbs stays grounded. past it they fall, and coyote time
+    /// starts. 0 leaves every drop a fall
+    pub max_snap_down: f32,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: 60.0,
+            air_acceleration: 20.0,
+            sprint_multiplier: 1.6,
+            sprint_ramp: 0.25,
+            sprint_forward_ticks: 6,
+            sprint_cancel_ticks: 30,
+            crouch_speed_multiplier: 0.5,
+            space: MovementSpace::CameraRelative,
+            face_movement: FaceMovement::Input,
+            turn_speed: 12.0,
+            ground_debounce: 0.04,
+            push_strength: 40.0,
+            push_max_mass: 50.0,
+            ceiling_damping: 1.0,
+            max_snap_down: 0.5,
+        }
+    }
+}
+
+impl MovementConfig {
+    /// the way a player moving along `direction` at `velocity` turns to face, or zero to keep
+    /// its facing
+    fn facing(&self, direction: Vec3, velocity: Vec3, up: &WorldUp) -> Vec3 {
+        /// horizontal speed below which facing velocity holds still
+        const FACING_SPEED: f32 = 0.5;
+        match self.face_movement {
+            FaceMovement::Off => Vec3::ZERO,
+            FaceMovement::Input => direction,
+            FaceMovement::Velocity if up.horizontal(velocity).length() > FACING_SPEED => {
+                up.horizontal(velocity)
+            }
+            // when idle the body keeps its last facing
+            FaceMovement::Velocity => Vec3::ZERO,
+        }
+    }
+}
+
+/// coyote time, jump buffering and air jumps, resolved together by JumpState::resolve
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JumpConfig {
+    /// seconds after leaving the ground that a press still counts as a ground jump
+    pub coyote_time: f32,
+    /// seconds a press that couldn't jump is remembered, jumping on landing if still fresh
+    pub buffer_time: f32,
+    /// extra jumps before landing again; 1 for a double jump
+    pub air_jumps: u32,
+    /// only start a ground jump on a fresh press. turned off, holding jump jumps again a tick
+    /// after each landing, bunny hop style. coyote, buffered and air jumps always need a press
+    pub jump_requires_release: bool,
+    /// which way a jump off the ground goes
+    pub jump_direction: JumpDirection,
+    /// steepest ground, in degrees, a SurfaceNormal jump leans out from; jumps off anything
+    /// steeper go straight up, so a steep slope can't throw the player sideways
+    pub max_jump_incline: f32,
+}
+
+/// which way a jump off the ground goes
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum JumpDirection {
+    /// straight along WorldUp whatever the ground's slope, keeping only the walk's speed across
+    #[default]
+    WorldUp,
+    /// out along the ground's normal, leaning away from slopes up to `max_jump_incline`
+    SurfaceNormal,
+}
+
+impl Default for JumpConfig {
+    fn default() -> Self {
+        Self {
+            coyote_time: 0.15,
+            buffer_time: 0.2,
+            air_jumps: 0,
+            jump_requires_release: true,
+            jump_direction: JumpDirection::WorldUp,
+            max_jump_incline: 45.0,
+        }
+    }
+}
+
+/// which rule started a jump
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum JumpKind {
+    /// pressed while grounded
+    Ground,
+    /// pressed shortly after leaving the ground
+    Coyote,
+    /// pressed shortly before landing
+    Buffered,
+    /// pressed in the air with air jumps left
+    Air,
+}
+
+/// jump bookkeeping of a player
+#[derive(Component, Default)]
+struct JumpState {
+    /// seconds since the player was last grounded, not counting coyote time
+    airborne_time: f32,
+    /// cleared by any jump, so coyote time can't follow a jump
+    coyote_available: bool,
+    /// age in seconds of a press waiting for the ground
+    buffered: Option<f32>,
+    air_jumps_used: u32,
+    was_pressed: bool,
+    /// a jump is running and the press that started it is still held
+    holding: bool,
+}
+
+impl JumpState {
+    /// advance by `dt` and pick the jump to start this tick, if any. precedence is grounded >
+    /// coyote > buffered > air jump: a fresh press on the ground or within coyote time jumps
+    /// right away, a buffered press jumps on landing, and only then does a press in the air
+    /// spend an air jump. a press none of these take is buffered. air jumps and coyote time
+    /// are only restored by real grounding. without `jump_requires_release`, jump held on the
+    /// ground counts as a ground jump's press too, once whatever jump it held has landed
+    fn resolve(
+        &mut self,
+        config: &JumpConfig,
+        grounded: bool,
+        pressed: bool,
+        dt: f32,
+    ) -> Option<JumpKind> {
+        let just_pressed = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+        self.holding &= pressed;
+        // a held jump ends on landing. it goes unfed for the landing tick, which tnua needs
+        // before it'll start it again
+        let landed = grounded && self.airborne_time > 0.0;
+        if landed {
+            self.holding = false;
+        }
+        let held = pressed && !config.jump_requires_release && !self.holding && !landed;
+
+        if grounded {
+            self.airborne_time = 0.0;
+            self.coyote_available = true;
+            self.air_jumps_used = 0;
+        } else {
+            self.airborne_time += dt;
+        }
+        self.buffered = self
+            .buffered
+            .map(|age| age + dt)
+            .filter(|&age| age <= config.buffer_time);
+
+        let kind = if (just_pressed || held) && grounded {
+            Some(JumpKind::Ground)
+        } else if just_pressed && self.coyote_available && self.airborne_time <= config.coyote_time
+        {
+            Some(JumpKind::Coyote)
+        } else if grounded && self.buffered.is_some() {
+            Some(JumpKind::Buffered)
+        } else if just_pressed && self.air_jumps_used < config.air_jumps {
+            Some(JumpKind::Air)
+        } else {
+            None
+        };
+
+        match kind {
+            Some(kind) => {
+                self.coyote_available = false;
+                self.buffered = None;
+                self.holding = pressed;
+                if kind == JumpKind::Air {
+                    self.air_jumps_used += 1;
+                }
+            }
+            None if just_pressed => self.buffered = Some(0.0),
+            None => {}
+        }
+        kind
+    }
+}
+
+#[cfg(test)]
+mod jump_state_tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    /// resolve `ticks` ticks in a row, returning the last tick's jump
+    fn run(
+        state: &mut JumpState,
+        config: &JumpConfig,
+        grounded: bool,
+        pressed: bool,
+        ticks: usize,
+    ) -> Option<JumpKind> {
+        (0..ticks)
+            .map(|_| state.resolve(config, grounded, pressed, DT))
+            .last()
+            .flatten()
+    }
+
+    /// a state that stood on the ground and then fell for `seconds` without jumping
+    fn falling_for(config: &JumpConfig, seconds: f32) -> JumpState {
+        let mut state = JumpState::default();
+        run(&mut state, config, true, false, 5);
+        run(
+            &mut state,
+            config,
+            false,
+            false,
+            (seconds / DT).round() as usize,
+        );
+        state
+    }
+
+    #[test]
+    fn grounded_press_jumps_from_the_ground() {
+        let config = JumpConfig::default();
+        let mut state = JumpState::default();
+        assert_eq!(run(&mut state, &config, true, false, 5), None);
+        assert_eq!(
+            run(&mut state, &config, true, true, 1),
+            Some(JumpKind::Ground)
+        );
+        // held, it doesn't jump again
+        assert_eq!(run(&mut state, &config, true, true, 10), None);
+    }
+
+    #[test]
+    fn coyote_jumps_only_within_coyote_time() {
+        let config = JumpConfig::default();
+        let mut state = falling_for(&config, config.coyote_time * 0.5);
+        assert_eq!(
+            run(&mut state, &config, false, true, 1),
+            Some(JumpKind::Coyote)
+        );
+
+        let mut state = falling_for(&config, config.coyote_time * 2.0);
+        assert_eq!(run(&mut state, &config, false, true, 1), None);
+    }
+
+    #[test]
+    fn coyote_time_goes_before_air_jumps() {
+        let config = JumpConfig {
+            air_jumps: 1,
+            ..default()
+        };
+        let mut state = falling_for(&config, config.coyote_time * 0.5);
+        assert_eq!(
+            run(&mut state, &config, false, true, 1),
+            Some(JumpKind::Coyote)
+        );
+        assert_eq!(state.air_jumps_used, 0);
+
+        // the air jump is still there for a second press
+        run(&mut state, &config, false, false, 1);
+        assert_eq!(
+            run(&mut state, &config, false, true, 1),
+            Some(JumpKind::Air)
+        );
+    }
+
+    #[test]
+    fn a_coyote_jump_cant_follow_a_jump() {
+        let config = JumpConfig::default();
+        let mut state = JumpState::default();
+        run(&mut state, &config, true, false, 5);
+        assert_eq!(
+            run(&mut state, &config, true, true, 1),
+            Some(JumpKind::Ground)
+        );
+        run(&mut state, &config, false, false, 2);
+        assert_eq!(run(&mut state, &config, false, true, 1), None);
+    }
+
+    #[test]
+    fn a_press_before_landing_jumps_on_landing_while_fresh() {
+        let config = JumpConfig::default();
+        let mut state = falling_for(&config, 1.0);
+        assert_eq!(run(&mut state, &config, false, true, 1), None);
+        assert_eq!(
+            run(&mut state, &config, false, false, 3),
+            None,
+            "buffered presses wait for the ground"
+        );
+        assert_eq!(
+            run(&mut state, &config, true, false, 1),
+            Some(JumpKind::Buffered)
+        );
+
+        // gone stale, the press is dropped
+        let mut state = falling_for(&config, 1.0);
+        run(&mut state, &config, false, true, 1);
+        let stale = (config.buffer_time * 2.0 / DT) as usize;
+        run(&mut state, &config, false, false, stale);
+        assert_eq!(run(&mut state, &config, true, false, 1), None);
+    }
+
+    #[test]
+    fn air_jumps_take_presses_that_would_otherwise_buffer() {
+        let config = JumpConfig {
+            air_jumps: 1,
+            ..default()
+        };
+        let mut state = falling_for(&config, 1.0);
+        assert_eq!(
+            run(&mut state, &config, false, true, 1),
+            Some(JumpKind::Air)
+        );
+        assert!(state.buffered.is_none());
+
+        // out of air jumps, the next press is buffered for the landing instead
+        run(&mut state, &config, false, false, 1);
+        assert_eq!(run(&mut state, &config, false, true, 1), None);
+        run(&mut state, &config, false, false, 1);
+        assert_eq!(
+            run(&mut state, &config, true, false, 1),
+            Some(JumpKind::Buffered)
+        );
+    }
+
+    #[test]
+    fn air_jumps_come_back_only_on_real_grounding() {
+        let config = JumpConfig {
+            air_jumps: 1,
+            ..default()
+        };
+        let mut state = falling_for(&config, 1.0);
+        assert_eq!(
+            run(&mut state, &config, false, true, 1),
+            Some(JumpKind::Air)
+        );
+
+        // falling on, nothing restores it
+        run(&mut state, &config, false, false, 60);
+        assert_eq!(run(&mut state, &config, false, true, 1), None);
+        assert_eq!(state.air_jumps_used, 1);
+
+        // touching down does, after the buffered press from above has been spent
+        run(&mut state, &config, false, false, 60);
+        run(&mut state, &config, true, false, 1);
+        assert_eq!(state.air_jumps_used, 0);
+        run(&mut state, &config, false, false, 30);
+        assert_eq!(
+            run(&mut state, &config, false, true, 1),
+            Some(JumpKind::Air)
+        );
+    }
+}
+
+/// event sent when a player lands after being airborne
+#[derive(Event)]
+pub struct PlayerLanded {
+    pub player: Entity,
+    /// downward speed just before touching the ground
+    pub impact_speed: f32,
+    /// the landing of a ground slam
+    pub slam: bool,
+}
+
+/// event sent when a rising player's head hits a ceiling, e.g. for a camera jolt or a sound
+#[derive(Event)]
+pub struct CeilingBonk {
+    pub player: Entity,
+    /// upward speed just before the bonk
+    pub speed: f32,
+}
+
+/// tracks the player's airborne state to detect landings
+#[derive(Component, Default)]
+pub(crate) struct LandingTracker {
+    airborne: bool,
+    /// fastest downward speed reached while airborne
+    fall_speed: f32,
+}
+
+/// sensor that launches the player on contact, without needing to jump
+#[derive(Component, Clone, Copy, Debug)]
+pub struct JumpPad {
+    pub launch_speed: f32,
+    /// launch direction in world space; normalized when applied
+    pub direction: Vec3,
+    /// keep the player's velocity across WorldUp, adding the launch to it instead of replacing
+    /// it. only the speed along up is replaced
+    pub preserve_horizontal: bool,
+}
+
+impl JumpPad {
+    /// the velocity a player moving at `velocity` leaves the pad with
+    fn launch_velocity(&self, velocity: Vec3, up: &WorldUp) -> Vec3 {
+        let launch = self.direction.normalize_or_zero() * self.launch_speed;
+        if !self.preserve_horizontal {
+            return launch;
+        }
+        let velocity = up.to_local(velocity);
+        let launch = up.to_local(launch);
+        up.from_local(Vec3::new(
+            velocity.x + launch.x,
+            launch.y,
+            velocity.z + launch.z,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod jump_pad_tests {
+    use super::*;
+
+    fn pad(preserve_horizontal: bool) -> JumpPad {
+        JumpPad {
+            launch_speed: 10.0,
+            direction: Vec3::Y,
+            preserve_horizontal,
+        }
+    }
+
+    #[test]
+    fn replaces_only_the_speed_along_up() {
+        let up = WorldUp::default();
+        let launched = pad(true).launch_velocity(Vec3::new(2.0, -5.0, 1.0), &up);
+        assert!(
+            launched.distance(Vec3::new(2.0, 10.0, 1.0)) < 1e-5,
+            "{launched}"
+        );
+        let launched = pad(false).launch_velocity(Vec3::new(2.0, -5.0, 1.0), &up);
+        assert!(launched.distance(Vec3::Y * 10.0) < 1e-5, "{launched}");
+    }
+
+    #[test]
+    fn keeps_the_speed_across_a_tilted_up() {
+        // up along +x: the pad points across it, so the launch adds sideways
+        let up = WorldUp {
+            up: Dir3::X,
+            ..default()
+        };
+        let launched = pad(true).launch_velocity(Vec3::new(-5.0, 3.0, 1.0), &up);
+        assert!(
+            launched.distance(Vec3::new(0.0, 13.0, 1.0)) < 1e-5,
+            "{launched}"
+        );
+    }
+}
+
+/// eased sprint state of a player
+#[derive(Component, Default)]
+pub(crate) struct Sprint {
+    /// 0 at walk speed, 1 at full sprint speed
+    factor: f32,
+    /// consecutive fixed ticks with forward input
+    forward_ticks: u32,
+    toggle: PressToggle,
+    /// consecutive fixed ticks a toggled sprint has been on without forward input
+    idle_ticks: u32,
+}
+
+/// a movement action, like jumping, crouching or a dash, run by a player's Abilities. new
+/// actions implement this rather than adding to update_player
+pub trait Ability: Send + Sync + 'static {
+    /// identifies the ability for Abilities::remove and exclusions
+    fn name(&self) -> &'static str;
+
+    /// abilities that block this one while they're active, e.g. jump excludes crouch
+    fn excludes(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// run once per physics tick, before the walk basis is set. feed tnua actions through
+    /// `ctx.controller` and return whether the ability is active this tick
+    fn update(&mut self, ctx: &mut AbilityContext) -> bool;
+}
+
+/// what an ability sees and acts on each tick
+pub struct AbilityContext<'a> {
+    pub controller: &'a mut TnuaController,
+    pub crouch_enforcer: &'a mut TnuaCrouchEnforcer,
+    pub intent: &'a MovementIntent,
+    /// the movement input turned into world space, across up
+    pub direction: Vec3,
+    /// the body's velocity as the tick starts
+    pub velocity: Vec3,
+    pub up: &'a WorldUp,
+    pub state: PlayerMovementState,
+    pub settings: &'a PlayerSettings,
+    pub dt: f32,
+    /// the head hit a ceiling this tick, so anything pushing the player up should stop
+    pub ceiling: bool,
+    /// the wall beside an airborne player, if there's one to run along
+    pub wall: Option<WallContact>,
+    /// the player's ground slam, for GroundSlamAbility to start and recover from
+    pub slam: &'a mut GroundSlam,
+    /// an ability this one excludes is active, so it shouldn't act
+    pub blocked: bool,
+    /// seconds left on this ability's cooldown, counting down by itself; set it when the
+    /// ability fires
+    pub cooldown: f32,
+    /// multiplies the walk speed, for abilities that slow or speed up the player
+    pub speed_multiplier: f32,
+    /// cleared by abilities that can't be used while sprinting
+    pub allow_sprint: bool,
+    /// set by an ability using the jump button this tick, e.g. to jump off a wall, so the jump
+    /// ability leaves the press alone
+    pub jump_taken: bool,
+    /// normal of the ground under a grounded player
+    pub ground_normal: Option<Vec3>,
+    /// velocity update_player adds to the body once the abilities have run, for a push the
+    /// actions fed don't give
+    pub launch: Vec3,
+    pub(crate) movement: &'a MovementConfig,
+    jump: &'a JumpConfig,
+    pub(crate) wall_run: &'a WallRunConfig,
+    /// None without GroundSlamPlugin, leaving GroundSlamAbility idle
+    pub(crate) ground_slam: Option<&'a GroundSlamConfig>,
+    bindings: &'a KeyBindings,
+}
+
+impl AbilityContext<'_> {
+    /// not blocked and off cooldown
+    pub fn ready(&self) -> bool {
+        !self.blocked && self.cooldown <= 0.0
+    }
+}
+
+struct AbilitySlot {
+    ability: Box<dyn Ability>,
+    cooldown: f32,
+    active: bool,
+}
+
+/// the abilities of a player, updated in order every physics tick. add and remove them at
+/// runtime, e.g. from a pickup granting a double jump
+#[derive(Component)]
+pub struct Abilities(Vec<AbilitySlot>);
+
+impl Default for Abilities {
+    /// ground slam, crouch, wall run and jump. the slam goes first so an airborne crouch press
+    /// slams rather than crouching, crouch before the jump so it can block a jump on the same
+    /// tick and the wall run before the jump so it can take the press to jump off a wall
+    fn default() -> Self {
+        Self(Vec::new())
+            .with(GroundSlamAbility::default())
+            .with(CrouchAbility::default())
+            .with(WallRunAbility::default())
+            .with(JumpAbility::default())
+    }
+}
+
+impl Abilities {
+    /// no abilities at all
+    pub fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    /// add, by value
+    pub fn with(mut self, ability: impl Ability) -> Self {
+        self.add(ability);
+        self
+    }
+
+    /// add an ability after the others, or replace the one with the same name in place
+    pub fn add(&mut self, ability: impl Ability) {
+        let slot = AbilitySlot {
+            ability: Box::new(ability),
+            cooldown: 0.0,
+            active: false,
+        };
+        match self.position(slot.ability.name()) {
+            Some(i) => self.0[i] = slot,
+            None => self.0.push(slot),
+        }
+    }
+
+    /// remove an ability by name, returning whether it was there
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|slot| slot.ability.name() != name);
+        self.0.len() != len
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// whether the named ability was active on its last update
+    pub fn is_active(&self, name: &str) -> bool {
+        self.position(name).is_some_and(|i| self.0[i].active)
+    }
+
+    /// seconds left on the named ability's cooldown
+    pub fn cooldown(&self, name: &str) -> Option<f32> {
+        self.position(name).map(|i| self.0[i].cooldown)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.0.iter().position(|slot| slot.ability.name() == name)
+    }
+
+    /// update every ability in order. each is blocked while one it excludes is active, as of
+    /// this tick for abilities before it and the last tick for those after
+    fn update(&mut self, ctx: &mut AbilityContext) {
+        for i in 0..self.0.len() {
+            let excludes = self.0[i].ability.excludes();
+            ctx.blocked = self
+                .0
+                .iter()
+                .any(|other| other.active && excludes.contains(&other.ability.name()));
+
+            let slot = &mut self.0[i];
+            ctx.cooldown = (slot.cooldown - ctx.dt).max(0.0);
+            slot.active = slot.ability.update(ctx);
+            slot.cooldown = ctx.cooldown;
+        }
+    }
+}
+
+/// the jump, with coyote time, buffering and air jumps from JumpConfig
+#[derive(Default)]
+pub struct JumpAbility {
+    state: JumpState,
+    air_jumps: Option<u32>,
+    requires_release: Option<bool>,
+    direction: Option<JumpDirection>,
+}
+
+impl JumpAbility {
+    /// override JumpConfig's air jumps, e.g. 1 for a double jump pickup
+    pub fn with_air_jumps(mut self, air_jumps: u32) -> Self {
+        self.air_jumps = Some(air_jumps);
+        self
+    }
+
+    /// override JumpConfig's `jump_requires_release`; false jumps again on each landing while
+    /// jump is held
+    pub fn with_requires_release(mut self, requires_release: bool) -> Self {
+        self.requires_release = Some(requires_release);
+        self
+    }
+
+    /// override JumpConfig's `jump_direction`
+    pub fn with_direction(mut self, direction: JumpDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+}
+
+impl Ability for JumpAbility {
+    fn name(&self) -> &'static str {
+        "jump"
+    }
+
+    /// crouch takes the action slot, so jumping waits until the crouch is released, and a
+    /// ground slam doesn't let up until the player has recovered from it
+    fn excludes(&self) -> &'static [&'static str] {
+        &["crouch", "ground slam"]
+    }
+
+    fn update(&mut self, ctx: &mut AbilityContext) -> bool {
+        // bonking stops feeding the jump like releasing it would, cutting it short
+        if ctx.ceiling {
+            self.state.holding = false;
+        }
+        // another ability has the button, so it's neither held for a jump nor a fresh press
+        if ctx.jump_taken {
+            self.state.holding = false;
+            self.state.was_pressed = ctx.intent.jump;
+        }
+        let config = JumpConfig {
+            air_jumps: self.air_jumps.unwrap_or(ctx.jump.air_jumps),
+            jump_requires_release: self
+                .requires_release
+                .unwrap_or(ctx.jump.jump_requires_release),
+            jump_direction: self.direction.unwrap_or(ctx.jump.jump_direction),
+            ..ctx.jump.clone()
+        };
+        // a blocked jump still runs, to keep coyote time and air jumps up to date
+        let started = self.state.resolve(
+            &config,
+            ctx.state.is_grounded(),
+            ctx.intent.jump && !ctx.blocked,
+            ctx.dt,
+        );
+        // tnua only jumps along up, so lean a jump off the ground out along its normal here
+        let leans = matches!(started, Some(JumpKind::Ground | JumpKind::Buffered))
+            && config.jump_direction == JumpDirection::SurfaceNormal;
+        let normal = ctx.ground_normal.filter(|normal| {
+            leans && normal.angle_between(*ctx.up.up).to_degrees() <= config.max_jump_incline
+        });
+        if let Some(normal) = normal {
+            let takeoff = (2.0 * ctx.up.gravity * ctx.settings.jump_height).sqrt();
+            ctx.launch += ctx.up.horizontal(normal) * takeoff;
+        }
+        let jumping = started.is_some() || self.state.holding;
+        if jumping {
+            // keep feeding the jump while it's held; tnua only needs to allow it in the air
+            ctx.controller.action(TnuaBuiltinJump {
+                height: ctx.settings.jump_height,
+                allow_in_air: true,
+                input_buffer_time: 0.0,
+                shorten_extra_gravity: 0.0,
+                ..default()
+            });
+        }
+        jumping
+    }
+}
+
+/// the crouch, held or toggled per KeyBindings, slowing the player and stopping sprint
+#[derive(Default)]
+pub struct CrouchAbility {
+    toggle: PressToggle,
+}
+
+impl Ability for CrouchAbility {
+    fn name(&self) -> &'static str {
+        "crouch"
+    }
+
+    fn excludes(&self) -> &'static [&'static str] {
+        &["ground slam"]
+    }
+
+    fn update(&mut self, ctx: &mut AbilityContext) -> bool {
+        let crouching = self
+            .toggle
+            .update(ctx.bindings.crouch_mode, ctx.intent.crouch)
+            && !ctx.blocked;
+        // an enforced crouch keeps going after the button is released, until there's headroom
+        if crouching
+            || ctx
+                .controller
+                .concrete_action::<TnuaBuiltinCrouch>()
+                .is_some()
+        {
+            ctx.speed_multiplier *= ctx.movement.crouch_speed_multiplier;
+            ctx.allow_sprint = false;
+        }
+        if crouching {
+            ctx.controller
+                .action(ctx.crouch_enforcer.enforcing(TnuaBuiltinCrouch {
+                    float_offset: ctx.settings.crouch_float_offset(),
+                    ..default()
+                }));
+        }
+        crouching
+    }
+}
+
+/// what a player is doing, for picking an animation
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum LocomotionState {
+    #[default]
+    Idle,
+    Walk,
+    Run,
+    /// airborne and rising
+    Jump,
+    /// airborne and falling
+    Fall,
+    Crouch,
+}
+
+/// speed thresholds between locomotion states
+#[derive(Resource, Clone)]
+pub struct LocomotionConfig {
+    /// horizontal speed below which a grounded player is idle
+    pub walk_speed: f32,
+    /// horizontal speed from which a grounded player runs, even without sprinting
+    pub run_speed: f32,
+    /// vertical speed that counts as clearly rising or falling. slower than this, around the
+    /// apex or just after walking off a ledge, an airborne player keeps its last state
+    pub apex_speed: f32,
+    /// seconds in the air after which a player falls even while still rising
+    pub max_rise_time: f32,
+}
+
+impl Default for LocomotionConfig {
+    fn default() -> Self {
+        Self {
+            walk_speed: 0.5,
+            run_speed: 12.0,
+            apex_speed: 0.5,
+            max_rise_time: 2.0,
+        }
+    }
+}
+
+/// whether a player is on the ground, debounced so brief airborne blips from tnua don't count.
+/// the rest of the controller reads this rather than tnua directly
+#[derive(Component, Clone, Copy, PartialEq, Default, Debug)]
+pub struct Grounded {
+    grounded: bool,
+    /// seconds tnua has reported airborne while this still reads grounded
+    pending: f32,
+}
+
+impl Grounded {
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// step with tnua's grounding for this tick. `launched` is set while jumping or knocked back,
+    /// which leaves the ground at once
+    pub fn update(&mut self, debounce: f32, airborne: bool, launched: bool, dt: f32) {
+        if launched {
+            self.grounded = false;
+            self.pending = 0.0;
+        } else if !airborne {
+            self.grounded = true;
+            self.pending = 0.0;
+        } else if self.grounded {
+            self.pending += dt;
+            if self.pending >= debounce {
+                self.grounded = false;
+                self.pending = 0.0;
+            }
+        }
+    }
+}
+
+/// what a player is doing, worked out once per physics tick from its debounced grounding and
+/// controller. other systems read this rather than checking grounding themselves
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum PlayerMovementState {
+    #[default]
+    Idle,
+    Walking,
+    Sprinting,
+    Crouching,
+    Airborne,
+    /// running along a wall, see WallRunAbility
+    WallRunning,
+    /// driving down in a ground slam, see GroundSlamAbility
+    GroundSlamming,
+}
+
+impl PlayerMovementState {
+    /// airborne wins over crouching, which wins over movement input. wall running and ground
+    /// slamming are set on top of this, from the player's Abilities and GroundSlam
+    pub fn from_controller(grounded: bool, crouched: bool, moving: bool, sprinting: bool) -> Self {
+        if !grounded {
+            Self::Airborne
+        } else if crouched {
+            Self::Crouching
+        } else if !moving {
+            Self::Idle
+        } else if sprinting {
+            Self::Sprinting
+        } else {
+            Self::Walking
+        }
+    }
+
+    pub fn is_grounded(self) -> bool {
+        !matches!(
+            self,
+            Self::Airborne | Self::WallRunning | Self::GroundSlamming
+        )
+    }
+}
+
+/// event sent once per change of a player's PlayerMovementState
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MovementStateChanged {
+    pub player: Entity,
+    pub from: PlayerMovementState,
+    pub to: PlayerMovementState,
+}
+
+/// seconds since a player last left the ground, zero while grounded
+#[derive(Component, Clone, Copy, PartialEq, Default, Debug)]
+pub struct AirTime(pub f32);
+
+impl LocomotionState {
+    /// the state for a player moving at `velocity`, after `air_time` seconds in the air and
+    /// coming from `previous`. airborne wins over crouching, which wins over speed; sprinting
+    /// runs at any speed above idle
+    pub fn from_motion(
+        config: &LocomotionConfig,
+        velocity: Vec3,
+        grounded: bool,
+        crouched: bool,
+        sprinting: bool,
+        air_time: f32,
+        previous: Self,
+    ) -> Self {
+        let speed = velocity.xz().length();
+        if !grounded {
+            let rising = velocity.y > config.apex_speed && air_time < config.max_rise_time;
+            if rising {
+                Self::Jump
+            } else if velocity.y < -config.apex_speed || air_time >= config.max_rise_time {
+                Self::Fall
+            } else {
+                // near the apex the vertical speed hovers around zero, so hold the last
+                // airborne state rather than flickering. leaving the ground this slowly falls
+                match previous {
+                    Self::Jump => Self::Jump,
+                    _ => Self::Fall,
+                }
+            }
+        } else if crouched {
+            Self::Crouch
+        } else if speed < config.walk_speed {
+            Self::Idle
+        } else if sprinting || speed >= config.run_speed {
+            Self::Run
+        } else {
+            Self::Walk
+        }
+    }
+}
+
+/// event sent when a player's LocomotionState changes
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LocomotionChanged {
+    pub player: Entity,
+    pub from: LocomotionState,
+    pub to: LocomotionState,
+}
+
+impl Sprint {
+    /// the sprint factor eased for speed interpolation, and anything else that scales with sprint
+    fn eased(&self) -> f32 {
+        let t = self.factor;
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// which way is up for players, and gravity with it. everything in the controller that needs an
+/// up direction or a horizontal plane gets it from here rather than assuming +y, and tnua takes
+/// its up from gravity, so tilting this tilts the whole controller
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct WorldUp {
+    pub up: Dir3,
+    /// gravity's strength, pulling along -up
+    pub gravity: f32,
+}
+
+impl Default for WorldUp {
+    fn default() -> Self {
+        Self {
+            up: Dir3::Y,
+            gravity: 9.81,
+        }
+    }
+}
+
+impl WorldUp {
+    /// gravity tilted 20° towards +x, so flat ground plays like a hillside
+    pub fn tilted() -> Self {
+        Self {
+            up: Dir3::new_unchecked(Quat::from_rotation_z(20f32.to_radians()) * Vec3::Y),
+            gravity: 9.81,
+        }
+    }
+
+    /// the rotation taking +y to up, which upright players stand in
+    pub fn frame(&self) -> Quat {
+        Quat::from_rotation_arc(Vec3::Y, *self.up)
+    }
+
+    /// a world vector in the frame where +y is up
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        self.frame().inverse() * v
+    }
+
+    /// a vector from the frame where +y is up, in world space
+    pub fn from_local(&self, v: Vec3) -> Vec3 {
+        self.frame() * v
+    }
+
+    /// the part of `v` along up
+    pub fn vertical(&self, v: Vec3) -> f32 {
+        v.dot(*self.up)
+    }
+
+    /// `v` flattened onto the plane across up
+    pub fn horizontal(&self, v: Vec3) -> Vec3 {
+        v - *self.up * self.vertical(v)
+    }
+
+    /// an upright body turned `yaw` radians left from facing local -z
+    pub fn rotation(&self, yaw: f32) -> Quat {
+        self.frame() * Quat::from_rotation_y(yaw)
+    }
+
+    /// a body's yaw around up, as passed to `rotation`
+    pub fn yaw(&self, transform: &Transform) -> f32 {
+        (self.frame().inverse() * transform.rotation)
+            .to_euler(EulerRot::YXZ)
+            .0
+    }
+
+    /// turn a body `angle` radians left around up, keeping any tilt it has
+    pub fn turn(&self, transform: &mut Transform, angle: f32) {
+        transform.rotate_axis(self.up, angle);
+    }
+
+    /// turn a body towards facing `direction` at `speed`, frame-rate independently
+    fn turn_towards(&self, transform: &mut Transform, direction: Vec3, speed: f32, dt: f32) {
+        let direction = self.to_local(direction);
+        let facing = (-direction.x).atan2(-direction.z);
+        let remaining = wrap_angle(facing - self.yaw(transform));
+        self.turn(transform, remaining * (1.0 - (-speed * dt).exp()));
+    }
+}
+
+/// point gravity along WorldUp whenever it changes, and stand players up in the new up
+pub(crate) fn apply_world_up(
+    up: Res<WorldUp>,
+    mut previous: Local<Option<Dir3>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut players: Query<&mut Transform, With<Player>>,
+) {
+    if !up.is_changed() {
+        return;
+    }
+    rapier_config.gravity = -*up.up * up.gravity;
+    // players already spawn standing in the first
+    let Some(from) = previous.replace(up.up) else {
+        return;
+    };
+    let tilt = Quat::from_rotation_arc(*from, *up.up);
+    for mut transform in &mut players {
+        transform.rotation = tilt * transform.rotation;
+    }
+}
+
+/// whether a player has the shorter crouched capsule in, see swap_crouch_shape
+#[derive(Component, Default)]
+pub(crate) struct CrouchedShape(pub(crate) bool);
+
+/// tnua casts the crouch enforcer's sensor up by the crouch's depth, but the crouched capsule
+/// is shorter as well as lower, so standing up takes twice that: carry the cast on over the
+/// rest when it found nothing
+pub(crate) fn sense_crouch_headroom(
+    rapier_context: Res<RapierContext>,
+    mut sensors: Query<(
+        &TnuaSubservientSensor,
+        &GlobalTransform,
+        &TnuaRapier3dSensorShape,
+        &mut TnuaProximitySensor,
+    )>,
+) {
+    for (subservient, transform, TnuaRapier3dSensorShape(shape), mut sensor) in &mut sensors {
+        if sensor.output.is_some() || sensor.cast_range <= 0.0 {
+            continue;
+        }
+        let direction = sensor.cast_direction;
+        let origin = transform.transform_point(sensor.cast_origin) + *direction * sensor.cast_range;
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(subservient.owner_entity);
+        let Some((entity, hit)) = rapier_context.cast_shape(
+            origin,
+            transform.compute_transform().rotation,
+            *direction,
+            shape,
+            ShapeCastOptions::with_max_time_of_impact(sensor.cast_range),
+            filter,
+        ) else {
+            continue;
+        };
+        sensor.output = Some(TnuaProximitySensorOutput {
+            entity,
+            proximity: sensor.cast_range + hit.time_of_impact,
+            normal: hit
+                .details
+                .and_then(|details| Dir3::new(details.normal1).ok())
+                .unwrap_or(-direction),
+            entity_linvel: Vec3::ZERO,
+            entity_angvel: Vec3::ZERO,
+        });
+    }
+}
+
+/// swap in the crouched capsule while a player crouches, so it fits under anything the crouch
+/// ducks under, and the standing one back once the crouch has ended. the crouch enforcer keeps
+/// the crouch going until it finds headroom, so it never ends with the player wedged under
+/// something
+#[allow(clippy::type_complexity)]
+pub(crate) fn swap_crouch_shape(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut players: Query<(
+        &TnuaController,
+        &PlayerSettings,
+        &mut CrouchedShape,
+        &mut Collider,
+        Option<&mut Handle<Mesh>>,
+    )>,
+) {
+    for (controller, settings, mut crouched, mut collider, mesh) in &mut players {
+        let crouching = controller.concrete_action::<TnuaBuiltinCrouch>().is_some();
+        if crouching == crouched.0 {
+            continue;
+        }
+        crouched.0 = crouching;
+        *collider = match crouching {
+            true => settings.crouch_collider(),
+            false => settings.collider(),
+        };
+        if let Some(mut mesh) = mesh {
+            *mesh = match crouching {
+                true => meshes.add(settings.crouch_mesh()),
+                false => meshes.add(settings.mesh()),
+            };
+        }
+    }
+}
+
+/// move tnua controllers by each player's ResolvedIntent, which translate_input resolved from
+/// their InputSnapshot this tick. it's timed by the fixed clock alone, so the same snapshots from
+/// the same state always move a player the same way
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn update_player(
+    bindings: Res<KeyBindings>,
+    movement: Res<MovementConfig>,
+    jump_config: Res<JumpConfig>,
+    wall_run: Res<WallRunConfig>,
+    ground_slam: Option<Res<GroundSlamConfig>>,
+    controls: LookControls,
+    up: Res<WorldUp>,
+    friction: Res<SurfaceFriction>,
+    time: Res<Time<Fixed>>,
+    mut query: Query<
+        (
+            &mut TnuaController,
+            &mut Transform,
+            &mut Sprint,
+            &PlayerSettings,
+            &mut Abilities,
+            &PlayerMovementState,
+            &mut TnuaCrouchEnforcer,
+            &mut Velocity,
+            &CeilingContact,
+            &wall_run::NearbyWall,
+            &mut GroundSlam,
+            &ResolvedIntent,
+            &TnuaProximitySensor,
+            Option<&Children>,
+        ),
+        With<Player>,
+    >,
+    grounds: Query<&GroundMaterial>,
+    mut camera_yaw: Query<&mut CameraYaw>,
+) {
+    let LookControls {
+        mode,
+        rig,
+        recenter,
+        look,
+    } = controls;
+    let orbits = recenter.orbits(&rig, *mode);
+    let free_looks = look.free_looks(&rig, *mode);
+    let world_axes = movement.space == MovementSpace::WorldAxes;
+
+    for (
+        mut controller,
+        mut transform,
+        mut sprint,
+        settings,
+        mut abilities,
+        state,
+        mut crouch_enforcer,
+        mut velocity,
+        ceiling,
+        wall,
+        mut slam,
+        ResolvedIntent(intent),
+        sensor,
+        children,
+    ) in &mut query
+    {
+        let mut direction = intent.direction;
+
+        match *mode {
+            // move in world axes whatever the camera does, optionally facing the movement
+            ControlMode::MouseLook if world_axes => {
+                direction = up.from_local(direction);
+                let facing = movement.facing(direction, velocity.linvel, &up);
+                if facing != Vec3::ZERO {
+                    up.turn_towards(
+                        &mut transform,
+                        facing,
+                        movement.turn_speed,
+                        time.delta_seconds(),
+                    );
+                }
+            }
+            // move relative to the orbiting camera, turning the body to face the movement
+            // direction while the camera holds its world yaw
+            ControlMode::MouseLook if orbits => {
+                let camera = children
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .find(|&child| camera_yaw.contains(child));
+                let mut yaw = camera.and_then(|camera| camera_yaw.get_mut(camera).ok());
+                let world_yaw = up.yaw(&transform) + yaw.as_ref().map_or(0.0, |yaw| yaw.0);
+                direction = up.rotation(world_yaw) * direction;
+
+                let facing = movement.facing(direction, velocity.linvel, &up);
+                if facing != Vec3::ZERO {
+                    up.turn_towards(
+                        &mut transform,
+                        facing,
+                        movement.turn_speed,
+                        time.delta_seconds(),
+                    );
+                    if let Some(yaw) = &mut yaw {
+                        yaw.0 = wrap_angle(world_yaw - up.yaw(&transform));
+                    }
+                }
+            }
+            // move relative to the free-looking camera, snapping the body to it
+            ControlMode::MouseLook if free_looks => {
+                let camera = children
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .find(|&child| camera_yaw.contains(child));
+                let mut yaw = camera.and_then(|camera| camera_yaw.get_mut(camera).ok());
+                let world_yaw = up.yaw(&transform) + yaw.as_ref().map_or(0.0, |yaw| yaw.0);
+                direction = up.rotation(world_yaw) * direction;
+
+                if direction != Vec3::ZERO {
+                    let turn = wrap_angle(world_yaw - up.yaw(&transform));
+                    up.turn(&mut transform, turn);
+                    if let Some(yaw) = &mut yaw {
+                        yaw.0 = 0.0;
+                    }
+                }
+            }
+            // transform direction to correspond to camera rotation
+            ControlMode::MouseLook => {
+                direction = up.rotation(up.yaw(&transform)) * direction;
+            }
+            // move in world axes, turning to face the movement direction
+            ControlMode::TopDown => {
+                direction = up.from_local(direction);
+                let facing = movement.facing(direction, velocity.linvel, &up);
+                if facing != Vec3::ZERO {
+                    up.turn_towards(
+                        &mut transform,
+                        facing,
+                        movement.turn_speed,
+                        time.delta_seconds(),
+                    );
+                }
+            }
+        }
+
+        // jump, crouch and any other abilities feed their actions and adjust the walk
+        let mut ctx = AbilityContext {
+            controller: &mut controller,
+            crouch_enforcer: &mut crouch_enforcer,
+            intent,
+            direction,
+            velocity: velocity.linvel,
+            up: &up,
+            state: *state,
+            settings,
+            dt: time.delta_seconds(),
+            ceiling: ceiling.0,
+            wall: wall.0,
+            slam: &mut slam,
+            blocked: false,
+            cooldown: 0.0,
+            speed_multiplier: 1.0,
+            allow_sprint: true,
+            jump_taken: false,
+            ground_normal: state
+                .is_grounded()
+                .then(|| sensor.output.as_ref().map(|output| *output.normal))
+                .flatten(),
+            launch: Vec3::ZERO,
+            movement: &movement,
+            jump: &jump_config,
+            wall_run: &wall_run,
+            ground_slam: ground_slam.as_deref(),
+            bindings: &bindings,
+        };
+        abilities.update(&mut ctx);
+        let (speed_multiplier, allow_sprint) = (ctx.speed_multiplier, ctx.allow_sprint);
+        if ctx.launch != Vec3::ZERO {
+            velocity.linvel += ctx.launch;
+        }
+
+        // sprint only once moving forward (or moving at all in world axes) for a few ticks
+        let forward = match *mode {
+            ControlMode::MouseLook if world_axes => intent.direction != Vec3::ZERO,
+            ControlMode::MouseLook => intent.direction.z < 0.0,
+            ControlMode::TopDown => intent.direction != Vec3::ZERO,
+        };
+        sprint.forward_ticks = if forward {
+            sprint.forward_ticks.saturating_add(1)
+        } else {
+            0
+        };
+        let mut sprinting = sprint.toggle.update(bindings.sprint_mode, intent.sprint);
+
+        // a toggled sprint turns itself off once forward movement stops for a moment
+        sprint.idle_ticks = if sprinting && !forward {
+            sprint.idle_ticks.saturating_add(1)
+        } else {
+            0
+        };
+        if bindings.sprint_mode == PressMode::Toggle
+            && sprint.idle_ticks >= movement.sprint_cancel_ticks
+        {
+            sprint.toggle.on = false;
+            sprinting = false;
+        }
+        sprinting &= allow_sprint && sprint.forward_ticks >= movement.sprint_forward_ticks;
+
+        // ramp towards the target factor; releasing sprint or stopping eases back down
+        let target = if sprinting { 1.0 } else { 0.0 };
+        sprint.factor = if movement.sprint_ramp > 0.0 {
+            let step = time.delta_seconds() / movement.sprint_ramp;
+            sprint.factor + (target - sprint.factor).clamp(-step, step)
+        } else {
+            target
+        };
+        let speed = settings.speed
+            * 1.0.lerp(movement.sprint_multiplier, sprint.eased())
+            * speed_multiplier;
+
+        // the ground underfoot makes it grippier or slipperier; tnua only uses the ground
+        // acceleration while grounded, so in the air it's the same anywhere
+        let ground = sensor
+            .output
+            .as_ref()
+            .and_then(|output| grounds.get(output.entity).ok())
+            .copied()
+            .unwrap_or_default();
+
+        // set controller basis
+        let mut walk = TnuaBuiltinWalk {
+            desired_velocity: direction.clamp_length_max(1.0) * speed,
+            float_height: settings.float_height(),
+            acceleration: movement.acceleration * friction.of(ground),
+            air_acceleration: movement.air_acceleration,
+            // coyote time is handled by JumpState, so tnua reports real grounding
+            coyote_time: 0.0,
+            // tnua only clings while grounded; once airborne the player has to come down to the
+            // float height to land
+            cling_distance: movement.max_snap_down,
+            ..default()
+        };
+        // a springy upright does its own straightening
+        if let Upright::Spring { .. } = settings.upright {
+            walk.tilt_offset_angvel = 0.0;
+            walk.tilt_offset_angacl = 0.0;
+        }
+        controller.basis(walk);
+    }
+}
+
+/// pull players with a springy upright back to standing, with a torque growing with their
+/// tilt and damped by how fast they're tipping. yaw is left alone, so looking and turning keep
+/// working while tipped
+pub(crate) fn upright_springs(
+    time: Res<Time>,
+    up: Res<WorldUp>,
+    mut players: Query<(&Transform, &mut Velocity, &PlayerSettings), With<Player>>,
+) {
+    for (transform, mut velocity, settings) in &mut players {
+        let Upright::Spring { stiffness, damping } = settings.upright else {
+            continue;
+        };
+        // as an angular acceleration, so it feels the same whatever the capsule weighs
+        let tilt = Quat::from_rotation_arc(*transform.up(), *up.up).to_scaled_axis();
+        let tipping = up.horizontal(velocity.angvel);
+        velocity.angvel += (tilt * stiffness - tipping * damping) * time.delta_seconds();
+    }
+}
+
+/// debounce each player's grounding from tnua's, for everything downstream in the tick and
+/// update_player in the next
+pub(crate) fn update_grounded(
+    time: Res<Time>,
+    movement: Res<MovementConfig>,
+    mut players: Query<(&TnuaController, &mut Grounded), With<Player>>,
+) {
+    for (controller, mut grounded) in &mut players {
+        let Ok(airborne) = controller.is_airborne() else {
+            continue;
+        };
+        // a running jump isn't grounded yet, or its first ticks would restore coyote time
+        let launched = controller.concrete_action::<TnuaBuiltinJump>().is_some()
+            || controller
+                .concrete_action::<TnuaBuiltinKnockback>()
+                .is_some();
+        grounded.update(
+            movement.ground_debounce,
+            airborne,
+            launched,
+            time.delta_seconds(),
+        );
+    }
+}
+
+/// update each player's PlayerMovementState, sending MovementStateChanged on transitions
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_movement_state(
+    mut ew_changed: EventWriter<MovementStateChanged>,
+    mut players: Query<
+        (
+            Entity,
+            &TnuaController,
+            &Grounded,
+            &Sprint,
+            &Abilities,
+            &GroundSlam,
+            &mut PlayerMovementState,
+        ),
+        With<Player>,
+    >,
+) {
+    for (player, controller, grounded, sprint, abilities, slam, mut state) in &mut players {
+        let moving = controller
+            .concrete_basis::<TnuaBuiltinWalk>()
+            .is_some_and(|(walk, _)| walk.desired_velocity != Vec3::ZERO);
+        let to = if abilities.is_active("wall run") && !grounded.is_grounded() {
+            PlayerMovementState::WallRunning
+        } else if *slam == GroundSlam::Slamming && !grounded.is_grounded() {
+            PlayerMovementState::GroundSlamming
+        } else {
+            PlayerMovementState::from_controller(
+                grounded.is_grounded(),
+                controller.concrete_action::<TnuaBuiltinCrouch>().is_some(),
+                moving,
+                sprint.factor > 0.0,
+            )
+        };
+        if *state != to {
+            ew_changed.send(MovementStateChanged {
+                player,
+                from: *state,
+                to,
+            });
+            *state = to;
+        }
+    }
+}
+
+/// turn grounded players with whatever they stand on, so a rotating platform carries their
+/// facing as well as their position (tnua already matches the platform's velocity under them).
+/// the yaw adds onto the body's rotation, so mouse look keeps working on top, and it stops as
+/// soon as the player leaves the ground
+pub(crate) fn ride_platforms(
+    time: Res<Time>,
+    up: Res<WorldUp>,
+    mut players: Query<(&mut Transform, &PlayerMovementState, &TnuaProximitySensor), With<Player>>,
+) {
+    for (mut transform, state, sensor) in &mut players {
+        if !state.is_grounded() {
+            continue;
+        }
+        // from the supporting body's rapier Velocity; zero for fixed bodies
+        let Some(ground) = &sensor.output else {
+            continue;
+        };
+        let spin = up.vertical(ground.entity_angvel);
+        if spin != 0.0 {
+            up.turn(&mut transform, spin * time.delta_seconds());
+        }
+    }
+}
+
+/// tnua takes a spinning platform's velocity at wherever its sensor disc first touched it,
+/// which can be well off towards the rim, so a player would slowly drift backwards across it.
+/// measure it under the middle of the player instead
+pub(crate) fn measure_platform_velocity(
+    up: Res<WorldUp>,
+    platforms: Query<(&GlobalTransform, &Velocity), Without<Player>>,
+    mut players: Query<(&Transform, &mut TnuaProximitySensor), With<Player>>,
+) {
+    for (transform, mut sensor) in &mut players {
+        let Some(ground) = &mut sensor.output else {
+            continue;
+        };
+        let Ok((platform, velocity)) = platforms.get(ground.entity) else {
+            continue;
+        };
+        if velocity.angvel == Vec3::ZERO {
+            continue;
+        }
+        let under = transform.translation - *up.up * ground.proximity;
+        ground.entity_linvel =
+            velocity.linvel + velocity.angvel.cross(under - platform.translation());
+    }
+}
+
+/// whether a player's head was against a ceiling this tick
+#[derive(Component, Default)]
+pub(crate) struct CeilingContact(bool);
+
+/// cast a disc up from each rising player's head and, if it hits a ceiling, take away their
+/// upward speed (and with it the jump, see JumpAbility) rather than letting them grind into it
+pub(crate) fn bonk_ceilings(
+    time: Res<Time>,
+    movement: Res<MovementConfig>,
+    up: Res<WorldUp>,
+    rapier_context: Res<RapierContext>,
+    mut ew_bonk: EventWriter<CeilingBonk>,
+    mut players: Query<
+        (
+            Entity,
+            &Transform,
+            &PlayerSettings,
+            &mut Velocity,
+            &mut CeilingContact,
+        ),
+        With<Player>,
+    >,
+) {
+    /// how close the capsule top has to come to a ceiling to touch it
+    const CEILING_MARGIN: f32 = 0.05;
+
+    for (player, transform, settings, mut velocity, mut contact) in &mut players {
+        let was_touching = std::mem::take(&mut contact.0);
+        let rising = up.vertical(velocity.linvel);
+        if rising <= 0.0 {
+            continue;
+        }
+
+        // from the top of the capsule's straight section, narrower than it so walls don't count
+        let head = transform.translation + *up.up * settings.capsule_length * 0.5;
+        let disc = Collider::cylinder(0.01, settings.capsule_radius * 0.9);
+        let reach = settings.capsule_radius + CEILING_MARGIN + rising * time.delta_seconds();
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(player);
+        let hit = rapier_context.cast_shape(
+            head,
+            up.frame(),
+            *up.up,
+            &disc,
+            ShapeCastOptions::with_max_time_of_impact(reach),
+            filter,
+        );
+        if hit.is_none() {
+            continue;
+        }
+
+        contact.0 = true;
+        velocity.linvel -= *up.up * rising * movement.ceiling_damping;
+        if !was_touching {
+            ew_bonk.send(CeilingBonk {
+                player,
+                speed: rising,
+            });
+        }
+    }
+}
+
+/// launch players that touch a jump pad
+pub(crate) fn jump_pads(
+    up: Res<WorldUp>,
+    mut er_collision: EventReader<CollisionEvent>,
+    pads: Query<&JumpPad>,
+    mut players: Query<(&mut TnuaController, &Velocity), With<Player>>,
+) {
+    for ev in er_collision.read() {
+        let CollisionEvent::Started(a, b, _) = *ev else {
+            continue;
+        };
+
+        let (player, pad) = if players.contains(a) {
+            (a, pads.get(b))
+        } else if players.contains(b) {
+            (b, pads.get(a))
+        } else {
+            continue;
+        };
+
+        let (Ok(pad), Ok((mut controller, velocity))) = (pad, players.get_mut(player)) else {
+            continue;
+        };
+
+        // shove via tnua rather than writing the velocity directly, so the walk basis's float
+        // spring doesn't immediately cancel the launch
+        controller.action(TnuaBuiltinKnockback {
+            shove: pad.launch_velocity(velocity.linvel, &up) - velocity.linvel,
+            ..default()
+        });
+    }
+}
+
+/// send PlayerLanded when the controller goes from airborne to grounded. this runs before
+/// land_slams, while a slam that's just landed is still Slamming
+#[allow(clippy::type_complexity)]
+pub(crate) fn detect_landing(
+    up: Res<WorldUp>,
+    mut ew_landed: EventWriter<PlayerLanded>,
+    mut query: Query<
+        (
+            Entity,
+            &PlayerMovementState,
+            &Velocity,
+            &GroundSlam,
+            &mut LandingTracker,
+        ),
+        With<Player>,
+    >,
+) {
+    for (player, state, velocity, slam, mut tracker) in &mut query {
+        let airborne = !state.is_grounded();
+
+        if airborne {
+            tracker.fall_speed = tracker.fall_speed.max(-up.vertical(velocity.linvel));
+        } else if tracker.airborne {
+            ew_landed.send(PlayerLanded {
+                player,
+                impact_speed: tracker.fall_speed,
+                slam: *slam == GroundSlam::Slamming,
+            });
+            tracker.fall_speed = 0.0;
+        }
+        tracker.airborne = airborne;
+    }
+}
+
+/// update each player's AirTime and LocomotionState, sending LocomotionChanged on transitions
+pub(crate) fn update_locomotion(
+    time: Res<Time>,
+    config: Res<LocomotionConfig>,
+    up: Res<WorldUp>,
+    mut ew_changed: EventWriter<LocomotionChanged>,
+    mut query: Query<(
+        Entity,
+        &TnuaController,
+        &Velocity,
+        &Sprint,
+        &PlayerMovementState,
+        &mut AirTime,
+        &mut LocomotionState,
+    )>,
+) {
+    for (player, controller, velocity, sprint, movement, mut air_time, mut state) in &mut query {
+        let airborne = !movement.is_grounded();
+        if airborne {
+            air_time.0 += time.delta_seconds();
+        } else if air_time.0 != 0.0 {
+            air_time.0 = 0.0;
+        }
+
+        let to = LocomotionState::from_motion(
+            &config,
+            up.to_local(velocity.linvel),
+            !airborne,
+            controller.concrete_action::<TnuaBuiltinCrouch>().is_some(),
+            sprint.factor > 0.0,
+            air_time.0,
+            *state,
+        );
+        if *state != to {
+            ew_changed.send(LocomotionChanged {
+                player,
+                from: *state,
+                to,
+            });
+            *state = to;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! movement gizmos: debug drawing of each player's feet against the ground, their ground sensor,
+//! and the velocity they want and have, toggled at runtime and skipped entirely while off
+
+use bevy::{
+    color::palettes::css::{FUCHSIA, GRAY, LIME, ORANGE, RED, SKY_BLUE, WHITE, YELLOW},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    prelude::{TnuaBuiltinWalk, TnuaController},
+    TnuaProximitySensor,
+};
+use bevy_tnua_rapier3d::TnuaRapier3dSensorShape;
+
+use crate::{
+    input::{Action, KeyBindings, Keys},
+    Player, PlayerSettings,
+};
+
+/// how far the feet can be off `ground_clearance` and still be drawn as where they should be
+const FEET_TOLERANCE: f32 = 0.05;
+
+/// length of the velocity arrows per unit per second
+const VELOCITY_SCALE: f32 = 0.2;
+
+/// whether the movement gizmos are drawn
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct MovementGizmos(pub bool);
+
+/// listen for Action::ToggleMovementGizmos to show or hide the movement gizmos
+pub(crate) fn toggle_movement_gizmos(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut gizmos: ResMut<MovementGizmos>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ToggleMovementGizmos) {
+        gizmos.0 = !gizmos.0;
+    }
+}
+
+/// ring the bottom of each player's capsule, with a line down to where the ground sensor meets
+/// the ground: green while the gap is the ground clearance, yellow while it's off (floating high
+/// after a jump, or squashed by a landing), red with the capsule in the ground, and grey with no
+/// ground under it
+pub(crate) fn draw_feet(
+    mut gizmos: Gizmos,
+    players: Query<(&GlobalTransform, &PlayerSettings, &TnuaProximitySensor), With<Player>>,
+) {
+    for (transform, settings, sensor) in &players {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        let up = rotation * Vec3::Y;
+        let feet = translation - up * settings.capsule_half_height();
+        let normal = Dir3::new(up).unwrap_or(Dir3::Y);
+
+        let Some(output) = &sensor.output else {
+            gizmos.circle(feet, normal, settings.capsule_radius, GRAY);
+            continue;
+        };
+        let origin = transform.transform_point(sensor.cast_origin);
+        let ground = origin + *sensor.cast_direction * output.proximity;
+        let gap = output.proximity - settings.capsule_half_height();
+        let color = if gap < 0.0 {
+            RED
+        } else if (gap - settings.ground_clearance).abs() <= FEET_TOLERANCE {
+            LIME
+        } else {
+            YELLOW
+        };
+        gizmos.circle(feet, normal, settings.capsule_radius, color);
+        gizmos.line(feet, ground, color);
+        gizmos.circle(ground, output.normal, settings.capsule_radius * 0.5, color);
+    }
+}
+
+/// the ground sensor of each player: its disc at the cast origin and the ray it's cast along, as
+/// far as tnua asked it to look, with the ground normal where it hit
+pub(crate) fn draw_ground_sensors(
+    mut gizmos: Gizmos,
+    players: Query<
+        (
+            &GlobalTransform,
+            &TnuaProximitySensor,
+            Option<&TnuaRapier3dSensorShape>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (transform, sensor, shape) in &players {
+        let origin = transform.transform_point(sensor.cast_origin);
+        let direction = *sensor.cast_direction;
+        let radius = shape
+            .and_then(|shape| shape.0.as_cylinder().map(|cylinder| cylinder.radius()))
+            .unwrap_or(0.0);
+        if radius > 0.0 {
+            gizmos.circle(origin, sensor.cast_direction, radius, SKY_BLUE);
+        }
+        gizmos.line(origin, origin + direction * sensor.cast_range, SKY_BLUE);
+
+        let Some(output) = &sensor.output else {
+            continue;
+        };
+        let hit = origin + direction * output.proximity;
+        if radius > 0.0 {
+            gizmos.circle(hit, sensor.cast_direction, radius, SKY_BLUE);
+        }
+        gizmos.arrow(hit, hit + *output.normal, FUCHSIA);
+    }
+}
+
+/// the velocity each player's walk basis is asking tnua for, from their centre, over the velocity
+/// they're actually moving at
+pub(crate) fn draw_desired_velocity(
+    mut gizmos: Gizmos,
+    players: Query<(&GlobalTransform, &TnuaController, &Velocity), With<Player>>,
+) {
+    for (transform, controller, velocity) in &players {
+        let center = transform.translation();
+        gizmos.arrow(center, center + velocity.linvel * VELOCITY_SCALE, WHITE);
+        let Some((walk, _)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+            continue;
+        };
+        gizmos.arrow(
+            center,
+            center + walk.desired_velocity * VELOCITY_SCALE,
+            ORANGE,
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! the obstacle course: stairs, ramps, gaps, a crawl space, a narrow beam and a tower
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::PlayerSettings;
+
+/// where the obstacle course goes
+#[derive(Resource, Clone)]
+pub struct ObstacleCourseConfig {
+    /// the course's near edge, centred across it; the course extends about 32 units towards -z
+    /// and 12 either side
+    pub origin: Vec3,
+}
+
+impl Default for ObstacleCourseConfig {
+    /// just past the far edge of the default demo ground
+    fn default() -> Self {
+        Self {
+            origin: Vec3::new(0.0, 0.0, -12.0),
+        }
+    }
+}
+
+/// a course for trying out the controller: stairs of several step heights, ramps from 15° to
+/// 60°, gap jumps, a crawl space, a narrow beam and a tower to climb
+#[derive(Clone, Default)]
+pub struct ObstacleCoursePlugin;
+
+impl Plugin for ObstacleCoursePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ObstacleCourseConfig>()
+            .add_systems(Startup, setup_obstacle_course);
+    }
+}
+
+/// builds the obstacle course out of fixed blocks, each with a collider matching its mesh
+struct Course<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    meshes: &'a mut Assets<Mesh>,
+    material: Handle<StandardMaterial>,
+    origin: Vec3,
+}
+
+impl Course<'_, '_, '_> {
+    /// a fixed block of `size`, positioned relative to the course origin
+    fn block(&mut self, size: Vec3, transform: Transform) {
+        self.commands
+            .spawn(PbrBundle {
+                mesh: self.meshes.add(Cuboid::from_size(size)),
+                material: self.material.clone(),
+                transform: transform.with_translation(self.origin + transform.translation),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(size.x * 0.5, size.y * 0.5, size.z * 0.5));
+    }
+
+    /// a flight of `count` steps climbing towards -z from `start` on the floor
+    fn stairs(&mut self, start: Vec3, step_height: f32, step_depth: f32, count: u32, width: f32) {
+        for i in 0..count {
+            let height = step_height * (i + 1) as f32;
+            let center = start + Vec3::new(0.0, height * 0.5, -step_depth * (i as f32 + 0.5));
+            self.block(
+                Vec3::new(width, height, step_depth),
+                Transform::from_translation(center),
+            );
+        }
+    }
+
+    /// a slab ramp rising towards -z at `angle` degrees, its lower edge on the floor at `start`
+    fn ramp(&mut self, start: Vec3, angle: f32, length: f32, width: f32) {
+        const THICKNESS: f32 = 0.2;
+        let rotation = Quat::from_rotation_x(angle.to_radians());
+        // along the slope, and out of its top face
+        let up_slope = rotation * Vec3::NEG_Z;
+        let normal = rotation * Vec3::Y;
+        let center = start + up_slope * length * 0.5 - normal * THICKNESS * 0.5;
+        self.block(
+            Vec3::new(width, THICKNESS, length),
+            Transform::from_translation(center).with_rotation(rotation),
+        );
+    }
+
+    /// platforms `height` tall heading towards -z from `start`, separated by each of `gaps`
+    fn gaps(&mut self, start: Vec3, gaps: &[f32], platform: f32, height: f32) {
+        let size = Vec3::new(platform, height, platform);
+        let mut z = start.z - platform * 0.5;
+        for gap in std::iter::once(&0.0).chain(gaps) {
+            z -= gap;
+            self.block(
+                size,
+                Transform::from_xyz(start.x, start.y + height * 0.5, z),
+            );
+            z -= platform;
+        }
+    }
+
+    /// a tunnel `clearance` high heading towards -z from `start`, low enough to need a crouch
+    fn crawl(&mut self, start: Vec3, length: f32, clearance: f32, width: f32) {
+        const WALL: f32 = 0.3;
+        const HEIGHT: f32 = 3.0;
+        let center = start - Vec3::Z * length * 0.5;
+        self.block(
+            Vec3::new(width + WALL * 2.0, HEIGHT - clearance, length),
+            Transform::from_translation(center + Vec3::Y * (clearance + HEIGHT) * 0.5),
+        );
+        for side in [-1.0, 1.0] {
+            self.block(
+                Vec3::new(WALL, clearance, length),
+                Transform::from_translation(
+                    center + Vec3::new(side * (width + WALL) * 0.5, clearance * 0.5, 0.0),
+                ),
+            );
+        }
+    }
+
+    /// a beam `width` wide and `height` off the floor, heading towards -z from `start`
+    fn beam(&mut self, start: Vec3, length: f32, width: f32, height: f32) {
+        self.block(
+            Vec3::new(width, 0.2, length),
+            Transform::from_translation(start + Vec3::new(0.0, height - 0.1, -length * 0.5)),
+        );
+    }
+
+    /// a square pillar `height` tall centred on `center`, with blocks spiralling up around it
+    /// `step_height` apart to climb it by
+    fn tower(&mut self, center: Vec3, height: f32, size: f32, step_height: f32) {
+        const STEP: f32 = 1.2;
+        const STEPS_PER_SIDE: u32 = 3;
+        self.block(
+            Vec3::new(size, height, size),
+            Transform::from_translation(center + Vec3::Y * height * 0.5),
+        );
+
+        let ring = (size + STEP) * 0.5;
+        let steps = (height / step_height) as u32;
+        for i in 0..steps {
+            // walk the ring a side at a time, STEPS_PER_SIDE blocks each
+            let side = i / STEPS_PER_SIDE % 4;
+            let along = (i % STEPS_PER_SIDE) as f32 / STEPS_PER_SIDE as f32 * 2.0 - 1.0;
+            let offset =
+                Quat::from_rotation_y(side as f32 * PI * 0.5) * Vec3::new(along * ring, 0.0, ring);
+            let top = step_height * (i + 1) as f32;
+            self.block(
+                Vec3::new(STEP, 0.3, STEP),
+                Transform::from_translation(center + offset + Vec3::Y * (top - 0.15)),
+            );
+        }
+    }
+}
+
+/// the obstacle course: stairs, ramps, gap jumps, a crawl, a beam and a tower on a floor of its
+/// own, laid out towards -z from the course origin
+fn setup_obstacle_course(
+    mut commands: Commands,
+    config: Res<ObstacleCourseConfig>,
+    settings: Res<PlayerSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut course = Course {
+        commands: &mut commands,
+        meshes: &mut meshes,
+        material: materials.add(StandardMaterial::from_color(Color::srgb(0.5, 0.5, 0.55))),
+        origin: config.origin,
+    };
+    course.block(
+        Vec3::new(24.0, 0.2, 34.0),
+        Transform::from_xyz(0.0, -0.1, -15.0),
+    );
+
+    course.material = materials.add(StandardMaterial::from_color(Color::srgb(0.8, 0.6, 0.3)));
+    for (i, step_height) in [0.1, 0.2, 0.3, 0.45].into_iter().enumerate() {
+        let x = -10.5 + 1.8 * i as f32;
+        course.stairs(Vec3::new(x, 0.0, 0.0), step_height, 0.5, 8, 1.5);
+    }
+
+    course.material = materials.add(StandardMaterial::from_color(Color::srgb(0.3, 0.6, 0.8)));
+    for (i, angle) in [15.0, 30.0, 45.0, 60.0].into_iter().enumerate() {
+        let x = -3.0 + 2.5 * i as f32;
+        course.ramp(Vec3::new(x, 0.0, 0.0), angle, 5.0, 2.0);
+    }
+
+    course.material = materials.add(StandardMaterial::from_color(Color::srgb(0.8, 0.3, 0.3)));
+    course.gaps(
+        Vec3::new(9.5, 0.0, 0.0),
+        &[1.0, 2.0, 3.0, 4.0, 5.0],
+        2.0,
+        1.0,
+    );
+
+    course.material = materials.add(StandardMaterial::from_color(Color::srgb(0.4, 0.7, 0.4)));
+    // halfway between the tops of the crouched and standing capsules
+    let crouched = settings.crouch_half_height() * 2.0 + settings.ground_clearance;
+    let standing = settings.capsule_half_height() * 2.0 + settings.ground_clearance;
+    course.crawl(
+        Vec3::new(-8.0, 0.0, -12.0),
+        6.0,
+        (crouched + standing) * 0.5,
+        2.0,
+    );
+
+    course.material = materials.add(StandardMaterial::from_color(Color::srgb(0.7, 0.7, 0.3)));
+    course.beam(Vec3::new(-2.0, 0.0, -12.0), 10.0, 0.3, 1.0);
+
+    course.material = materials.add(StandardMaterial::from_color(Color::srgb(0.6, 0.4, 0.7)));
+    course.tower(Vec3::new(3.0, 0.0, -22.0), 12.0, 2.0, 0.4);
+}
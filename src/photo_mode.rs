@@ -0,0 +1,227 @@
+//! photo mode: physics paused, a free camera around the player, and screenshots
+
+use std::f32::consts::PI;
+
+use bevy::{
+    input::mouse::MouseMotion, prelude::*, render::view::screenshot::ScreenshotManager, time::Real,
+    utils::SystemTime, window::PrimaryWindow,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    camera::CameraRig,
+    input::{Action, KeyBindings, Keys},
+    KeyboardMouseInput, MouseLocked, Player, PlayerCamera,
+};
+
+/// photo mode configuration
+#[derive(Resource)]
+pub(crate) struct PhotoModeSettings {
+    /// how far the free camera may wander from the player's eye
+    max_radius: f32,
+    /// free camera movement speed in units per second
+    move_speed: f32,
+    /// hide UI nodes for the frame a screenshot is captured
+    hide_ui: bool,
+}
+
+impl Default for PhotoModeSettings {
+    fn default() -> Self {
+        Self {
+            max_radius: 5.0,
+            move_speed: 3.0,
+            hide_ui: true,
+        }
+    }
+}
+
+/// present while photo mode is active, holding the state to restore on exit
+#[derive(Resource)]
+pub(crate) struct PhotoMode {
+    velocity: Velocity,
+    camera_transform: Transform,
+    camera_pitch: f32,
+    fov: f32,
+    /// free camera orientation (yaw, pitch, roll) relative to the player
+    look: Vec3,
+    /// ui nodes hidden for a screenshot, shown again next frame
+    hidden_ui: Vec<Entity>,
+}
+
+/// listen for Action::TogglePhotoMode to enter/exit photo mode, pausing physics while active
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn toggle_photo_mode(
+    mut commands: Commands,
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    photo_mode: Option<Res<PhotoMode>>,
+    rig: Res<CameraRig>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut locked: ResMut<MouseLocked>,
+    mut player: Query<&mut Velocity, (With<Player>, With<KeyboardMouseInput>)>,
+    mut camera: Query<
+        (&mut Transform, &mut PlayerCamera, &mut Projection),
+        With<KeyboardMouseInput>,
+    >,
+) {
+    if !bindings.just_pressed(&keyboard, Action::TogglePhotoMode) {
+        return;
+    }
+
+    let (Ok(mut velocity), Ok((mut transform, mut player_camera, mut projection))) =
+        (player.get_single_mut(), camera.get_single_mut())
+    else {
+        return;
+    };
+    let Projection::Perspective(perspective) = &mut *projection else {
+        return;
+    };
+
+    match photo_mode {
+        None => {
+            // pausing the rapier pipeline also stops tnua from advancing
+            rapier_config.physics_pipeline_active = false;
+            commands.insert_resource(PhotoMode {
+                velocity: *velocity,
+                camera_transform: *transform,
+                camera_pitch: player_camera.0,
+                fov: perspective.fov,
+                look: Vec3::new(rig.yaw_bias, player_camera.0 + rig.pitch_bias, 0.0),
+                hidden_ui: Vec::new(),
+            });
+        }
+        Some(photo_mode) => {
+            *velocity = photo_mode.velocity;
+            *transform = photo_mode.camera_transform;
+            player_camera.0 = photo_mode.camera_pitch;
+            perspective.fov = photo_mode.fov;
+            rapier_config.physics_pipeline_active = true;
+            commands.remove_resource::<PhotoMode>();
+
+            // regrab (or release) the cursor according to MouseLocked
+            locked.set_changed();
+        }
+    }
+}
+
+/// free camera for photo mode: mouse look, movement/jump/sprint keys to move, photo roll and FOV
+/// actions
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn photo_camera(
+    time: Res<Time<Real>>,
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    locked: Res<MouseLocked>,
+    settings: Res<PhotoModeSettings>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut er_motion: EventReader<MouseMotion>,
+    mut camera: Query<
+        (&mut Transform, &mut Projection),
+        (With<PlayerCamera>, With<KeyboardMouseInput>),
+    >,
+) {
+    const SENS: f32 = 0.005;
+    const ROLL_SPEED: f32 = 1.0;
+    const FOV_SPEED: f32 = 0.5;
+
+    let Ok((mut transform, mut projection)) = camera.get_single_mut() else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    let pressed = |action| bindings.pressed(&keyboard, action);
+
+    if locked.0 {
+        for ev in er_motion.read() {
+            photo_mode.look.x -= ev.delta.x * SENS;
+            photo_mode.look.y = (photo_mode.look.y - ev.delta.y * SENS).clamp(-PI / 2.0, PI / 2.0);
+        }
+    }
+
+    if pressed(Action::PhotoRollLeft) {
+        photo_mode.look.z += ROLL_SPEED * dt;
+    }
+    if pressed(Action::PhotoRollRight) {
+        photo_mode.look.z -= ROLL_SPEED * dt;
+    }
+
+    if let Projection::Perspective(perspective) = &mut *projection {
+        if pressed(Action::PhotoFovDown) {
+            perspective.fov -= FOV_SPEED * dt;
+        }
+        if pressed(Action::PhotoFovUp) {
+            perspective.fov += FOV_SPEED * dt;
+        }
+        perspective.fov = perspective.fov.clamp(0.2, PI * 0.8);
+    }
+
+    let look = photo_mode.look;
+    transform.rotation = Quat::from_euler(EulerRot::YXZ, look.x, look.y, look.z);
+
+    let mut direction = Vec3::ZERO;
+    if pressed(Action::MoveForward) {
+        direction -= Vec3::Z;
+    }
+    if pressed(Action::MoveBack) {
+        direction += Vec3::Z;
+    }
+    if pressed(Action::MoveLeft) {
+        direction -= Vec3::X;
+    }
+    if pressed(Action::MoveRight) {
+        direction += Vec3::X;
+    }
+    if pressed(Action::Jump) {
+        direction += Vec3::Y;
+    }
+    if pressed(Action::Sprint) {
+        direction -= Vec3::Y;
+    }
+
+    // keep the camera within the allowed radius of the player's eye
+    let eye = photo_mode.camera_transform.translation;
+    let offset = transform.translation - eye
+        + transform.rotation * direction.normalize_or_zero() * settings.move_speed * dt;
+    transform.translation = eye + offset.clamp_length_max(settings.max_radius);
+}
+
+/// listen for Action::Screenshot to save a screenshot while in photo mode
+#[allow(clippy::type_complexity)]
+pub(crate) fn photo_capture(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    settings: Res<PhotoModeSettings>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut ui: Query<(Entity, &mut Visibility), (With<Node>, Without<Parent>)>,
+) {
+    // show ui hidden for last frame's capture
+    for entity in photo_mode.hidden_ui.drain(..) {
+        if let Ok((_, mut visibility)) = ui.get_mut(entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+
+    if !bindings.just_pressed(&keyboard, Action::Screenshot) {
+        return;
+    }
+
+    if settings.hide_ui {
+        for (entity, mut visibility) in &mut ui {
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+                photo_mode.hidden_ui.push(entity);
+            }
+        }
+    }
+
+    // std's clock panics on the web
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = format!("./photo-{secs}.png");
+    match screenshots.save_screenshot_to_disk(window.single(), &path) {
+        Ok(()) => info!("saving screenshot to {path}"),
+        Err(err) => warn!("could not take screenshot: {err}"),
+    }
+}
@@ -0,0 +1,184 @@
+//! moving platforms: kinematic bodies driven along a path of waypoints or spun around an axis,
+//! which tnua carries players standing on
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// what a platform does after its last waypoint
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PlatformPath {
+    /// carry on from the last waypoint back to the first
+    Loop,
+    /// turn around at either end and retrace the path
+    #[default]
+    PingPong,
+}
+
+/// somewhere on a platform's path
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Waypoint {
+    pub position: Vec3,
+    /// seconds the platform waits here before moving on
+    pub dwell: f32,
+}
+
+impl Waypoint {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            dwell: 0.0,
+        }
+    }
+
+    pub fn with_dwell(mut self, dwell: f32) -> Self {
+        self.dwell = dwell;
+        self
+    }
+}
+
+impl From<Vec3> for Waypoint {
+    fn from(position: Vec3) -> Self {
+        Self::new(position)
+    }
+}
+
+/// moves a RigidBody::KinematicPositionBased between waypoints at a constant speed. give it a
+/// Velocity too, so rapier reports the speed it's moving at and tnua carries the players on it
+#[derive(Component, Clone, Debug)]
+pub struct MovingPlatform {
+    pub waypoints: Vec<Waypoint>,
+    /// in units per second
+    pub speed: f32,
+    pub path: PlatformPath,
+    /// the waypoint it's heading for
+    target: usize,
+    /// heading back towards the first waypoint, when ping-ponging
+    reversed: bool,
+    /// seconds left to wait before leaving the last waypoint reached
+    waiting: f32,
+}
+
+impl MovingPlatform {
+    /// a platform heading first for the first waypoint, which is usually where it's spawned
+    pub fn new(
+        path: PlatformPath,
+        speed: f32,
+        waypoints: impl IntoIterator<Item = impl Into<Waypoint>>,
+    ) -> Self {
+        Self {
+            waypoints: waypoints.into_iter().map(Into::into).collect(),
+            speed,
+            path,
+            target: 0,
+            reversed: false,
+            waiting: 0.0,
+        }
+    }
+
+    /// move on to the waypoint after the target
+    fn advance(&mut self) {
+        let last = self.waypoints.len().saturating_sub(1);
+        match self.path {
+            PlatformPath::Loop => self.target = (self.target + 1) % self.waypoints.len(),
+            PlatformPath::PingPong => {
+                if self.target == last {
+                    self.reversed = true;
+                } else if self.target == 0 {
+                    self.reversed = false;
+                }
+                self.target = if self.reversed {
+                    self.target.saturating_sub(1)
+                } else {
+                    (self.target + 1).min(last)
+                };
+            }
+        }
+    }
+
+    /// where the platform is after another `seconds` on from `position`
+    fn step(&mut self, mut position: Vec3, seconds: f32) -> Vec3 {
+        if self.waypoints.is_empty() {
+            return position;
+        }
+        let mut time = seconds;
+        // at most once round the path per tick, so a path of one point doesn't spin forever
+        for _ in 0..=self.waypoints.len() {
+            if self.waiting > 0.0 {
+                let waited = self.waiting.min(time);
+                self.waiting -= waited;
+                time -= waited;
+            }
+            if time <= 0.0 {
+                break;
+            }
+            let Waypoint {
+                position: target,
+                dwell,
+            } = self.waypoints[self.target];
+            let reach = self.speed * time;
+            let distance = position.distance(target);
+            if distance > reach {
+                position = position.move_towards(target, reach);
+                break;
+            }
+            position = target;
+            if self.speed > 0.0 {
+                time -= distance / self.speed;
+            }
+            self.waiting = dwell;
+            self.advance();
+        }
+        position
+    }
+}
+
+/// moving and rotating platforms
+#[derive(Clone, Default)]
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (move_platforms, rotate_platforms).before(PhysicsSet::SyncBackend),
+        );
+    }
+}
+
+/// move the platforms along their paths. rapier takes a kinematic body's new transform as its
+/// next position, working out the velocity it moved at, so this runs before it syncs
+pub(crate) fn move_platforms(
+    time: Res<Time>,
+    mut platforms: Query<(&mut MovingPlatform, &mut Transform)>,
+) {
+    for (mut platform, mut transform) in &mut platforms {
+        transform.translation = platform.step(transform.translation, time.delta_seconds());
+    }
+}
+
+/// spins a RigidBody::KinematicPositionBased around an axis through its origin at a constant
+/// speed. like MovingPlatform it needs a Velocity for players to ride it; tnua carries them
+/// round with the surface under them, and ride_platforms turns them with it
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RotatingPlatform {
+    pub axis: Dir3,
+    /// in radians per second, anticlockwise looking back along the axis
+    pub speed: f32,
+}
+
+impl RotatingPlatform {
+    pub fn new(axis: Dir3, speed: f32) -> Self {
+        Self { axis, speed }
+    }
+}
+
+/// turn the rotating platforms, before rapier syncs them the same as move_platforms
+pub(crate) fn rotate_platforms(
+    time: Res<Time>,
+    mut platforms: Query<(&RotatingPlatform, &mut Transform)>,
+) {
+    for (platform, mut transform) in &mut platforms {
+        let turn = Quat::from_axis_angle(*platform.axis, platform.speed * time.delta_seconds());
+        transform.rotation = (turn * transform.rotation).normalize();
+    }
+}
@@ -0,0 +1,221 @@
+//! players drawn as a glTF model, animated from their LocomotionState
+
+use std::time::Duration;
+
+use bevy::{asset::LoadState, gltf::Gltf, prelude::*, utils::HashMap};
+
+use crate::{LocomotionChanged, LocomotionState, Player, PlayerSettings};
+
+/// what players are drawn as. the capsule collider does the physics either way
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PlayerVisual {
+    Capsule,
+    /// a glTF scene under assets/, falling back to the capsule if it fails to load
+    Model(String),
+}
+
+/// how players are drawn, and the animation clips a model plays for each LocomotionState
+#[derive(Resource, Clone)]
+pub struct PlayerModelConfig {
+    pub visual: PlayerVisual,
+    /// keep drawing the capsule under the model
+    pub keep_capsule: bool,
+    /// model scale and offset from where its origin is placed, the ground under the collider
+    pub scale: f32,
+    pub offset: Vec3,
+    /// glTF animation names per state; states without one play the idle clip
+    pub clips: HashMap<LocomotionState, String>,
+    /// seconds to crossfade between clips
+    pub crossfade: f32,
+}
+
+impl Default for PlayerModelConfig {
+    fn default() -> Self {
+        Self {
+            visual: PlayerVisual::Capsule,
+            keep_capsule: false,
+            scale: 1.0,
+            offset: Vec3::ZERO,
+            clips: [
+                (LocomotionState::Idle, "Idle"),
+                (LocomotionState::Walk, "Walk"),
+                (LocomotionState::Run, "Run"),
+            ]
+            .into_iter()
+            .map(|(state, clip)| (state, clip.to_string()))
+            .collect(),
+            crossfade: 0.2,
+        }
+    }
+}
+
+/// a player's glTF model, while loading and once spawned
+#[derive(Component)]
+pub(crate) struct PlayerModel {
+    gltf: Handle<Gltf>,
+    spawned: bool,
+    /// the model's AnimationPlayer, once its scene has spawned
+    animation_player: Option<Entity>,
+}
+
+/// animation graph built from a player model's clips, with the node to play for each state
+#[derive(Component)]
+pub(crate) struct PlayerAnimations {
+    graph: Handle<AnimationGraph>,
+    nodes: HashMap<LocomotionState, AnimationNodeIndex>,
+}
+
+impl PlayerAnimations {
+    fn node(&self, state: LocomotionState) -> Option<AnimationNodeIndex> {
+        self.nodes
+            .get(&state)
+            .or_else(|| self.nodes.get(&LocomotionState::Idle))
+            .copied()
+    }
+}
+
+/// start loading the configured model for newly spawned players
+pub(crate) fn load_player_models(
+    mut commands: Commands,
+    config: Res<PlayerModelConfig>,
+    asset_server: Res<AssetServer>,
+    players: Query<Entity, Added<Player>>,
+) {
+    let PlayerVisual::Model(path) = &config.visual else {
+        return;
+    };
+    for player in &players {
+        commands.entity(player).insert(PlayerModel {
+            gltf: asset_server.load(path.clone()),
+            spawned: false,
+            animation_player: None,
+        });
+    }
+}
+
+/// spawn each player's model once it loads, hiding the capsule, or keep the capsule if it fails
+#[allow(clippy::type_complexity)]
+pub(crate) fn spawn_player_models(
+    mut commands: Commands,
+    config: Res<PlayerModelConfig>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    mut graphs: ResMut<Assets<AnimationGraph>>,
+    mut players: Query<(Entity, &mut PlayerModel, &PlayerSettings)>,
+) {
+    for (player, mut model, settings) in &mut players {
+        if model.spawned {
+            continue;
+        }
+
+        if let LoadState::Failed(err) = asset_server.load_state(&model.gltf) {
+            warn!("could not load player model, keeping the capsule: {err}");
+            commands.entity(player).remove::<PlayerModel>();
+            continue;
+        }
+        let Some(gltf) = gltfs.get(&model.gltf) else {
+            continue;
+        };
+        let Some(scene) = gltf.default_scene.clone().or(gltf.scenes.first().cloned()) else {
+            warn!("player model has no scenes, keeping the capsule");
+            commands.entity(player).remove::<PlayerModel>();
+            continue;
+        };
+        model.spawned = true;
+
+        let mut graph = AnimationGraph::new();
+        let mut nodes = HashMap::new();
+        for (&state, name) in &config.clips {
+            match gltf.named_animations.get(name.as_str()) {
+                Some(clip) => {
+                    nodes.insert(state, graph.add_clip(clip.clone(), 1.0, graph.root));
+                }
+                None => warn!("player model has no {name:?} animation"),
+            }
+        }
+
+        let mut entity = commands.entity(player);
+        entity
+            .insert(PlayerAnimations {
+                graph: graphs.add(graph),
+                nodes,
+            })
+            .with_children(|children| {
+                // models have their origin at their feet, which go on the ground tnua floats
+                // the collider above
+                children.spawn(SceneBundle {
+                    scene,
+                    transform: Transform::from_translation(
+                        Vec3::NEG_Y * settings.float_height() + config.offset,
+                    )
+                    .with_scale(Vec3::splat(config.scale)),
+                    ..default()
+                });
+            });
+        if !config.keep_capsule {
+            entity.remove::<Handle<Mesh>>();
+        }
+    }
+}
+
+/// hook up a player model's AnimationPlayer when its scene spawns, starting the current state's clip
+pub(crate) fn attach_animation_players(
+    mut commands: Commands,
+    mut added: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+    parents: Query<&Parent>,
+    mut models: Query<(&mut PlayerModel, &PlayerAnimations, &LocomotionState)>,
+) {
+    for (entity, mut animation_player) in &mut added {
+        let Some(player) = parents
+            .iter_ancestors(entity)
+            .find(|&ancestor| models.contains(ancestor))
+        else {
+            continue;
+        };
+        let Ok((mut model, animations, state)) = models.get_mut(player) else {
+            continue;
+        };
+
+        let mut transitions = AnimationTransitions::new();
+        if let Some(node) = animations.node(*state) {
+            transitions
+                .play(&mut animation_player, node, Duration::ZERO)
+                .repeat();
+        }
+        commands
+            .entity(entity)
+            .insert(animations.graph.clone())
+            .insert(transitions);
+        model.animation_player = Some(entity);
+    }
+}
+
+/// crossfade player models to the clip for their new LocomotionState
+pub(crate) fn play_locomotion_animations(
+    config: Res<PlayerModelConfig>,
+    mut er_changed: EventReader<LocomotionChanged>,
+    models: Query<(&PlayerModel, &PlayerAnimations)>,
+    mut animation_players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+    for ev in er_changed.read() {
+        let Ok((model, animations)) = models.get(ev.player) else {
+            continue;
+        };
+        let (Some(entity), Some(node)) = (model.animation_player, animations.node(ev.to)) else {
+            continue;
+        };
+        let Ok((mut animation_player, mut transitions)) = animation_players.get_mut(entity) else {
+            continue;
+        };
+        // states sharing a clip keep playing it
+        if transitions.get_main_animation() != Some(node) {
+            transitions
+                .play(
+                    &mut animation_player,
+                    node,
+                    Duration::from_secs_f32(config.crossfade),
+                )
+                .repeat();
+        }
+    }
+}
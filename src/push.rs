@@ -0,0 +1,214 @@
+//! pushing: players shove the light dynamic bodies they walk into, slowing to the speed the
+//! body's mass lets them push it at, and can't budge the heavy ones. crate tuning lives here too
+
+use bevy::prelude::*;
+use bevy_rapier3d::{prelude::*, rapier::dynamics::RigidBody as RapierBody};
+use bevy_tnua::{
+    prelude::{TnuaBuiltinWalk, TnuaController},
+    TnuaProximitySensor, TnuaProximitySensorOutput,
+};
+
+use crate::{MovementConfig, Player, WorldUp};
+
+/// friction of a crate against the ground and whatever else it rubs on. low enough that a push
+/// slides it along rather than tipping it over
+pub const CRATE_FRICTION: f32 = 0.3;
+
+/// how quickly a crate slows down once it's no longer pushed, on top of friction
+pub const CRATE_LINEAR_DAMPING: f32 = 1.0;
+
+/// how quickly a crate stops spinning, so a knocked stack settles rather than rolling away
+pub const CRATE_ANGULAR_DAMPING: f32 = 2.0;
+
+/// mass of a crate that barely slows the player down
+pub const LIGHT_CRATE_MASS: f32 = 5.0;
+
+/// mass of a crate that can be pushed at around half walking speed
+pub const MEDIUM_CRATE_MASS: f32 = 25.0;
+
+/// mass of a crate too heavy to push at all
+pub const HEAVY_CRATE_MASS: f32 = 500.0;
+
+/// the physics of a dynamic crate `size` across weighing `mass` kilograms, for spawning with a
+/// mesh and transform
+pub fn crate_body(size: f32, mass: f32) -> impl Bundle {
+    (
+        RigidBody::Dynamic,
+        Collider::cuboid(size * 0.5, size * 0.5, size * 0.5),
+        ColliderMassProperties::Mass(mass),
+        Friction::coefficient(CRATE_FRICTION),
+        Damping {
+            linear_damping: CRATE_LINEAR_DAMPING,
+            angular_damping: CRATE_ANGULAR_DAMPING,
+        },
+    )
+}
+
+/// the bodies a player's collider is currently touching, kept from rapier's collision events
+#[derive(Component, Default)]
+pub(crate) struct PushContacts(Vec<Entity>);
+
+/// keep each player's PushContacts up to date with the bodies it starts and stops touching
+pub(crate) fn track_push_contacts(
+    mut er_collision: EventReader<CollisionEvent>,
+    rapier_context: Res<RapierContext>,
+    mut players: Query<&mut PushContacts>,
+) {
+    for ev in er_collision.read() {
+        let (a, b, started) = match *ev {
+            CollisionEvent::Started(a, b, _) => (a, b, true),
+            CollisionEvent::Stopped(a, b, _) => (a, b, false),
+        };
+
+        for (player, other) in [(a, b), (b, a)] {
+            let Ok(mut contacts) = players.get_mut(player) else {
+                continue;
+            };
+            // the event has colliders; push the body they belong to
+            let body = rapier_context.collider_parent(other).unwrap_or(other);
+            if started {
+                if !contacts.0.contains(&body) {
+                    contacts.0.push(body);
+                }
+            } else {
+                contacts.0.retain(|&e| e != body);
+            }
+        }
+    }
+}
+
+/// give the dynamic body behind `entity` the impulse `impulse` works out from its state, if
+/// any. rapier takes a Velocity component over the body's own, so that's kept in step
+pub(crate) fn impulse_body(
+    rapier_context: &mut RapierContext,
+    velocities: &mut Query<&mut Velocity>,
+    entity: Entity,
+    impulse: impl FnOnce(&RapierBody) -> Option<Vec3>,
+) {
+    let Some(&handle) = rapier_context.entity2body().get(&entity) else {
+        return;
+    };
+    let Some(body) = rapier_context.bodies.get_mut(handle) else {
+        return;
+    };
+    if !body.is_dynamic() {
+        return;
+    }
+    let Some(impulse) = impulse(body) else {
+        return;
+    };
+    body.apply_impulse(impulse.into(), true);
+    if let Ok(mut velocity) = velocities.get_mut(entity) {
+        velocity.linvel = (*body.linvel()).into();
+    }
+}
+
+/// how fast a body can be pushed, as a fraction of the speed the player walks into it at: near
+/// 1 for the lightest, falling to 0 at push_max_mass
+fn push_factor(mass: f32, max_mass: f32) -> f32 {
+    (1.0 - mass / max_mass.max(f32::EPSILON)).max(0.0)
+}
+
+/// shove the dynamic bodies players are walking into up to the speed their mass allows, and
+/// hold the players' walk towards each body down to that speed. this runs after update_player
+/// so it can rein in the walk it fed: a player walking into a body faster than it gives way
+/// would have the capsule's rounded bottom ride up its edge until the float sensor finds its
+/// top, stepping them up onto it
+pub(crate) fn push_bodies(
+    time: Res<Time>,
+    movement: Res<MovementConfig>,
+    up: Res<WorldUp>,
+    mut rapier_context: ResMut<RapierContext>,
+    mut players: Query<(&Transform, &mut TnuaController, &PushContacts)>,
+    mut velocities: Query<&mut Velocity>,
+) {
+    if movement.push_strength <= 0.0 {
+        return;
+    }
+
+    for (transform, mut controller, contacts) in &mut players {
+        let Some((walk, _)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+            continue;
+        };
+        let mut walk = walk.clone();
+        let fed = walk.desired_velocity;
+
+        for &entity in &contacts.0 {
+            impulse_body(&mut rapier_context, &mut velocities, entity, |body| {
+                // only push away from the player, never pull what they're walking away from
+                let offset = Vec3::from(*body.translation()) - transform.translation;
+                let away = up.horizontal(offset).normalize_or_zero();
+                let speed = walk.desired_velocity.dot(away);
+                if speed <= 0.0 {
+                    return None;
+                }
+
+                let push_speed = speed * push_factor(body.mass(), movement.push_max_mass);
+                walk.desired_velocity -= away * (speed - push_speed);
+                if push_speed <= 0.0 {
+                    return None;
+                }
+
+                // bring it up to the push speed, as hard as push_strength allows
+                let along = Vec3::from(*body.linvel()).dot(away);
+                let needed = (push_speed - along).max(0.0) * body.mass();
+                Some(away * needed.min(movement.push_strength * speed * time.delta_seconds()))
+            });
+        }
+
+        if walk.desired_velocity != fed {
+            controller.basis(walk);
+        }
+    }
+}
+
+/// tnua's sensor disc reaches out nearly to the side of the capsule, past its rounded bottom, so
+/// walking into a crate taller than a step the disc finds the crate's top edge before the
+/// capsule touches it and the player floats up onto it. a dynamic body the disc finds that
+/// isn't under the middle of the player is one they're beside rather than on, so look straight
+/// down past it instead
+pub(crate) fn sense_past_bodies_beside(
+    rapier_context: Res<RapierContext>,
+    bodies: Query<(&RigidBody, Option<&Velocity>)>,
+    mut players: Query<(Entity, &GlobalTransform, &mut TnuaProximitySensor), With<Player>>,
+) {
+    for (player, transform, mut sensor) in &mut players {
+        let Some(ground) = &sensor.output else {
+            continue;
+        };
+        let body = rapier_context
+            .collider_parent(ground.entity)
+            .unwrap_or(ground.entity);
+        if !matches!(bodies.get(body), Ok((RigidBody::Dynamic, _))) {
+            continue;
+        }
+
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(player);
+        let hit = rapier_context.cast_ray_and_get_normal(
+            transform.transform_point(sensor.cast_origin),
+            *sensor.cast_direction,
+            sensor.cast_range,
+            false,
+            filter,
+        );
+        if hit.is_some_and(|(entity, _)| entity == ground.entity) {
+            continue;
+        }
+        sensor.output = hit.map(|(entity, hit)| {
+            let velocity = bodies
+                .get(rapier_context.collider_parent(entity).unwrap_or(entity))
+                .ok()
+                .and_then(|(_, velocity)| velocity.copied())
+                .unwrap_or_default();
+            TnuaProximitySensorOutput {
+                entity,
+                proximity: hit.time_of_impact,
+                normal: Dir3::new(hit.normal).unwrap_or(-sensor.cast_direction),
+                entity_linvel: velocity.linvel,
+                entity_angvel: velocity.angvel,
+            }
+        });
+    }
+}
@@ -0,0 +1,230 @@
+//! obstacle scattering: boxes, ramps and pillars strewn over the demo scene's flat ground from a
+//! seeded rng, for stress testing movement on layouts nobody built by hand
+
+use bevy::{prelude::*, utils::SystemTime};
+use bevy_rapier3d::prelude::*;
+
+use crate::SceneObject;
+
+/// attempts at placing an obstacle clear of the spawn point before it's left out
+const PLACEMENT_TRIES: u32 = 32;
+
+/// how many obstacles to scatter and how big. where they go is down to ScatterRng's seed
+#[derive(Resource, Clone, Debug)]
+pub struct ScatterConfig {
+    /// obstacles to scatter over the flat ground in place of the demo props, or 0 for none
+    pub count: u32,
+    /// range of obstacle sizes, in units
+    pub min_size: f32,
+    pub max_size: f32,
+    /// distance from the spawn point no obstacle comes within, so players never spawn inside one
+    pub spawn_clearance: f32,
+}
+
+impl Default for ScatterConfig {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            min_size: 0.5,
+            max_size: 3.0,
+            spawn_clearance: 3.0,
+        }
+    }
+}
+
+/// the rng obstacles are scattered from: splitmix64, the same on every platform, so the same seed
+/// always scatters the same layout
+#[derive(Resource, Clone, Debug)]
+pub struct ScatterRng {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for ScatterRng {
+    fn default() -> Self {
+        // a new layout every run
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        Self::new(seed)
+    }
+}
+
+impl ScatterRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// start the sequence over from the seed
+    pub fn reset(&mut self) {
+        self.state = self.seed;
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut h = self.state;
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^ (h >> 31)
+    }
+
+    /// a value in 0..1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// a value in min..max
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObstacleKind {
+    Box,
+    /// a slab tilted up off the ground
+    Ramp,
+    /// a cylinder standing upright; its size is its diameter, height, diameter
+    Pillar,
+}
+
+/// one scattered obstacle, a fixed body
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub size: Vec3,
+    pub transform: Transform,
+}
+
+impl Obstacle {
+    pub fn mesh(&self) -> Mesh {
+        match self.kind {
+            ObstacleKind::Box | ObstacleKind::Ramp => Cuboid::from_size(self.size).into(),
+            ObstacleKind::Pillar => Cylinder::new(self.size.x * 0.5, self.size.y).into(),
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        let half = self.size * 0.5;
+        match self.kind {
+            ObstacleKind::Box | ObstacleKind::Ramp => Collider::cuboid(half.x, half.y, half.z),
+            ObstacleKind::Pillar => Collider::cylinder(half.y, half.x),
+        }
+    }
+
+    /// the radius of the circle it stands within, seen from above
+    pub fn footprint(&self) -> f32 {
+        Vec2::new(self.size.x, self.size.z).length() * 0.5
+    }
+}
+
+/// scatter `config.count` obstacles over flat ground `ground` wide and deep either side of the
+/// origin, none within `spawn_clearance` of `spawn`. the rng is reset first, so a seed always gives
+/// the same layout. an obstacle that can't be placed clear of the spawn point is left out
+pub fn scatter_layout(
+    config: &ScatterConfig,
+    rng: &mut ScatterRng,
+    ground: Vec2,
+    spawn: Vec2,
+) -> Vec<Obstacle> {
+    rng.reset();
+    let (min_size, max_size) = (config.min_size, config.max_size.max(config.min_size));
+    let mut obstacles = Vec::new();
+    for _ in 0..config.count {
+        let yaw = Quat::from_rotation_y(rng.range(0.0, std::f32::consts::TAU));
+        let mut obstacle = match rng.next_u64() % 3 {
+            0 => {
+                let size = Vec3::new(
+                    rng.range(min_size, max_size),
+                    rng.range(min_size, max_size),
+                    rng.range(min_size, max_size),
+                );
+                Obstacle {
+                    kind: ObstacleKind::Box,
+                    size,
+                    transform: Transform::from_xyz(0.0, size.y * 0.5, 0.0).with_rotation(yaw),
+                }
+            }
+            1 => {
+                let width = rng.range(min_size, max_size);
+                let length = rng.range(min_size, max_size) * 2.0;
+                let tilt = rng.range(10.0, 25.0).to_radians();
+                // lifted so its low end rests on the ground
+                Obstacle {
+                    kind: ObstacleKind::Ramp,
+                    size: Vec3::new(width, 0.2, length),
+                    transform: Transform::from_xyz(0.0, length * 0.5 * tilt.sin(), 0.0)
+                        .with_rotation(yaw * Quat::from_rotation_x(tilt)),
+                }
+            }
+            _ => {
+                let diameter = rng.range(min_size, max_size) * 0.5;
+                let height = rng.range(min_size, max_size) * 2.0;
+                Obstacle {
+                    kind: ObstacleKind::Pillar,
+                    size: Vec3::new(diameter, height, diameter),
+                    transform: Transform::from_xyz(0.0, height * 0.5, 0.0),
+                }
+            }
+        };
+
+        // somewhere on the ground, all of it, and clear of the spawn point
+        let footprint = obstacle.footprint();
+        let room = ground - footprint;
+        if room.min_element() <= 0.0 {
+            continue;
+        }
+        let position = (0..PLACEMENT_TRIES)
+            .map(|_| Vec2::new(rng.range(-room.x, room.x), rng.range(-room.y, room.y)))
+            .find(|position| position.distance(spawn) >= config.spawn_clearance + footprint);
+        if let Some(position) = position {
+            obstacle.transform.translation.x = position.x;
+            obstacle.transform.translation.z = position.y;
+            obstacles.push(obstacle);
+        }
+    }
+    obstacles
+}
+
+/// spawn the scattered obstacles as part of the scene, logging the seed they came from
+pub(crate) fn spawn_obstacles(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    config: &ScatterConfig,
+    rng: &mut ScatterRng,
+    ground: Vec2,
+) {
+    let obstacles = scatter_layout(config, rng, ground, Vec2::ZERO);
+    info!(
+        "scattered {} obstacles from seed {} (pass --scatter-seed {} for this layout again)",
+        obstacles.len(),
+        rng.seed(),
+        rng.seed()
+    );
+
+    let box_material = materials.add(StandardMaterial::from_color(Color::srgb(0.55, 0.5, 0.45)));
+    let ramp_material = materials.add(StandardMaterial::from_color(Color::srgb(0.4, 0.5, 0.6)));
+    let pillar_material = materials.add(StandardMaterial::from_color(Color::srgb(0.6, 0.6, 0.6)));
+    for obstacle in obstacles {
+        let material = match obstacle.kind {
+            ObstacleKind::Box => box_material.clone(),
+            ObstacleKind::Ramp => ramp_material.clone(),
+            ObstacleKind::Pillar => pillar_material.clone(),
+        };
+        commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(obstacle.mesh()),
+                material,
+                transform: obstacle.transform,
+                ..default()
+            })
+            .insert(SceneObject)
+            .insert(RigidBody::Fixed)
+            .insert(obstacle.collider());
+    }
+}
@@ -0,0 +1,301 @@
+//! the demo scene described in a RON file instead of code, so test levels can be changed
+//! without recompiling
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    camera::world_and_viewmodel_layers,
+    camera_path::CameraPathTrigger,
+    health::{DamageVolume, Hazard},
+    spawn_point, Checkpoint, CurrentCheckpoint, JumpPad, ResetLevel, SpawnPoint, SpawnSelection,
+    TriggerVolume,
+};
+
+/// a scene: what to spawn, and optionally where players start
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub(crate) struct SceneFile {
+    #[serde(default)]
+    objects: Vec<SceneObject>,
+    #[serde(default)]
+    lights: Vec<SceneLight>,
+    /// where players start, as a spawn point
+    #[serde(default)]
+    spawn: Option<Vec3>,
+}
+
+/// one thing in the scene. tagged objects are sensors for the tag; the rest are solid
+#[derive(Deserialize, Debug)]
+struct SceneObject {
+    #[serde(default)]
+    position: Vec3,
+    /// euler angles in degrees, turning around y, then x, then z
+    #[serde(default)]
+    rotation: Vec3,
+    shape: SceneShape,
+    /// srgb colour to draw it in; without one only the collider is there
+    #[serde(default)]
+    color: Option<[f32; 3]>,
+    #[serde(default)]
+    body: SceneBody,
+    #[serde(default)]
+    tag: Option<SceneTag>,
+}
+
+impl SceneObject {
+    fn transform(&self) -> Transform {
+        let [y, x, z] = [self.rotation.y, self.rotation.x, self.rotation.z].map(f32::to_radians);
+        Transform::from_translation(self.position).with_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            y,
+            x,
+            z,
+        ))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+enum SceneShape {
+    /// `size` is the full width, height and depth
+    Cuboid {
+        size: Vec3,
+    },
+    Sphere {
+        radius: f32,
+    },
+    /// upright along y
+    Cylinder {
+        radius: f32,
+        height: f32,
+    },
+    /// upright along y; `length` is the straight section between the caps
+    Capsule {
+        radius: f32,
+        length: f32,
+    },
+}
+
+impl SceneShape {
+    fn mesh(&self) -> Mesh {
+        match *self {
+            Self::Cuboid { size } => Cuboid::from_size(size).into(),
+            Self::Sphere { radius } => Sphere::new(radius).into(),
+            Self::Cylinder { radius, height } => Cylinder::new(radius, height).into(),
+            Self::Capsule { radius, length } => Capsule3d::new(radius, length).into(),
+        }
+    }
+
+    fn collider(&self) -> Collider {
+        match *self {
+            Self::Cuboid { size } => Collider::cuboid(size.x * 0.5, size.y * 0.5, size.z * 0.5),
+            Self::Sphere { radius } => Collider::ball(radius),
+            Self::Cylinder { radius, height } => Collider::cylinder(height * 0.5, radius),
+            Self::Capsule { radius, length } => Collider::capsule_y(length * 0.5, radius),
+        }
+    }
+}
+
+/// how an untagged object moves
+#[derive(Deserialize, Default, Debug)]
+enum SceneBody {
+    /// never moves
+    #[default]
+    Fixed,
+    /// pushed around by physics, weighing `mass`
+    Dynamic { mass: f32 },
+    /// spins at a constant `angvel`, in radians per second, carrying what's on it
+    Spinning { angvel: Vec3 },
+}
+
+/// what a tagged volume does to players inside it
+#[derive(Deserialize, Debug)]
+enum SceneTag {
+    /// moves the player's respawn point here on entry
+    Checkpoint,
+    /// damages the player once on entry
+    Hazard { damage: f32 },
+    /// damages the player continuously while inside
+    DamageVolume { dps: f32 },
+    /// launches the player on contact
+    JumpPad {
+        launch_speed: f32,
+        direction: Vec3,
+        #[serde(default)]
+        preserve_horizontal: bool,
+    },
+    /// plays the camera path on entry
+    CameraPath,
+    /// sends TriggerEnter and TriggerExit, for game code to match on the tag
+    Trigger {
+        #[serde(default)]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct SceneLight {
+    position: Vec3,
+    /// in lumens
+    #[serde(default = "SceneLight::default_intensity")]
+    intensity: f32,
+    #[serde(default = "SceneLight::default_range")]
+    range: f32,
+    #[serde(default)]
+    shadows: bool,
+}
+
+impl SceneLight {
+    fn default_intensity() -> f32 {
+        10_000_000.0
+    }
+
+    fn default_range() -> f32 {
+        100.0
+    }
+}
+
+/// reads SceneFile from RON
+#[derive(Default)]
+pub(crate) struct SceneFileLoader;
+
+impl AssetLoader for SceneFileLoader {
+    type Asset = SceneFile;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SceneFile, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["scene.ron"]
+    }
+}
+
+/// the scene file being shown
+#[derive(Resource)]
+pub(crate) struct LoadedSceneFile(pub(crate) Handle<SceneFile>);
+
+/// marks what was spawned from the scene file, to clear it out on a reload
+#[derive(Component)]
+pub(crate) struct FromSceneFile;
+
+/// spawn the scene file's contents once it's loaded, replacing them whenever it's reloaded or
+/// the level's reset
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spawn_scene_file(
+    mut commands: Commands,
+    mut er_asset: EventReader<AssetEvent<SceneFile>>,
+    mut er_reset: EventReader<ResetLevel>,
+    file: Res<LoadedSceneFile>,
+    files: Res<Assets<SceneFile>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    spawned: Query<Entity, With<FromSceneFile>>,
+    mut checkpoint: ResMut<CurrentCheckpoint>,
+    mut selection: ResMut<SpawnSelection>,
+) {
+    let loaded = er_asset
+        .read()
+        .any(|ev| ev.is_loaded_with_dependencies(&file.0) || ev.is_modified(&file.0));
+    // spawned afresh on a ResetLevel too, like the demo scene
+    if !loaded && er_reset.read().count() == 0 {
+        return;
+    }
+    let Some(scene) = files.get(&file.0) else {
+        return;
+    };
+    for entity in &spawned {
+        commands.entity(entity).despawn_recursive();
+    }
+    // its checkpoints are gone with it
+    checkpoint.0 = None;
+
+    for object in &scene.objects {
+        let mut entity = commands.spawn((
+            TransformBundle::from_transform(object.transform()),
+            VisibilityBundle::default(),
+            object.shape.collider(),
+            FromSceneFile,
+        ));
+        if let Some([r, g, b]) = object.color {
+            entity
+                .insert(meshes.add(object.shape.mesh()))
+                .insert(materials.add(StandardMaterial::from_color(Color::srgb(r, g, b))));
+        }
+
+        let Some(tag) = &object.tag else {
+            match object.body {
+                SceneBody::Fixed => entity.insert(RigidBody::Fixed),
+                SceneBody::Dynamic { mass } => entity
+                    .insert(RigidBody::Dynamic)
+                    .insert(ColliderMassProperties::Mass(mass)),
+                SceneBody::Spinning { angvel } => entity
+                    .insert(RigidBody::KinematicVelocityBased)
+                    .insert(Velocity::angular(angvel)),
+            };
+            continue;
+        };
+        entity.insert(Sensor).insert(ActiveEvents::COLLISION_EVENTS);
+        match *tag {
+            SceneTag::Checkpoint => entity
+                .insert(Checkpoint::default())
+                .insert(TriggerVolume::tagged("checkpoint")),
+            SceneTag::Hazard { damage } => entity.insert(Hazard { damage }),
+            SceneTag::DamageVolume { dps } => entity.insert(DamageVolume { dps }),
+            SceneTag::JumpPad {
+                launch_speed,
+                direction,
+                preserve_horizontal,
+            } => entity.insert(JumpPad {
+                launch_speed,
+                direction,
+                preserve_horizontal,
+            }),
+            SceneTag::CameraPath => entity.insert(CameraPathTrigger),
+            SceneTag::Trigger { ref tag } => entity.insert(TriggerVolume { tag: tag.clone() }),
+        };
+    }
+
+    for light in &scene.lights {
+        commands
+            .spawn(PointLightBundle {
+                point_light: PointLight {
+                    shadows_enabled: light.shadows,
+                    intensity: light.intensity,
+                    range: light.range,
+                    shadow_depth_bias: 0.2,
+                    ..default()
+                },
+                transform: Transform::from_translation(light.position),
+                ..default()
+            })
+            .insert(world_and_viewmodel_layers())
+            .insert(FromSceneFile);
+    }
+
+    if let Some(spawn) = scene.spawn {
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(spawn)),
+            SpawnPoint::named(spawn_point::LEVEL_SPAWN),
+            FromSceneFile,
+        ));
+        spawn_point::select_level_spawn(&mut selection);
+    }
+    info!(
+        "spawned {} objects and {} lights from the scene file",
+        scene.objects.len(),
+        scene.lights.len()
+    );
+}
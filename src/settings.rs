@@ -0,0 +1,229 @@
+//! settings.ron: the saved look, camera and control settings, loaded on startup, reloaded when
+//! the file changes and saved from the settings menu
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::utils::SystemTime;
+use bevy::{
+    asset::{
+        io::{AssetSource, AssetSourceBuilder, Reader},
+        AssetLoader, AsyncReadExt, LoadContext,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{CameraRecenter, LookConfig},
+    input::{GamepadConfig, KeyBindings, TouchConfig},
+    movement::{JumpConfig, MovementConfig},
+    spawn::{FollowsSettings, SpawnPosition},
+    PlayerSettings, SkyboxConfig, SpawnPoints, WorldUp,
+};
+
+/// where settings are saved to from the settings menu
+const SETTINGS_PATH: &str = "settings.ron";
+
+/// SETTINGS_PATH for the asset server, through a source rooted at the working directory
+const SETTINGS_ASSET: &str = "settings://settings.ron";
+
+/// settings persisted to disk, loaded as an asset so edits to the file apply while running
+#[derive(Asset, TypePath, Serialize, Deserialize)]
+pub(crate) struct SavedSettings {
+    #[serde(default)]
+    pub(crate) look: LookConfig,
+    #[serde(default)]
+    pub(crate) bindings: KeyBindings,
+    #[serde(default)]
+    pub(crate) movement: MovementConfig,
+    #[serde(default)]
+    pub(crate) recenter: CameraRecenter,
+    #[serde(default)]
+    pub(crate) gamepad: GamepadConfig,
+    #[serde(default)]
+    pub(crate) touch: TouchConfig,
+    #[serde(default)]
+    pub(crate) player: PlayerSettings,
+    #[serde(default)]
+    pub(crate) jump: JumpConfig,
+    #[serde(default)]
+    pub(crate) sky: SkyboxConfig,
+}
+
+impl SavedSettings {
+    pub(crate) fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| err.to_string())
+            .and_then(|text| std::fs::write(SETTINGS_PATH, text).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => info!("saved settings to {SETTINGS_PATH}"),
+            Err(err) => warn!("could not save {SETTINGS_PATH}: {err}"),
+        }
+    }
+}
+
+/// reads SavedSettings from RON
+#[derive(Default)]
+pub(crate) struct SettingsLoader;
+
+impl AssetLoader for SettingsLoader {
+    type Asset = SavedSettings;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SavedSettings, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// the loaded settings asset. bevy's file watcher isn't enabled, so natively SETTINGS_PATH is
+/// polled for changes instead
+#[derive(Resource)]
+pub(crate) struct SettingsFile {
+    handle: Option<Handle<SavedSettings>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    modified: Option<SystemTime>,
+    #[cfg(not(target_arch = "wasm32"))]
+    poll: Timer,
+}
+
+impl Default for SettingsFile {
+    fn default() -> Self {
+        Self {
+            handle: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            modified: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            poll: Timer::from_seconds(0.5, TimerMode::Repeating),
+        }
+    }
+}
+
+/// when SETTINGS_PATH was last modified, if it exists
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_modified() -> Option<SystemTime> {
+    std::fs::metadata(SETTINGS_PATH)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// registers the "settings" asset source that settings.ron loads from. add it before
+/// DefaultPlugins
+pub struct SettingsSourcePlugin;
+
+impl Plugin for SettingsSourcePlugin {
+    fn build(&self, app: &mut App) {
+        // settings live in the working directory, where the settings menu saves them, rather
+        // than next to assets/
+        let settings_dir = std::env::current_dir()
+            .map_or_else(|_| ".".into(), |dir| dir.to_string_lossy().into_owned());
+        app.register_asset_source(
+            "settings",
+            AssetSourceBuilder::default()
+                .with_reader(AssetSource::get_default_reader(settings_dir)),
+        );
+    }
+}
+
+/// start loading the settings asset. natively a missing file is left for watch_settings to load
+/// once it's saved
+pub(crate) fn load_settings(asset_server: Res<AssetServer>, mut file: ResMut<SettingsFile>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        file.modified = settings_modified();
+        if file.modified.is_none() {
+            return;
+        }
+    }
+    file.handle = Some(asset_server.load(SETTINGS_ASSET));
+}
+
+/// load or reload the settings asset when SETTINGS_PATH is created or modified
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn watch_settings(
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut file: ResMut<SettingsFile>,
+) {
+    if !file.poll.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // a deleted file keeps the current settings
+    let modified = settings_modified();
+    if modified.is_none() || modified == file.modified {
+        return;
+    }
+    file.modified = modified;
+    if file.handle.is_some() {
+        asset_server.reload(SETTINGS_ASSET);
+    } else {
+        file.handle = Some(asset_server.load(SETTINGS_ASSET));
+    }
+}
+
+/// apply the settings asset whenever it's loaded or reloaded, including to the players spawned
+/// to follow it; players spawned with settings of their own keep them. a file that fails to
+/// parse is logged by the asset server and leaves the current values alone
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_settings(
+    mut er_asset: EventReader<AssetEvent<SavedSettings>>,
+    file: Res<SettingsFile>,
+    assets: Res<Assets<SavedSettings>>,
+    mut look: ResMut<LookConfig>,
+    mut bindings: ResMut<KeyBindings>,
+    mut movement: ResMut<MovementConfig>,
+    mut recenter: ResMut<CameraRecenter>,
+    mut gamepad: ResMut<GamepadConfig>,
+    mut touch: ResMut<TouchConfig>,
+    mut jump: ResMut<JumpConfig>,
+    mut player: ResMut<PlayerSettings>,
+    mut sky: ResMut<SkyboxConfig>,
+    mut players: Query<(&mut PlayerSettings, &mut SpawnPosition), With<FollowsSettings>>,
+    points: SpawnPoints,
+    up: Res<WorldUp>,
+) {
+    let Some(handle) = &file.handle else {
+        return;
+    };
+    if !er_asset
+        .read()
+        .any(|ev| ev.is_added(handle) || ev.is_modified(handle))
+    {
+        return;
+    }
+    let Some(settings) = assets.get(handle) else {
+        return;
+    };
+
+    *look = settings.look.clone();
+    look.validate_pitch_range();
+    *bindings = settings.bindings.clone();
+    bindings.fill_defaults();
+    *movement = settings.movement.clone();
+    *recenter = settings.recenter.clone();
+    *gamepad = settings.gamepad.clone();
+    *touch = settings.touch.clone();
+    *jump = settings.jump.clone();
+    *player = settings.player.clone();
+    sky.set_if_neq(settings.sky.clone());
+    for (mut player, mut spawn) in &mut players {
+        *player = settings.player.clone();
+        // players at a spawn point stand on it whatever the spawn height
+        if points.is_empty() {
+            let height = up.vertical(spawn.0);
+            spawn.0 += *up.up * (settings.player.spawn_height - height);
+        }
+    }
+    info!("applied {SETTINGS_PATH}");
+}
@@ -0,0 +1,301 @@
+//! the sky behind the 3d cameras: a cubemap or panorama from assets/, or a generated gradient
+//! without one
+
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::LoadState,
+    core_pipeline::Skybox,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{
+            Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+        },
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// the sky drawn behind every 3d camera
+#[derive(Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkyboxConfig {
+    /// an image under assets/: either a cubemap with its six faces stacked top to bottom as +x,
+    /// -x, +y, -y, +z, -z, or an equirectangular panorama twice as wide as it is tall. a
+    /// missing image falls back to a generated gradient
+    pub image: String,
+    /// how bright the sky is, in cd/m². around 1000 suits bevy's default camera exposure
+    pub brightness: f32,
+    /// turn the sky left around world y, in degrees
+    pub rotation: f32,
+}
+
+impl Default for SkyboxConfig {
+    fn default() -> Self {
+        Self {
+            image: "skybox.png".into(),
+            brightness: 1000.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+/// the cubemap the cameras draw, rebaked in place from the source image (or the gradient) when
+/// it loads or the config changes
+#[derive(Resource)]
+pub(crate) struct Sky {
+    /// the image the source was loaded from
+    path: Option<String>,
+    source: Option<Handle<Image>>,
+    baked: Handle<Image>,
+}
+
+/// reserve the baked sky, which update_sky fills in
+pub(crate) fn setup_sky(mut commands: Commands, images: Res<Assets<Image>>) {
+    commands.insert_resource(Sky {
+        path: None,
+        source: None,
+        baked: images.reserve_handle(),
+    });
+}
+
+/// load the configured image, and bake the sky from it once it's loaded (or from the gradient
+/// if there's no image) whenever it or the config changes
+pub(crate) fn update_sky(
+    config: Res<SkyboxConfig>,
+    asset_server: Res<AssetServer>,
+    mut er_image: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    mut sky: ResMut<Sky>,
+) {
+    let mut rebake = config.is_changed();
+    if sky.path.as_ref() != Some(&config.image) {
+        sky.path = Some(config.image.clone());
+        sky.source = sky_asset_exists(&config.image).then(|| asset_server.load(&config.image));
+        rebake = true;
+    }
+    if let Some(source) = &sky.source {
+        if er_image
+            .read()
+            .any(|ev| ev.is_loaded_with_dependencies(source))
+        {
+            rebake = true;
+        }
+        if let LoadState::Failed(err) = asset_server.load_state(source) {
+            warn!("could not load the sky: {err}");
+            sky.source = None;
+            rebake = true;
+        }
+    }
+    if !rebake {
+        return;
+    }
+
+    let rotation = Quat::from_rotation_y(-config.rotation.to_radians());
+    let baked = match sky.source.as_ref().map(|source| images.get(source)) {
+        // still loading; keep what's there
+        Some(None) => return,
+        Some(Some(source)) => match SkySource::new(source) {
+            Some(source) => cubemap(source.face_size(), |dir| source.sample(rotation * dir)),
+            None => {
+                warn!("the sky needs a cubemap strip or a 2:1 panorama in 8-bit rgba");
+                cubemap(GRADIENT_SIZE, gradient)
+            }
+        },
+        None => cubemap(GRADIENT_SIZE, gradient),
+    };
+    images.insert(&sky.baked, baked);
+}
+
+/// give every 3d camera the sky as it's spawned, and keep their brightness up to date. cameras
+/// drawn over another one, like the viewmodel's, don't clear and so don't get one
+pub(crate) fn attach_skybox(
+    mut commands: Commands,
+    config: Res<SkyboxConfig>,
+    sky: Res<Sky>,
+    cameras: Query<(Entity, &Camera), Added<Camera3d>>,
+    mut skyboxes: Query<&mut Skybox>,
+) {
+    for (entity, camera) in &cameras {
+        if matches!(camera.clear_color, ClearColorConfig::None) {
+            continue;
+        }
+        commands.entity(entity).insert(Skybox {
+            image: sky.baked.clone(),
+            brightness: config.brightness,
+        });
+    }
+    if config.is_changed() {
+        for mut skybox in &mut skyboxes {
+            if skybox.image == sky.baked {
+                skybox.brightness = config.brightness;
+            }
+        }
+    }
+}
+
+/// whether an image is there to load, so a starter without one doesn't log a failed load
+fn sky_asset_exists(path: &str) -> bool {
+    if path.is_empty() {
+        return false;
+    }
+    // the web can only find out by trying
+    cfg!(target_arch = "wasm32") || std::path::Path::new("assets").join(path).exists()
+}
+
+/// faces of the generated gradient, in texels
+const GRADIENT_SIZE: u32 = 64;
+
+/// a clear blue sky over a pale horizon and dim ground, in srgb
+fn gradient(dir: Vec3) -> [u8; 4] {
+    let zenith = Vec3::new(0.24, 0.45, 0.78);
+    let horizon = Vec3::new(0.78, 0.84, 0.9);
+    let ground = Vec3::new(0.32, 0.3, 0.28);
+    let color = if dir.y >= 0.0 {
+        horizon.lerp(zenith, dir.y.powf(0.6))
+    } else {
+        horizon.lerp(ground, (-dir.y * 4.0).min(1.0))
+    };
+    let [r, g, b] = (color * 255.0).round().to_array().map(|c| c as u8);
+    [r, g, b, 255]
+}
+
+/// a cubemap built by coloring each texel's world direction
+fn cubemap(size: u32, color: impl Fn(Vec3) -> [u8; 4]) -> Image {
+    let mut data = Vec::with_capacity((size * size * 6 * 4) as usize);
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                data.extend(color(texture_to_world(face_direction(face, u, v))));
+            }
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    image
+}
+
+/// bevy's skybox samples its cubemap with z flipped, so texture and world directions differ in z
+fn texture_to_world(dir: Vec3) -> Vec3 {
+    Vec3::new(dir.x, dir.y, -dir.z).normalize()
+}
+
+/// the direction through a point on a cubemap face, with `u` right and `v` down in -1..1
+fn face_direction(face: u32, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -v, -u),
+        1 => Vec3::new(-1.0, -v, u),
+        2 => Vec3::new(u, 1.0, v),
+        3 => Vec3::new(u, -1.0, -v),
+        4 => Vec3::new(u, -v, 1.0),
+        _ => Vec3::new(-u, -v, -1.0),
+    }
+}
+
+/// the cubemap face a direction passes through, and where, as face_direction takes them
+fn face_point(dir: Vec3) -> (u32, f32, f32) {
+    let abs = dir.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        if dir.x > 0.0 {
+            (0, -dir.z / abs.x, -dir.y / abs.x)
+        } else {
+            (1, dir.z / abs.x, -dir.y / abs.x)
+        }
+    } else if abs.y >= abs.z {
+        if dir.y > 0.0 {
+            (2, dir.x / abs.y, dir.z / abs.y)
+        } else {
+            (3, dir.x / abs.y, -dir.z / abs.y)
+        }
+    } else if dir.z > 0.0 {
+        (4, dir.x / abs.z, -dir.y / abs.z)
+    } else {
+        (5, -dir.x / abs.z, -dir.y / abs.z)
+    }
+}
+
+/// a loaded sky image, read on the cpu so it can be rotated while baking
+enum SkySource<'a> {
+    /// faces stacked top to bottom
+    Strip { data: &'a [u8], size: u32 },
+    /// longitude across, from -z in the middle, and latitude down
+    Panorama {
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+    },
+}
+
+impl<'a> SkySource<'a> {
+    fn new(image: &'a Image) -> Option<Self> {
+        if !matches!(
+            image.texture_descriptor.format,
+            TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm
+        ) {
+            return None;
+        }
+        let (width, height) = (image.width(), image.height());
+        let data = image.data.as_slice();
+        if height == width * 6 {
+            Some(Self::Strip { data, size: width })
+        } else if width == height * 2 {
+            Some(Self::Panorama {
+                data,
+                width,
+                height,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// faces of the baked cubemap, about as sharp as the source
+    fn face_size(&self) -> u32 {
+        match *self {
+            Self::Strip { size, .. } => size,
+            Self::Panorama { height, .. } => height / 2,
+        }
+    }
+
+    /// the nearest texel in a world direction
+    fn sample(&self, dir: Vec3) -> [u8; 4] {
+        let (data, width, x, y) = match *self {
+            Self::Strip { data, size } => {
+                let (face, u, v) = face_point(Vec3::new(dir.x, dir.y, -dir.z));
+                let texel = |t: f32| (((t + 1.0) * 0.5 * size as f32) as u32).min(size - 1);
+                (data, size, texel(u), face * size + texel(v))
+            }
+            Self::Panorama {
+                data,
+                width,
+                height,
+            } => {
+                let longitude = dir.x.atan2(-dir.z);
+                let latitude = dir.y.clamp(-1.0, 1.0).asin();
+                let u = 0.5 + longitude / (2.0 * PI);
+                let v = 0.5 - latitude / PI;
+                let x = ((u * width as f32) as u32).min(width - 1);
+                let y = ((v * height as f32) as u32).min(height - 1);
+                (data, width, x, y)
+            }
+        };
+        let i = ((y * width + x) * 4) as usize;
+        [data[i], data[i + 1], data[i + 2], data[i + 3]]
+    }
+}
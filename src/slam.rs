@@ -0,0 +1,254 @@
+//! ground slam: an airborne player pressing crouch drives straight down, landing with a
+//! shockwave that shoves what's around and shakes their camera, and takes a moment to recover
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    TnuaAction, TnuaActionContext, TnuaActionInitiationDirective, TnuaActionLifecycleDirective,
+    TnuaActionLifecycleStatus, TnuaMotor, TnuaVelChange,
+};
+
+use crate::{
+    detect_landing, push::impulse_body, shake_cameras, Ability, AbilityContext, CameraShake,
+    MovementConfig, Player, PlayerMovementState, WorldUp,
+};
+
+/// ground slam tuning, shared by every player's GroundSlamAbility
+#[derive(Resource, Clone, Debug)]
+pub struct GroundSlamConfig {
+    /// speed a slam drives the player down at
+    pub fall_speed: f32,
+    /// seconds after landing a slam that the player moves slower and can't jump or slam again
+    pub recovery: f32,
+    /// top speed multiplier while recovering
+    pub recovery_speed_multiplier: f32,
+    /// send a SlamShockwave on landing
+    pub shockwave: bool,
+    /// how far the shockwave reaches
+    pub shockwave_radius: f32,
+    /// speed the shockwave throws a body at its centre; it falls off to nothing at its edge,
+    /// and bodies past push_max_mass weigh too much for it. 0 leaves them alone
+    pub shockwave_speed: f32,
+    /// how hard the shockwave shakes the slamming player's camera, from 0 to 1
+    pub camera_shake: f32,
+}
+
+impl Default for GroundSlamConfig {
+    fn default() -> Self {
+        Self {
+            fall_speed: 30.0,
+            recovery: 0.4,
+            recovery_speed_multiplier: 0.3,
+            shockwave: true,
+            shockwave_radius: 4.0,
+            shockwave_speed: 8.0,
+            camera_shake: 0.8,
+        }
+    }
+}
+
+/// where a player's ground slam is at
+#[derive(Component, Clone, Copy, PartialEq, Default, Debug)]
+pub enum GroundSlam {
+    #[default]
+    Ready,
+    /// on the way down
+    Slamming,
+    /// landed, with this many seconds left before moving normally again
+    Recovering(f32),
+}
+
+/// event sent when a ground slam lands, if GroundSlamConfig's `shockwave` is on
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct SlamShockwave {
+    pub player: Entity,
+    /// where the player landed
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+/// the tnua action fed while slamming: it holds the player's velocity at `fall_speed` straight
+/// down, stopping them going anywhere across up
+struct TnuaGroundSlam {
+    fall_speed: f32,
+}
+
+impl TnuaAction for TnuaGroundSlam {
+    const NAME: &'static str = "TnuaGroundSlam";
+    type State = ();
+    const VIOLATES_COYOTE_TIME: bool = true;
+
+    fn apply(
+        &self,
+        _state: &mut (),
+        ctx: TnuaActionContext,
+        lifecycle_status: TnuaActionLifecycleStatus,
+        motor: &mut TnuaMotor,
+    ) -> TnuaActionLifecycleDirective {
+        let down = -*ctx.up_direction * self.fall_speed;
+        motor.lin = TnuaVelChange::boost(down - ctx.tracker.velocity);
+        lifecycle_status.directive_simple()
+    }
+
+    fn initiation_decision(
+        &self,
+        _ctx: TnuaActionContext,
+        _being_fed_for: &bevy::time::Stopwatch,
+    ) -> TnuaActionInitiationDirective {
+        TnuaActionInitiationDirective::Allow
+    }
+}
+
+/// the ground slam, tuned by GroundSlamConfig. a fresh crouch press in the air starts it, and
+/// it's active until the player has recovered from landing. it does nothing without
+/// GroundSlamPlugin
+#[derive(Default)]
+pub struct GroundSlamAbility {
+    was_crouching: bool,
+}
+
+impl Ability for GroundSlamAbility {
+    fn name(&self) -> &'static str {
+        "ground slam"
+    }
+
+    fn excludes(&self) -> &'static [&'static str] {
+        &["wall run"]
+    }
+
+    fn update(&mut self, ctx: &mut AbilityContext) -> bool {
+        let Some(config) = ctx.ground_slam else {
+            return false;
+        };
+        let just_crouched = ctx.intent.crouch && !self.was_crouching;
+        self.was_crouching = ctx.intent.crouch;
+
+        match *ctx.slam {
+            GroundSlam::Ready => {
+                if !just_crouched || ctx.state.is_grounded() || !ctx.ready() {
+                    return false;
+                }
+                *ctx.slam = GroundSlam::Slamming;
+            }
+            // land_slams ends it once the player's down
+            GroundSlam::Slamming => {}
+            GroundSlam::Recovering(left) => {
+                let left = left - ctx.dt;
+                if left <= 0.0 {
+                    *ctx.slam = GroundSlam::Ready;
+                    return false;
+                }
+                *ctx.slam = GroundSlam::Recovering(left);
+                ctx.speed_multiplier *= config.recovery_speed_multiplier;
+                ctx.allow_sprint = false;
+                return true;
+            }
+        }
+
+        ctx.controller.action(TnuaGroundSlam {
+            fall_speed: config.fall_speed,
+        });
+        true
+    }
+}
+
+/// the ground slam: landing it, its shockwave and the shake it gives the slamming player's
+/// camera
+#[derive(Clone, Default)]
+pub struct GroundSlamPlugin;
+
+impl Plugin for GroundSlamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GroundSlamConfig>()
+            .add_event::<SlamShockwave>()
+            .add_systems(
+                FixedUpdate,
+                (land_slams, shockwave_bodies).chain().after(detect_landing),
+            )
+            .add_systems(Update, shake_slamming_cameras.before(shake_cameras));
+    }
+}
+
+/// land the slamming players that have reached the ground, starting their recovery and sending
+/// the shockwave. runs after update_movement_state, once this tick's grounding is known
+pub(crate) fn land_slams(
+    config: Res<GroundSlamConfig>,
+    mut ew_shockwave: EventWriter<SlamShockwave>,
+    mut players: Query<(Entity, &Transform, &PlayerMovementState, &mut GroundSlam), With<Player>>,
+) {
+    for (player, transform, state, mut slam) in &mut players {
+        if *slam != GroundSlam::Slamming || !state.is_grounded() {
+            continue;
+        }
+        *slam = GroundSlam::Recovering(config.recovery);
+        if config.shockwave {
+            ew_shockwave.send(SlamShockwave {
+                player,
+                position: transform.translation,
+                radius: config.shockwave_radius,
+            });
+        }
+    }
+}
+
+/// throw the dynamic bodies a shockwave reaches out and up from it, lighter and nearer ones
+/// faster
+pub(crate) fn shockwave_bodies(
+    config: Res<GroundSlamConfig>,
+    movement: Res<MovementConfig>,
+    up: Res<WorldUp>,
+    mut er_shockwave: EventReader<SlamShockwave>,
+    mut rapier_context: ResMut<RapierContext>,
+    mut velocities: Query<&mut Velocity>,
+    players: Query<(), With<Player>>,
+) {
+    for ev in er_shockwave.read() {
+        if config.shockwave_speed <= 0.0 {
+            continue;
+        }
+        // the colliders it reaches, each body once however many it has
+        let mut bodies = Vec::new();
+        let filter = QueryFilter::only_dynamic().exclude_sensors();
+        rapier_context.intersections_with_shape(
+            ev.position,
+            Quat::IDENTITY,
+            &Collider::ball(ev.radius),
+            filter,
+            |collider| {
+                let body = rapier_context.collider_parent(collider).unwrap_or(collider);
+                if !players.contains(body) && !bodies.contains(&body) {
+                    bodies.push(body);
+                }
+                true
+            },
+        );
+        for entity in bodies {
+            impulse_body(&mut rapier_context, &mut velocities, entity, |body| {
+                let offset = Vec3::from(*body.translation()) - ev.position;
+                let falloff = 1.0 - offset.length() / ev.radius.max(f32::EPSILON);
+                let weight = 1.0 - body.mass() / movement.push_max_mass.max(f32::EPSILON);
+                if falloff <= 0.0 || weight <= 0.0 {
+                    return None;
+                }
+                let out = up.horizontal(offset).normalize_or_zero();
+                let throw = (out + *up.up).normalize() * config.shockwave_speed * falloff * weight;
+                Some(throw * body.mass())
+            });
+        }
+    }
+}
+
+/// shake a player's camera when their ground slam lands
+fn shake_slamming_cameras(
+    config: Res<GroundSlamConfig>,
+    mut er_shockwave: EventReader<SlamShockwave>,
+    mut cameras: Query<(&Parent, &mut CameraShake)>,
+) {
+    for ev in er_shockwave.read() {
+        for (parent, mut shake) in &mut cameras {
+            if parent.get() == ev.player {
+                shake.trauma = (shake.trauma + config.camera_shake).min(1.0);
+            }
+        }
+    }
+}
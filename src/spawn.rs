@@ -0,0 +1,594 @@
+//! spawning players, and despawning and respawning them after deaths, falls and level resets
+
+use bevy::{color::palettes::css::LIME, ecs::system::EntityCommands, prelude::*};
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    control_helpers::TnuaCrouchEnforcer, prelude::TnuaControllerBundle,
+    subservient_sensors::TnuaSubservientSensor,
+};
+use bevy_tnua_rapier3d::{TnuaRapier3dIOBundle, TnuaRapier3dSensorShape};
+#[cfg(feature = "leafwing")]
+use leafwing_input_manager::prelude::InputManagerBundle;
+
+#[cfg(feature = "leafwing")]
+use crate::input::PlayerAction;
+use crate::{
+    breakable,
+    camera::{
+        spawn_viewmodel, world_camera_layers, CameraPreset, CameraRig, CameraShake, CameraYaw,
+        Lean, LookConfig, SplitScreen, SplitScreenSlot, TimeSinceLook, MAX_LOCAL_PLAYERS,
+    },
+    health::{Health, HealthConfig, TimeSinceDamage},
+    input::{Action, KeyBindings, Keys, ResolvedIntent},
+    movement::{CeilingContact, CrouchedShape, LandingTracker, Sprint},
+    push, speed_fov, wall_run, Abilities, AirTime, CurrentCheckpoint, GamepadInput, GroundSlam,
+    Grounded, InputSnapshot, KeyboardMouseInput, LocomotionState, Player, PlayerCamera,
+    PlayerEntity, PlayerMovementState, PlayerSettings, ResetLevel, SceneObject, SpawnPlayerCameras,
+    SpawnPoints, Upright, WorldUp,
+};
+
+/// gap between local players' spawns
+pub(crate) const PLAYER_SPAWN_SPACING: f32 = 3.0;
+
+/// where a player spawns and respawns, at their spawn point if there is one
+#[derive(Component)]
+pub(crate) struct SpawnPosition(pub(crate) Vec3);
+
+/// listen for Action::ResetLevel
+pub(crate) fn reset_level_key(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut ew_reset: EventWriter<ResetLevel>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ResetLevel) {
+        ew_reset.send(ResetLevel);
+    }
+}
+
+/// what drives a spawned player
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum PlayerInput {
+    /// driven by your own systems through a MovementIntent component, e.g. AI or remote players
+    None,
+    #[default]
+    KeyboardMouse,
+    /// the nth connected gamepad
+    Gamepad(usize),
+}
+
+/// the camera to attach to a spawned player
+#[derive(Clone, Debug)]
+pub struct PlayerCameraOptions {
+    /// split-screen slot, which also picks the viewmodel's render layer
+    pub slot: usize,
+    /// where the camera starts above the player's center, before the camera rig places it
+    pub eye_height: f32,
+    /// starting field of view in degrees
+    pub fov: f32,
+    /// starting pitch in degrees, positive looking up
+    pub pitch: f32,
+    /// add the viewmodel camera and placeholder held item
+    pub viewmodel: bool,
+}
+
+impl Default for PlayerCameraOptions {
+    fn default() -> Self {
+        Self {
+            slot: 0,
+            eye_height: CameraPreset::default().rig().height,
+            fov: LookConfig::default().fov,
+            pitch: 0.0,
+            viewmodel: true,
+        }
+    }
+}
+
+/// options for spawn_player
+#[derive(Clone)]
+pub struct PlayerSpawnOptions {
+    pub transform: Transform,
+    /// speed, heights and capsule size
+    pub settings: PlayerSettings,
+    pub max_health: f32,
+    pub input: PlayerInput,
+    /// None to spawn without a camera
+    pub camera: Option<PlayerCameraOptions>,
+    /// take on the saved `player` settings whenever settings.ron reloads, as setup_player's
+    /// players do, rather than keeping `settings`
+    pub follow_settings: bool,
+}
+
+impl Default for PlayerSpawnOptions {
+    fn default() -> Self {
+        let settings = PlayerSettings::default();
+        Self {
+            transform: Transform::from_xyz(0.0, settings.spawn_height, 0.0),
+            settings,
+            max_health: HealthConfig::default().max,
+            input: PlayerInput::default(),
+            camera: Some(PlayerCameraOptions::default()),
+            follow_settings: false,
+        }
+    }
+}
+
+/// entities made by spawn_player
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpawnedPlayer {
+    pub player: Entity,
+    pub camera: Option<Entity>,
+}
+
+/// the options a player was spawned with, to respawn it the same way after a DespawnPlayer
+#[derive(Component)]
+pub(crate) struct Respawn(PlayerSpawnOptions);
+
+/// a player spawned with PlayerSpawnOptions's `follow_settings`
+#[derive(Component)]
+pub(crate) struct FollowsSettings;
+
+/// event to despawn a player along with its cameras, e.g. on death or a level transition. it
+/// respawns after RespawnConfig's delay unless that's turned off
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DespawnPlayer {
+    pub player: Entity,
+}
+
+/// event to spawn a player, as spawn_player does. a keyboard and mouse player becomes the
+/// PlayerEntity
+#[derive(Event, Clone)]
+pub struct SpawnPlayer {
+    pub options: PlayerSpawnOptions,
+}
+
+/// what happens after a DespawnPlayer
+#[derive(Resource, Clone, Debug)]
+pub struct RespawnConfig {
+    /// respawn despawned players at their spawn point
+    pub automatic: bool,
+    /// seconds before respawning
+    pub delay: f32,
+    /// players falling below this height along WorldUp are despawned, as for a DespawnPlayer
+    pub kill_height: f32,
+}
+
+impl Default for RespawnConfig {
+    fn default() -> Self {
+        Self {
+            automatic: true,
+            delay: 2.0,
+            kill_height: -50.0,
+        }
+    }
+}
+
+/// despawned players waiting to respawn
+#[derive(Resource, Default)]
+pub(crate) struct PendingRespawns(Vec<PendingRespawn>);
+
+struct PendingRespawn {
+    timer: Timer,
+    options: PlayerSpawnOptions,
+    /// stands in for the player's camera until the respawn, frozen where it was
+    camera: Option<Entity>,
+}
+
+/// stands in for a despawned player's camera until it respawns
+#[derive(Component)]
+pub(crate) struct DeathCamera;
+
+/// spawn a player, with its physics, movement state and optionally a camera
+pub fn spawn_player(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    options: PlayerSpawnOptions,
+) -> SpawnedPlayer {
+    let respawn = Respawn(options.clone());
+    let PlayerSpawnOptions {
+        transform,
+        settings,
+        max_health,
+        input,
+        camera,
+        follow_settings,
+    } = options;
+    // the player and its cameras and viewmodel are all tagged with its input
+    let insert_input = |entity: &mut EntityCommands| match input {
+        PlayerInput::None => {}
+        PlayerInput::KeyboardMouse => {
+            entity.insert(KeyboardMouseInput);
+        }
+        PlayerInput::Gamepad(index) => {
+            entity.insert(GamepadInput(index));
+        }
+    };
+
+    let mut player = commands.spawn(Player);
+    insert_input(&mut player);
+    #[cfg(feature = "leafwing")]
+    if input == PlayerInput::KeyboardMouse {
+        player.insert(InputManagerBundle::with_map(
+            PlayerAction::default_input_map(),
+        ));
+    }
+    player
+        .insert(PbrBundle {
+            mesh: meshes.add(settings.mesh()),
+            material: materials.add(StandardMaterial::from_color(LIME)),
+            transform,
+            ..default()
+        })
+        .insert(RigidBody::Dynamic)
+        .insert(settings.collider())
+        .insert(breakable::player_groups())
+        .insert(Ccd {
+            enabled: settings.ccd,
+        })
+        .insert(TnuaControllerBundle::default())
+        .insert(TnuaRapier3dIOBundle::default())
+        .insert(settings.sensor())
+        .insert(LandingTracker::default())
+        // contact events for pushing whatever the player walks into
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(push::PushContacts::default())
+        .insert(CeilingContact::default())
+        .insert(wall_run::NearbyWall::default())
+        .insert(GroundSlam::default())
+        .insert(Sprint::default())
+        .insert(ResolvedIntent::default())
+        .insert(InputSnapshot::default())
+        .insert(Abilities::default())
+        .insert(Grounded::default())
+        .insert(PlayerMovementState::default())
+        .insert(AirTime::default())
+        .insert(LocomotionState::default())
+        .insert(settings.crouch_enforcer())
+        .insert(CrouchedShape::default())
+        .insert(Health::full(max_health))
+        .insert(TimeSinceDamage::default())
+        .insert(SpawnPosition(transform.translation))
+        .insert(settings.locked_axes())
+        .insert(respawn)
+        .insert(settings);
+    if follow_settings {
+        player.insert(FollowsSettings);
+    }
+
+    let mut camera_id = None;
+    if let Some(camera_options) = camera {
+        player.with_children(|children| {
+            let mut camera = children.spawn(Camera3dBundle {
+                transform: Transform::from_xyz(0.0, camera_options.eye_height, 0.0)
+                    .with_rotation(Quat::from_rotation_x(camera_options.pitch.to_radians())),
+                projection: Projection::Perspective(PerspectiveProjection {
+                    fov: camera_options.fov.to_radians(),
+                    ..default()
+                }),
+                ..default()
+            });
+            camera_id = Some(camera.id());
+            insert_input(&mut camera);
+            camera
+                .insert(PlayerCamera(camera_options.pitch.to_radians()))
+                .insert(CameraYaw::default())
+                .insert(TimeSinceLook::default())
+                .insert(Lean::default())
+                .insert(CameraShake::default())
+                .insert(speed_fov::SpeedFov::default())
+                .insert(world_camera_layers(camera_options.slot))
+                .insert(SplitScreenSlot(camera_options.slot));
+            if camera_options.viewmodel {
+                spawn_viewmodel(
+                    &mut camera,
+                    camera_options.slot,
+                    meshes,
+                    materials,
+                    insert_input,
+                );
+            }
+        });
+    }
+
+    SpawnedPlayer {
+        player: player.id(),
+        camera: camera_id,
+    }
+}
+
+/// setup player entities (including child cameras), one per local player, at the chosen spawn
+/// point
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn setup_player(
+    mut commands: Commands,
+    split: Res<SplitScreen>,
+    rig: Res<CameraRig>,
+    look: Res<LookConfig>,
+    health: Res<HealthConfig>,
+    settings: Res<PlayerSettings>,
+    up: Res<WorldUp>,
+    cameras: Res<SpawnPlayerCameras>,
+    points: SpawnPoints,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let point = points.chosen();
+    for index in 0..split.players.clamp(1, MAX_LOCAL_PLAYERS) {
+        let spawned = spawn_player(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            PlayerSpawnOptions {
+                // without a spawn point, dropped in over the origin
+                transform: match &point {
+                    Some(point) => points.transform(point, index, &settings, &up),
+                    None => Transform::from_translation(up.from_local(Vec3::new(
+                        PLAYER_SPAWN_SPACING * index as f32,
+                        settings.spawn_height,
+                        0.0,
+                    )))
+                    .with_rotation(up.rotation(settings.spawn_yaw.to_radians())),
+                },
+                settings: settings.clone(),
+                max_health: health.max,
+                // the first player uses keyboard and mouse, the rest use gamepads in connection order
+                input: match index {
+                    0 => PlayerInput::KeyboardMouse,
+                    _ => PlayerInput::Gamepad(index - 1),
+                },
+                camera: cameras.0.then(|| PlayerCameraOptions {
+                    slot: index,
+                    eye_height: rig.height,
+                    fov: look.fov,
+                    pitch: settings.spawn_pitch,
+                    viewmodel: true,
+                }),
+                follow_settings: true,
+            },
+        );
+
+        if index == 0 {
+            commands.insert_resource(PlayerEntity(spawned.player));
+        }
+    }
+}
+
+/// despawn the scene's objects on a ResetLevel, for the scene setup to spawn them afresh, and
+/// forget the checkpoint reached
+pub(crate) fn reset_scene(
+    mut commands: Commands,
+    mut er_reset: EventReader<ResetLevel>,
+    checkpoint: Option<ResMut<CurrentCheckpoint>>,
+    objects: Query<Entity, With<SceneObject>>,
+) {
+    if er_reset.read().count() == 0 {
+        return;
+    }
+    for entity in &objects {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Some(mut checkpoint) = checkpoint {
+        checkpoint.0 = None;
+    }
+}
+
+/// despawn the players on a ResetLevel, along with any waiting to respawn, for setup_player to
+/// spawn them afresh
+#[allow(clippy::type_complexity)]
+pub(crate) fn reset_players(
+    mut commands: Commands,
+    mut er_reset: EventReader<ResetLevel>,
+    mut pending: ResMut<PendingRespawns>,
+    players: Query<Entity, Or<(With<Player>, With<DeathCamera>)>>,
+) {
+    if er_reset.read().count() == 0 {
+        return;
+    }
+    for entity in &players {
+        commands.entity(entity).despawn_recursive();
+    }
+    pending.0.clear();
+}
+
+/// despawn players who've fallen past RespawnConfig's kill height
+pub(crate) fn kill_fallen_players(
+    config: Res<RespawnConfig>,
+    up: Res<WorldUp>,
+    players: Query<(Entity, &Transform), With<Player>>,
+    mut ew_despawn: EventWriter<DespawnPlayer>,
+) {
+    for (player, transform) in &players {
+        if up.vertical(transform.translation) < config.kill_height {
+            ew_despawn.send(DespawnPlayer { player });
+        }
+    }
+}
+
+/// despawn players on DespawnPlayer, leaving a frozen camera in place of theirs and queueing
+/// the respawn, at the current checkpoint if there is one
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn despawn_players(
+    mut commands: Commands,
+    config: Res<RespawnConfig>,
+    checkpoint: Option<Res<CurrentCheckpoint>>,
+    up: Res<WorldUp>,
+    mut er_despawn: EventReader<DespawnPlayer>,
+    mut pending: ResMut<PendingRespawns>,
+    players: Query<(&Respawn, &PlayerSettings, &SpawnPosition, Option<&Children>), With<Player>>,
+    cameras: Query<(&GlobalTransform, &Projection, &SplitScreenSlot), With<PlayerCamera>>,
+) {
+    // without CheckpointPlugin, always back to the spawn
+    let checkpoint = checkpoint.as_deref().copied().unwrap_or_default();
+    for ev in er_despawn.read() {
+        let Ok((respawn, settings, spawn, children)) = players.get(ev.player) else {
+            continue;
+        };
+
+        let frozen = children
+            .into_iter()
+            .flatten()
+            .find_map(|&child| cameras.get(child).ok());
+        let camera = frozen.map(|(transform, projection, slot)| {
+            commands
+                .spawn(Camera3dBundle {
+                    transform: transform.compute_transform(),
+                    projection: projection.clone(),
+                    ..default()
+                })
+                .insert(SplitScreenSlot(slot.0))
+                .insert(DeathCamera)
+                .id()
+        });
+        commands.entity(ev.player).despawn_recursive();
+
+        if !config.automatic {
+            if let Some(camera) = camera {
+                commands.entity(camera).despawn_recursive();
+            }
+            continue;
+        }
+        // respawn as the player is now, in case its settings changed since it spawned
+        let mut options = respawn.0.clone();
+        checkpoint.place(
+            &mut options.transform,
+            spawn.0,
+            settings.float_height(),
+            &up,
+        );
+        options.settings = settings.clone();
+        pending.0.push(PendingRespawn {
+            timer: Timer::from_seconds(config.delay, TimerMode::Once),
+            options,
+            camera,
+        });
+    }
+}
+
+/// respawn despawned players once their delay runs out
+pub(crate) fn respawn_players(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending: ResMut<PendingRespawns>,
+    mut ew_spawn: EventWriter<SpawnPlayer>,
+) {
+    for respawn in &mut pending.0 {
+        respawn.timer.tick(time.delta());
+    }
+    let (ready, waiting): (Vec<_>, _) = std::mem::take(&mut pending.0)
+        .into_iter()
+        .partition(|respawn| respawn.timer.finished());
+    pending.0 = waiting;
+
+    for respawn in ready {
+        if let Some(camera) = respawn.camera {
+            commands.entity(camera).despawn_recursive();
+        }
+        ew_spawn.send(SpawnPlayer {
+            options: respawn.options,
+        });
+    }
+}
+
+/// spawn players on SpawnPlayer
+pub(crate) fn spawn_players(
+    mut commands: Commands,
+    mut er_spawn: EventReader<SpawnPlayer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for ev in er_spawn.read() {
+        let spawned = spawn_player(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            ev.options.clone(),
+        );
+        if ev.options.input == PlayerInput::KeyboardMouse {
+            commands.insert_resource(PlayerEntity(spawned.player));
+        }
+    }
+}
+
+/// drop PlayerEntity once its player is despawned, so it never points at a dead entity
+pub(crate) fn forget_despawned_player(
+    mut commands: Commands,
+    player: Option<Res<PlayerEntity>>,
+    mut removed: RemovedComponents<Player>,
+) {
+    if let Some(player) = player {
+        if removed.read().any(|entity| entity == player.0) {
+            commands.remove_resource::<PlayerEntity>();
+        }
+    }
+}
+
+/// rebuild a player's mesh and colliders when its capsule size changes, and switch ccd and the
+/// upright mode
+#[allow(clippy::type_complexity)]
+pub(crate) fn apply_player_shape(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    up: Res<WorldUp>,
+    mut players: Query<
+        (
+            Ref<PlayerSettings>,
+            &CrouchedShape,
+            Option<&mut Handle<Mesh>>,
+            &mut Collider,
+            &mut TnuaRapier3dSensorShape,
+            &mut TnuaCrouchEnforcer,
+            &mut Ccd,
+            &mut LockedAxes,
+            &mut Transform,
+            Option<&Children>,
+        ),
+        Changed<PlayerSettings>,
+    >,
+    enforcer_sensors: Query<(), With<TnuaSubservientSensor>>,
+) {
+    for (
+        settings,
+        crouched,
+        mesh,
+        mut collider,
+        mut sensor,
+        mut enforcer,
+        mut ccd,
+        mut locked,
+        mut transform,
+        children,
+    ) in &mut players
+    {
+        // spawned with the right shape
+        if settings.is_added() {
+            continue;
+        }
+        // players drawn as a model have no capsule mesh
+        if let Some(mut mesh) = mesh {
+            *mesh = match crouched.0 {
+                true => meshes.add(settings.crouch_mesh()),
+                false => meshes.add(settings.mesh()),
+            };
+        }
+        *collider = match crouched.0 {
+            true => settings.crouch_collider(),
+            false => settings.collider(),
+        };
+        *sensor = settings.sensor();
+        // the enforcer spawns its sensor once, so drop the old one for it to respawn resized
+        for &child in children.into_iter().flatten() {
+            if enforcer_sensors.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+        *enforcer = settings.crouch_enforcer();
+        ccd.enabled = settings.ccd;
+        // locking a tipped player stands it straight up first
+        if *locked != settings.locked_axes() {
+            *locked = settings.locked_axes();
+            if settings.upright == Upright::Locked {
+                transform.rotation = up.rotation(up.yaw(&transform));
+            }
+        }
+    }
+}
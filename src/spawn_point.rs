@@ -0,0 +1,163 @@
+//! spawn points: places in the scene players spawn at, the default or one picked by name with
+//! `--spawn <name>`, and respawn at until a checkpoint's reached
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    spawn::{SpawnPosition, PLAYER_SPAWN_SPACING},
+    terrain::{SpawnedTerrain, TerrainConfig},
+    Player, PlayerSettings, WorldUp,
+};
+
+/// the name a level's or scene file's own spawn point gets, selected once it's loaded unless
+/// another is
+pub(crate) const LEVEL_SPAWN: &str = "spawn";
+
+/// height over a spawn point, on top of their float height, that players are dropped from so
+/// the float spring settles them onto it rather than them starting in the ground
+const SPAWN_DROP: f32 = 0.25;
+
+/// a place players spawn at, standing on its translation and facing its way (turned by
+/// PlayerSettings's `spawn_yaw`). spawn it as a top-level entity with a Transform. the default
+/// is the one with the lowest `index`
+#[derive(Component, Clone, Default, Debug)]
+pub struct SpawnPoint {
+    pub name: Option<String>,
+    pub index: u32,
+}
+
+impl SpawnPoint {
+    pub fn new(index: u32) -> Self {
+        Self { name: None, index }
+    }
+
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            index: 0,
+        }
+    }
+
+    pub fn with_index(mut self, index: u32) -> Self {
+        self.index = index;
+        self
+    }
+}
+
+/// the spawn point to use, by name, or None for the default. changing it moves the players
+/// straight there
+#[derive(Resource, Clone, Default, PartialEq, Eq, Debug)]
+pub struct SpawnSelection(pub Option<String>);
+
+/// looks up the scene's spawn points
+#[derive(SystemParam)]
+pub struct SpawnPoints<'w, 's> {
+    selection: Res<'w, SpawnSelection>,
+    points: Query<'w, 's, (&'static SpawnPoint, &'static Transform)>,
+    terrain: Option<Res<'w, SpawnedTerrain>>,
+}
+
+impl SpawnPoints<'_, '_> {
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Transform> {
+        self.points
+            .iter()
+            .find(|(point, _)| point.name.as_deref() == Some(name))
+            .map(|(_, &transform)| transform)
+    }
+
+    /// the one with the lowest index
+    pub fn default_point(&self) -> Option<Transform> {
+        self.points
+            .iter()
+            .min_by_key(|(point, _)| point.index)
+            .map(|(_, &transform)| transform)
+    }
+
+    /// the selected spawn point, or the default if none is selected. a name that isn't one of
+    /// them falls back to the default with a warning
+    pub fn chosen(&self) -> Option<Transform> {
+        let Some(name) = &self.selection.0 else {
+            return self.default_point();
+        };
+        self.get(name).or_else(|| {
+            warn!("there's no spawn point named {name:?}, so spawning at the default");
+            self.default_point()
+        })
+    }
+
+    /// where the `nth` local player spawns at `point`, see spawn_transform
+    pub(crate) fn transform(
+        &self,
+        point: &Transform,
+        nth: usize,
+        settings: &PlayerSettings,
+        up: &WorldUp,
+    ) -> Transform {
+        let terrain = self.terrain.as_deref().map(|terrain| &terrain.0);
+        spawn_transform(point, nth, settings, up, terrain)
+    }
+}
+
+/// select a level's own spawn point, just spawned, and move the players to it. the selection
+/// is left alone if another's been picked, but still moves the players back to it
+pub(crate) fn select_level_spawn(selection: &mut SpawnSelection) {
+    selection.0.get_or_insert_with(|| LEVEL_SPAWN.into());
+}
+
+/// where the `nth` local player spawns at `point`: side by side across it, dropped from just
+/// over it, or over the terrain's surface where that's higher
+pub(crate) fn spawn_transform(
+    point: &Transform,
+    nth: usize,
+    settings: &PlayerSettings,
+    up: &WorldUp,
+    terrain: Option<&TerrainConfig>,
+) -> Transform {
+    let rotation = up.rotation(up.yaw(point) + settings.spawn_yaw.to_radians());
+    let mut ground = point.translation + rotation * Vec3::X * PLAYER_SPAWN_SPACING * nth as f32;
+    if let Some(terrain) = terrain {
+        ground.y = ground.y.max(terrain.height_at(ground.x, ground.z));
+    }
+    Transform::from_translation(ground + *up.up * (settings.float_height() + SPAWN_DROP))
+        .with_rotation(rotation)
+}
+
+/// move the players to the newly selected spawn point, where they'll respawn from now on
+#[allow(clippy::type_complexity)]
+pub(crate) fn follow_spawn_selection(
+    selection: Res<SpawnSelection>,
+    points: SpawnPoints,
+    up: Res<WorldUp>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &mut SpawnPosition,
+            &PlayerSettings,
+        ),
+        (With<Player>, Without<SpawnPoint>),
+    >,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+    let Some(point) = points.chosen() else {
+        return;
+    };
+
+    let mut players: Vec<_> = players.iter_mut().collect();
+    players.sort_by_key(|(player, ..)| *player);
+    for (nth, (_, mut transform, mut velocity, mut position, settings)) in
+        players.into_iter().enumerate()
+    {
+        *transform = points.transform(&point, nth, settings, &up);
+        position.0 = transform.translation;
+        *velocity = Velocity::zero();
+    }
+}
@@ -0,0 +1,76 @@
+//! speed fov: the player cameras' field of view widens with how fast the player is moving
+//! across up, however they got going, in place of LookConfig's fixed fov
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::WorldUp;
+
+/// how the fov follows speed. below `min_speed` it's `min_fov`, above `max_speed` it's
+/// `max_fov`, and in between it's in proportion. zoom narrows it from there as it does the fixed
+/// fov
+#[derive(Resource, Clone, Debug)]
+pub struct SpeedFovConfig {
+    /// use the speed fov rather than LookConfig's
+    pub enabled: bool,
+    /// horizontal speed the fov starts widening at
+    pub min_speed: f32,
+    /// horizontal speed the fov is widest at
+    pub max_speed: f32,
+    /// vertical field of view at or below `min_speed`, in degrees
+    pub min_fov: f32,
+    /// vertical field of view at or above `max_speed`, in degrees
+    pub max_fov: f32,
+    /// how quickly the speed the fov follows catches up with the player's, per second, so it
+    /// doesn't jitter with every bump
+    pub smoothing: f32,
+}
+
+impl Default for SpeedFovConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // walking speed, so only going faster than that widens it
+            min_speed: 10.0,
+            max_speed: 25.0,
+            min_fov: 90.0,
+            max_fov: 110.0,
+            smoothing: 4.0,
+        }
+    }
+}
+
+impl SpeedFovConfig {
+    /// the fov at a horizontal speed, in degrees
+    pub fn fov_at(&self, speed: f32) -> f32 {
+        let range = (self.max_speed - self.min_speed).max(f32::EPSILON);
+        let t = ((speed - self.min_speed) / range).clamp(0.0, 1.0);
+        self.min_fov.lerp(self.max_fov, t)
+    }
+}
+
+/// a player camera's eased horizontal speed, which its speed fov follows
+#[derive(Component, Default)]
+pub(crate) struct SpeedFov(pub(crate) f32);
+
+/// ease each player camera's SpeedFov towards its player's horizontal speed
+pub(crate) fn ease_speed_fov(
+    time: Res<Time>,
+    config: Res<SpeedFovConfig>,
+    up: Res<WorldUp>,
+    mut cameras: Query<(&Parent, &mut SpeedFov)>,
+    players: Query<&Velocity>,
+) {
+    if !config.enabled {
+        return;
+    }
+
+    let t = 1.0 - (-config.smoothing * time.delta_seconds()).exp();
+    for (parent, mut eased) in &mut cameras {
+        let Ok(velocity) = players.get(parent.get()) else {
+            continue;
+        };
+        let speed = up.horizontal(velocity.linvel).length();
+        eased.0 = eased.0.lerp(speed, t);
+    }
+}
@@ -0,0 +1,171 @@
+//! the speedometer: a readout of the keyboard and mouse player's horizontal speed, for tuning
+//! movement
+
+use bevy::{
+    color::palettes::css::{LIME, ORANGE, RED},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    input::{Action, KeyBindings, Keys},
+    KeyboardMouseInput, MovementConfig, Player, PlayerSettings, WorldUp,
+};
+
+/// the bar's width, in pixels. it fills at SPEEDOMETER_FULL times the sprint speed
+const BAR_WIDTH: f32 = 160.0;
+const SPEEDOMETER_FULL: f32 = 1.5;
+
+/// what the speedometer shows. toggled with Action::ToggleSpeedometer
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpeedometerConfig {
+    pub visible: bool,
+    /// a bar under the readout, green up to walking speed, orange up to sprinting speed and red
+    /// past it
+    pub bar: bool,
+}
+
+impl Default for SpeedometerConfig {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            bar: true,
+        }
+    }
+}
+
+/// the speedometer's root node, in the top-left corner
+#[derive(Component)]
+pub(crate) struct Speedometer;
+
+#[derive(Component)]
+pub(crate) struct SpeedText;
+
+/// the speedometer's bar, hidden without SpeedometerConfig's `bar`
+#[derive(Component)]
+pub(crate) struct SpeedBarBackground;
+
+/// the fill of the speedometer's bar
+#[derive(Component)]
+pub(crate) struct SpeedBar;
+
+pub(crate) fn setup_speedometer(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.0),
+                top: Val::Px(16.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(Speedometer)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section("-- m/s", default()))
+                .insert(SpeedText);
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(8.0),
+                        ..default()
+                    },
+                    background_color: Color::BLACK.with_alpha(0.5).into(),
+                    ..default()
+                })
+                .insert(SpeedBarBackground)
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: LIME.into(),
+                            ..default()
+                        })
+                        .insert(SpeedBar);
+                });
+        });
+}
+
+/// listen for Action::ToggleSpeedometer to show or hide the speedometer
+pub(crate) fn toggle_speedometer(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<SpeedometerConfig>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ToggleSpeedometer) {
+        config.visible = !config.visible;
+    }
+}
+
+/// show the keyboard and mouse player's speed across up, or dashes while there's no player, e.g.
+/// between despawning and respawning
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(crate) fn update_speedometer(
+    config: Res<SpeedometerConfig>,
+    movement: Res<MovementConfig>,
+    up: Res<WorldUp>,
+    player: Query<(&Velocity, &PlayerSettings), (With<Player>, With<KeyboardMouseInput>)>,
+    mut root: Query<&mut Visibility, With<Speedometer>>,
+    mut text: Query<&mut Text, With<SpeedText>>,
+    mut bar: Query<(&mut Style, &mut BackgroundColor), With<SpeedBar>>,
+    mut bar_background: Query<&mut Visibility, (With<SpeedBarBackground>, Without<Speedometer>)>,
+) {
+    let shown = |shown| {
+        if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    };
+    for mut visibility in &mut root {
+        visibility.set_if_neq(shown(config.visible));
+    }
+    for mut visibility in &mut bar_background {
+        visibility.set_if_neq(shown(config.bar));
+    }
+    if !config.visible {
+        return;
+    }
+
+    let speed = player.get_single().ok().map(|(velocity, settings)| {
+        let speed = up.horizontal(velocity.linvel).length();
+        (
+            speed,
+            settings.speed,
+            settings.speed * movement.sprint_multiplier,
+        )
+    });
+    for mut text in &mut text {
+        text.sections[0].value = match speed {
+            Some((speed, ..)) => format!("{speed:.1} m/s"),
+            None => "-- m/s".to_string(),
+        };
+    }
+
+    for (mut style, mut color) in &mut bar {
+        let Some((speed, walk, sprint)) = speed else {
+            style.width = Val::Percent(0.0);
+            continue;
+        };
+        // a little over each top speed, so holding it steady doesn't flicker between colours
+        let range = if speed <= walk * 1.05 {
+            LIME
+        } else if speed <= sprint * 1.05 {
+            ORANGE
+        } else {
+            RED
+        };
+        color.0 = range.into();
+        let full = (sprint * SPEEDOMETER_FULL).max(f32::EPSILON);
+        style.width = Val::Percent((speed / full).min(1.0) * 100.0);
+    }
+}
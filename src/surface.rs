@@ -0,0 +1,35 @@
+//! ground materials: what a surface is made of, and how grippy that makes it underfoot
+
+use bevy::{prelude::*, utils::HashMap};
+
+/// what a collider's surface is made of. ground without one is GroundMaterial::Default
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum GroundMaterial {
+    #[default]
+    Default,
+    Ice,
+    Rubber,
+}
+
+/// how grippy each ground material is, scaling the walk's ground acceleration: below 1 players
+/// take longer to get going and slide to a stop, above 1 they start and stop sharper. air
+/// acceleration is left alone, so surfaces only matter while grounded
+#[derive(Resource, Clone, Debug)]
+pub struct SurfaceFriction(pub HashMap<GroundMaterial, f32>);
+
+impl Default for SurfaceFriction {
+    fn default() -> Self {
+        Self(HashMap::from_iter([
+            (GroundMaterial::Default, 1.0),
+            (GroundMaterial::Ice, 0.08),
+            (GroundMaterial::Rubber, 1.6),
+        ]))
+    }
+}
+
+impl SurfaceFriction {
+    /// the friction of a material, 1 for one missing from the map
+    pub fn of(&self, material: GroundMaterial) -> f32 {
+        self.0.get(&material).copied().unwrap_or(1.0).max(0.0)
+    }
+}
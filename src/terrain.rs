@@ -0,0 +1,193 @@
+//! heightmap terrain from seeded value noise, as a mesh and a matching rapier heightfield
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+use bevy_rapier3d::prelude::*;
+
+/// the terrain's size, shape and seed. the same config always generates the same terrain, so a
+/// seed is enough to reproduce a bug report
+#[derive(Resource, Clone)]
+pub struct TerrainConfig {
+    /// replace the demo scene's flat ground with terrain
+    pub enabled: bool,
+    pub seed: u64,
+    /// half the width and depth of the terrain
+    pub size: Vec2,
+    /// vertices along each side
+    pub resolution: usize,
+    /// height of the highest possible peak above the lowest possible valley
+    pub height: f32,
+    /// width of the largest hills, in units
+    pub feature_size: f32,
+    /// noise layers, each half the size and height of the last; more is rougher
+    pub octaves: u32,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seed: 0,
+            size: Vec2::splat(40.0),
+            resolution: 129,
+            height: 8.0,
+            feature_size: 24.0,
+            octaves: 4,
+        }
+    }
+}
+
+impl TerrainConfig {
+    /// the terrain's height at a world position
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let mut height = 0.0;
+        let mut amplitude = 0.5;
+        let mut frequency = 1.0 / self.feature_size;
+        for octave in 0..self.octaves {
+            let seed = self.seed ^ (octave as u64).wrapping_mul(0xd6e8_feb8_6659_fd93);
+            height += value_noise(seed, x * frequency, z * frequency) * amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        // centred on zero, so the terrain straddles where the flat ground would be
+        (height - 0.5) * self.height
+    }
+}
+
+/// the terrain the demo scene spawned, for spawn points to stand players on its surface
+#[derive(Resource, Clone)]
+pub(crate) struct SpawnedTerrain(pub TerrainConfig);
+
+/// a uniform grid of terrain heights
+#[derive(Clone, PartialEq, Debug)]
+pub struct Heightmap {
+    size: Vec2,
+    resolution: usize,
+    /// row by row from -z, each from -x
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    pub fn generate(config: &TerrainConfig) -> Self {
+        let resolution = config.resolution.max(2);
+        let mut heightmap = Self {
+            size: config.size,
+            resolution,
+            heights: Vec::with_capacity(resolution * resolution),
+        };
+        for row in 0..resolution {
+            for col in 0..resolution {
+                let point = heightmap.point(row, col);
+                heightmap.heights.push(config.height_at(point.x, point.y));
+            }
+        }
+        heightmap
+    }
+
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    /// the height of a grid vertex, `row` along z and `col` along x
+    pub fn height(&self, row: usize, col: usize) -> f32 {
+        self.heights[row * self.resolution + col]
+    }
+
+    /// the horizontal position of a grid vertex
+    pub fn point(&self, row: usize, col: usize) -> Vec2 {
+        let step = self.size * 2.0 / (self.resolution - 1) as f32;
+        Vec2::new(col as f32, row as f32) * step - self.size
+    }
+
+    /// a mesh of the grid, with smooth normals and uvs stretched once over the whole terrain
+    pub fn mesh(&self) -> Mesh {
+        let n = self.resolution;
+        let step = self.size * 2.0 / (n - 1) as f32;
+        let mut positions = Vec::with_capacity(n * n);
+        let mut normals = Vec::with_capacity(n * n);
+        let mut uvs = Vec::with_capacity(n * n);
+        for row in 0..n {
+            for col in 0..n {
+                let point = self.point(row, col);
+                positions.push([point.x, self.height(row, col), point.y]);
+
+                // central differences, one-sided at the edges
+                let (left, right) = (col.saturating_sub(1), (col + 1).min(n - 1));
+                let (back, front) = (row.saturating_sub(1), (row + 1).min(n - 1));
+                let dx = (self.height(row, right) - self.height(row, left))
+                    / ((right - left) as f32 * step.x);
+                let dz = (self.height(front, col) - self.height(back, col))
+                    / ((front - back) as f32 * step.y);
+                normals.push(Vec3::new(-dx, 1.0, -dz).normalize().to_array());
+
+                uvs.push([col as f32 / (n - 1) as f32, row as f32 / (n - 1) as f32]);
+            }
+        }
+
+        let mut indices = Vec::with_capacity((n - 1) * (n - 1) * 6);
+        for row in 0..n - 1 {
+            for col in 0..n - 1 {
+                let a = (row * n + col) as u32;
+                let b = a + 1;
+                let c = a + n as u32;
+                let d = c + 1;
+                // counter-clockwise seen from above
+                indices.extend([a, c, b, b, c, d]);
+            }
+        }
+
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+    }
+
+    /// a heightfield collider matching the mesh
+    pub fn collider(&self) -> Collider {
+        let n = self.resolution;
+        // rapier wants rows along z and columns along x, column by column
+        let heights = (0..n)
+            .flat_map(|col| (0..n).map(move |row| (row, col)))
+            .map(|(row, col)| self.height(row, col))
+            .collect();
+        Collider::heightfield(
+            heights,
+            n,
+            n,
+            Vec3::new(self.size.x * 2.0, 1.0, self.size.y * 2.0),
+        )
+    }
+}
+
+/// smoothly interpolated noise in 0..1, one random value per integer lattice point
+fn value_noise(seed: u64, x: f32, z: f32) -> f32 {
+    let (x0, z0) = (x.floor(), z.floor());
+    let (tx, tz) = (x - x0, z - z0);
+    let (tx, tz) = (tx * tx * (3.0 - 2.0 * tx), tz * tz * (3.0 - 2.0 * tz));
+    let (ix, iz) = (x0 as i64, z0 as i64);
+    let corner = |dx: i64, dz: i64| lattice(seed, ix + dx, iz + dz);
+    let back = corner(0, 0).lerp(corner(1, 0), tx);
+    let front = corner(0, 1).lerp(corner(1, 1), tx);
+    back.lerp(front, tz)
+}
+
+/// a random value in 0..1 for a lattice point, the same on every platform
+fn lattice(seed: u64, x: i64, z: i64) -> f32 {
+    // splitmix64 over the seed and coordinates
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (z as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^= h >> 31;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
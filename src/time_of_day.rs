@@ -0,0 +1,272 @@
+//! a day-night cycle: a directional sun (or moon) moving across the sky, with its colour,
+//! brightness and the ambient light following keyframes through the day
+
+use std::f32::consts::TAU;
+
+use bevy::{pbr::CascadeShadowConfigBuilder, prelude::*};
+
+use crate::{camera::world_and_viewmodel_layers, photo_mode::PhotoMode, Action, KeyBindings, Keys};
+
+/// the time of day, and how fast it passes
+#[derive(Resource, Clone, Debug)]
+pub struct TimeOfDay {
+    /// how far through the day it is, from 0 to 1: midnight at 0, sunrise at 0.25, noon at 0.5
+    /// and sunset at 0.75
+    pub time: f32,
+    /// real seconds a whole day takes
+    pub day_length: f32,
+    /// freeze the cycle, e.g. for screenshots. it's also frozen in photo mode
+    pub paused: bool,
+    /// how many times faster the day passes while Action::FastForwardTime is held
+    pub fast_forward: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            time: 0.4,
+            day_length: 600.0,
+            paused: false,
+            fast_forward: 60.0,
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// move the time on by `seconds` of real time, wrapping past midnight
+    pub fn advance(&mut self, seconds: f32) {
+        if !self.paused && self.day_length > 0.0 {
+            self.time = (self.time + seconds / self.day_length).rem_euclid(1.0);
+        }
+    }
+
+    /// where the sun is, pointing from the ground towards it. it rises in +x, passes south of
+    /// overhead (towards +z) at noon and sets in -x
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = (self.time - 0.25) * TAU;
+        let tilt = Quat::from_rotation_x(SUN_TILT.to_radians());
+        tilt * Vec3::new(angle.cos(), angle.sin(), 0.0)
+    }
+
+    /// the lighting at this time of day
+    pub fn daylight(&self) -> Daylight {
+        let (from, to) = KEYFRAMES
+            .windows(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .find(|(_, to)| self.time <= to.time)
+            .unwrap_or((
+                &KEYFRAMES[KEYFRAMES.len() - 2],
+                &KEYFRAMES[KEYFRAMES.len() - 1],
+            ));
+        let t = ((self.time - from.time) / (to.time - from.time)).clamp(0.0, 1.0);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+
+        // the moon stands opposite the sun, so the light never comes from below the ground
+        let sun = self.sun_direction();
+        let towards = if sun.y >= 0.0 { sun } else { -sun };
+        Daylight {
+            direction: Dir3::new(-towards).unwrap_or(Dir3::NEG_Y),
+            color: kelvin_to_color(lerp(from.kelvin, to.kelvin)),
+            illuminance: lerp(from.illuminance, to.illuminance),
+            ambient_color: kelvin_to_color(lerp(from.ambient_kelvin, to.ambient_kelvin)),
+            ambient_brightness: lerp(from.ambient, to.ambient),
+        }
+    }
+}
+
+/// the sun's light at some time of day
+#[derive(Clone, Copy, Debug)]
+pub struct Daylight {
+    /// the way the light travels, from the sun (or moon) down
+    pub direction: Dir3,
+    pub color: Color,
+    /// in lux
+    pub illuminance: f32,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+}
+
+/// degrees the sun's path leans away from straight overhead
+const SUN_TILT: f32 = 30.0;
+
+/// the sun turns in steps this size, in degrees, so its shadows don't crawl every frame
+const SUN_STEP: f32 = 0.2;
+
+/// the lighting at a time of day; the cycle interpolates between these
+struct Keyframe {
+    time: f32,
+    /// colour temperature of the sun or moon
+    kelvin: f32,
+    illuminance: f32,
+    ambient_kelvin: f32,
+    ambient: f32,
+}
+
+/// night, dawn, noon and dusk. the light dims right down as the sun crosses the horizon, where
+/// it swaps with the moon
+const KEYFRAMES: [Keyframe; 9] = [
+    Keyframe {
+        time: 0.0,
+        kelvin: 9000.0,
+        illuminance: 400.0,
+        ambient_kelvin: 12000.0,
+        ambient: 40.0,
+    },
+    Keyframe {
+        time: 0.21,
+        kelvin: 9000.0,
+        illuminance: 400.0,
+        ambient_kelvin: 12000.0,
+        ambient: 40.0,
+    },
+    Keyframe {
+        time: 0.25,
+        kelvin: 2000.0,
+        illuminance: 20.0,
+        ambient_kelvin: 5000.0,
+        ambient: 60.0,
+    },
+    Keyframe {
+        time: 0.3,
+        kelvin: 3000.0,
+        illuminance: 3000.0,
+        ambient_kelvin: 7000.0,
+        ambient: 120.0,
+    },
+    Keyframe {
+        time: 0.5,
+        kelvin: 5800.0,
+        illuminance: 10_000.0,
+        ambient_kelvin: 9000.0,
+        ambient: 250.0,
+    },
+    Keyframe {
+        time: 0.7,
+        kelvin: 2800.0,
+        illuminance: 3000.0,
+        ambient_kelvin: 7000.0,
+        ambient: 120.0,
+    },
+    Keyframe {
+        time: 0.75,
+        kelvin: 1900.0,
+        illuminance: 20.0,
+        ambient_kelvin: 4500.0,
+        ambient: 60.0,
+    },
+    Keyframe {
+        time: 0.79,
+        kelvin: 9000.0,
+        illuminance: 400.0,
+        ambient_kelvin: 12000.0,
+        ambient: 40.0,
+    },
+    Keyframe {
+        time: 1.0,
+        kelvin: 9000.0,
+        illuminance: 400.0,
+        ambient_kelvin: 12000.0,
+        ambient: 40.0,
+    },
+];
+
+/// the colour of a black body at a temperature, after Tanner Helland's fit
+fn kelvin_to_color(kelvin: f32) -> Color {
+    let t = kelvin / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.7 * (t - 60.0).powf(-0.1332)
+    };
+    let green = if t <= 66.0 {
+        99.47 * t.ln() - 161.12
+    } else {
+        288.12 * (t - 60.0).powf(-0.0755)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.52 * (t - 10.0).ln() - 305.04
+    };
+    let channel = |c: f32| (c / 255.0).clamp(0.0, 1.0);
+    Color::srgb(channel(red), channel(green), channel(blue))
+}
+
+/// the directional light moved by the cycle
+#[derive(Component)]
+pub(crate) struct Sun;
+
+/// spawn the sun, with shadow cascades sized for the demo scene rather than bevy's default of
+/// a thousand units, so the near cascade stays sharp
+pub(crate) fn setup_sun(mut commands: Commands) {
+    commands
+        .spawn(DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: true,
+                ..default()
+            },
+            cascade_shadow_config: CascadeShadowConfigBuilder {
+                num_cascades: 3,
+                first_cascade_far_bound: 12.0,
+                maximum_distance: 80.0,
+                ..default()
+            }
+            .build(),
+            ..default()
+        })
+        .insert(world_and_viewmodel_layers())
+        .insert(Sun);
+}
+
+/// listen for Action::PauseTimeOfDay, and advance the time of day, faster while
+/// Action::FastForwardTime is held
+pub(crate) fn advance_time_of_day(
+    time: Res<Time>,
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    photo_mode: Option<Res<PhotoMode>>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    if bindings.just_pressed(&keyboard, Action::PauseTimeOfDay) {
+        time_of_day.paused = !time_of_day.paused;
+    }
+    if photo_mode.is_some() {
+        return;
+    }
+    let speed = if bindings.pressed(&keyboard, Action::FastForwardTime) {
+        time_of_day.fast_forward
+    } else {
+        1.0
+    };
+    // leave the resource untouched while paused, so apply_time_of_day has nothing to do
+    if !time_of_day.paused {
+        time_of_day.advance(time.delta_seconds() * speed);
+    }
+}
+
+/// light the scene for the time of day
+pub(crate) fn apply_time_of_day(
+    time_of_day: Res<TimeOfDay>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun: Query<(&mut DirectionalLight, &mut Transform), With<Sun>>,
+) {
+    if !time_of_day.is_changed() {
+        return;
+    }
+    let daylight = time_of_day.daylight();
+    ambient.color = daylight.ambient_color;
+    ambient.brightness = daylight.ambient_brightness;
+
+    for (mut light, mut transform) in &mut sun {
+        light.color = daylight.color;
+        light.illuminance = daylight.illuminance;
+
+        // turn in steps rather than every frame
+        let direction = *daylight.direction;
+        if transform.forward().angle_between(direction).to_degrees() >= SUN_STEP {
+            *transform = Transform::default().looking_to(direction, Vec3::Y);
+        }
+    }
+}
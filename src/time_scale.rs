@@ -0,0 +1,77 @@
+//! game speed: the TimeScale slowing or speeding up everything but the camera, and the
+//! bullet-time toggle
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    input::{Action, KeyBindings, Keys},
+    PHYSICS_HZ,
+};
+
+/// the TimeScale Action::ToggleBulletTime drops to
+const BULLET_TIME_SCALE: f32 = 0.3;
+
+/// how fast the game runs: 1 is normal speed, 0.5 half speed. it sets the speed of bevy's
+/// virtual clock and shortens the fixed timestep and rapier's dt to match, so the fixed
+/// schedule still ticks PHYSICS_HZ times a real second, each tick stepping less game time.
+/// physics, movement, jumps and everything else timed by it slow down together without
+/// stuttering, while the camera and look keep real time to stay responsive. it's clamped
+/// between MIN and MAX
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl TimeScale {
+    pub const MIN: f32 = 0.05;
+    pub const MAX: f32 = 4.0;
+
+    /// the scale the game actually runs at
+    pub fn clamped(self) -> f32 {
+        if self.0.is_nan() {
+            1.0
+        } else {
+            self.0.clamp(Self::MIN, Self::MAX)
+        }
+    }
+}
+
+/// run the virtual clock at TimeScale, and step the fixed schedule and rapier by that much
+/// less (or more) game time each tick, from the frame after it changes
+pub(crate) fn apply_time_scale(
+    scale: Res<TimeScale>,
+    mut time: ResMut<Time<Virtual>>,
+    mut fixed: ResMut<Time<Fixed>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if !scale.is_changed() {
+        return;
+    }
+    let scale = scale.clamped();
+    time.set_relative_speed(scale);
+    fixed.set_timestep_seconds(scale as f64 / PHYSICS_HZ);
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: scale / PHYSICS_HZ as f32,
+        substeps: 1,
+    };
+}
+
+/// listen for Action::ToggleBulletTime, dropping to BULLET_TIME_SCALE or back to normal speed
+pub(crate) fn toggle_bullet_time(
+    keyboard: Keys,
+    bindings: Res<KeyBindings>,
+    mut scale: ResMut<TimeScale>,
+) {
+    if bindings.just_pressed(&keyboard, Action::ToggleBulletTime) {
+        scale.0 = if scale.clamped() < 1.0 {
+            1.0
+        } else {
+            BULLET_TIME_SCALE
+        };
+    }
+}
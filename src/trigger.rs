@@ -0,0 +1,106 @@
+//! trigger volumes: sensor colliders that send events as players (and anything else marked to
+//! set them off) go in and out
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::Player;
+
+/// a sensor that sends TriggerEnter and TriggerExit as players, or entities with a
+/// TriggerActivator, start and stop overlapping it. spawn it with a Collider and a Sensor
+#[derive(Component, Clone, Default, Debug)]
+pub struct TriggerVolume {
+    /// what the volume is for, e.g. "checkpoint" or "music:cave", for game code to match on
+    pub tag: Option<String>,
+}
+
+impl TriggerVolume {
+    pub fn tagged(tag: impl Into<String>) -> Self {
+        Self {
+            tag: Some(tag.into()),
+        }
+    }
+}
+
+/// lets an entity other than a player set off trigger volumes
+#[derive(Component, Default)]
+pub struct TriggerActivator;
+
+/// event sent when an entity starts overlapping a trigger volume
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TriggerEnter {
+    pub volume: Entity,
+    pub other: Entity,
+}
+
+/// event sent when an entity stops overlapping a trigger volume, including when either is
+/// teleported away or despawned
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TriggerExit {
+    pub volume: Entity,
+    pub other: Entity,
+}
+
+/// who's in each trigger volume, as of the last physics step
+#[derive(Resource, Default)]
+pub(crate) struct TriggerOccupants(HashMap<Entity, HashSet<Entity>>);
+
+/// trigger volumes, sending TriggerEnter and TriggerExit once each physics step is written back
+#[derive(Clone, Default)]
+pub struct TriggerPlugin;
+
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TriggerEnter>()
+            .add_event::<TriggerExit>()
+            .init_resource::<TriggerOccupants>()
+            .add_systems(
+                FixedUpdate,
+                update_trigger_volumes.after(PhysicsSet::Writeback),
+            );
+    }
+}
+
+/// compare each volume's overlaps after the physics step with the last ones, sending events for
+/// the differences. overlaps are read fresh every tick rather than from collision events, so
+/// nothing is missed when an occupant jumps straight out or a volume goes away while occupied
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_trigger_volumes(
+    rapier_context: Res<RapierContext>,
+    mut occupants: ResMut<TriggerOccupants>,
+    volumes: Query<Entity, With<TriggerVolume>>,
+    activators: Query<(), Or<(With<Player>, With<TriggerActivator>)>>,
+    mut ew_enter: EventWriter<TriggerEnter>,
+    mut ew_exit: EventWriter<TriggerExit>,
+) {
+    // volumes that are gone empty out
+    occupants.0.retain(|&volume, inside| {
+        if volumes.contains(volume) {
+            return true;
+        }
+        for &other in inside.iter() {
+            ew_exit.send(TriggerExit { volume, other });
+        }
+        false
+    });
+
+    for volume in &volumes {
+        let now: HashSet<Entity> = rapier_context
+            .intersection_pairs_with(volume)
+            .filter(|&(_, _, intersecting)| intersecting)
+            .map(|(a, b, _)| if a == volume { b } else { a })
+            .filter(|&other| activators.contains(other))
+            .collect();
+        let inside = occupants.0.entry(volume).or_default();
+        for &other in inside.difference(&now) {
+            ew_exit.send(TriggerExit { volume, other });
+        }
+        for &other in now.difference(inside) {
+            ew_enter.send(TriggerEnter { volume, other });
+        }
+        *inside = now;
+    }
+}
@@ -0,0 +1,278 @@
+//! wall running: an airborne player moving along a wall and holding towards it runs along it
+//! for a while under lighter gravity, and can jump off it
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    builtins::TnuaBuiltinKnockback, TnuaAction, TnuaActionContext, TnuaActionInitiationDirective,
+    TnuaActionLifecycleDirective, TnuaActionLifecycleStatus, TnuaMotor, TnuaVelChange,
+};
+
+use crate::{Ability, AbilityContext, Player, PlayerMovementState, PlayerSettings, WorldUp};
+
+/// wall run tuning, shared by every player's WallRunAbility
+#[derive(Resource, Clone, Debug)]
+pub struct WallRunConfig {
+    /// fraction of gravity left while running; 0 runs level, 1 falls as usual
+    pub gravity_scale: f32,
+    /// seconds a run lasts at most before the player drops off
+    pub max_duration: f32,
+    /// seconds after a run ends before another can start
+    pub cooldown: f32,
+    /// speed along the wall while running
+    pub speed: f32,
+    /// speed along the wall needed to start a run
+    pub min_speed: f32,
+    /// speed a wall jump pushes out from the wall; the jump up is the usual jump height
+    pub jump_push: f32,
+}
+
+impl Default for WallRunConfig {
+    fn default() -> Self {
+        Self {
+            gravity_scale: 0.2,
+            max_duration: 1.5,
+            cooldown: 0.4,
+            speed: 9.0,
+            min_speed: 2.0,
+            jump_push: 7.0,
+        }
+    }
+}
+
+/// a wall found beside an airborne player
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WallContact {
+    pub entity: Entity,
+    /// out of the wall, across up
+    pub normal: Vec3,
+}
+
+/// the wall beside each player this tick, for the AbilityContext
+#[derive(Component, Default)]
+pub(crate) struct NearbyWall(pub(crate) Option<WallContact>);
+
+/// furthest a wall can be from the side of the capsule and still be run along
+const WALL_REACH: f32 = 0.3;
+
+/// steepest a wall's normal can point up or down, as the sine of its angle off level, and
+/// still count as a wall
+const WALL_MAX_TILT: f32 = 0.3;
+
+/// how much of the input has to point into the wall to count as holding towards it
+const WALL_HOLD: f32 = 0.2;
+
+/// cast to either side of each airborne player, across the way they're moving, for a wall to
+/// run along. like bonk_ceilings, this runs before update_player so abilities see this tick's
+/// walls
+#[allow(clippy::type_complexity)]
+pub(crate) fn detect_walls(
+    up: Res<WorldUp>,
+    rapier_context: Res<RapierContext>,
+    mut players: Query<
+        (
+            Entity,
+            &Transform,
+            &Velocity,
+            &PlayerSettings,
+            &PlayerMovementState,
+            &mut NearbyWall,
+        ),
+        With<Player>,
+    >,
+) {
+    for (player, transform, velocity, settings, state, mut wall) in &mut players {
+        wall.0 = None;
+        let Some(forward) = up.horizontal(velocity.linvel).try_normalize() else {
+            continue;
+        };
+        if state.is_grounded() {
+            continue;
+        }
+
+        // a little inside the capsule, so one already touching the wall doesn't start in it
+        let ball = Collider::ball(settings.capsule_radius * 0.9);
+        let reach = settings.capsule_radius * 0.1 + WALL_REACH;
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(player);
+        let side = up.up.cross(forward);
+        wall.0 = [side, -side]
+            .into_iter()
+            .filter_map(|side| {
+                let (entity, hit) = rapier_context.cast_shape(
+                    transform.translation,
+                    Quat::IDENTITY,
+                    side,
+                    &ball,
+                    ShapeCastOptions::with_max_time_of_impact(reach),
+                    filter,
+                )?;
+                // the first shape is the one hit
+                let normal = hit.details?.normal1;
+                (up.vertical(normal).abs() <= WALL_MAX_TILT).then(|| {
+                    let contact = WallContact {
+                        entity,
+                        normal: up.horizontal(normal).normalize_or_zero(),
+                    };
+                    (hit.time_of_impact, contact)
+                })
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, contact)| contact);
+    }
+}
+
+/// the tnua action fed while wall running. it takes over from the walk (and from a jump,
+/// which would otherwise keep its fall gravity until landing): it pulls the player towards
+/// `velocity` along the wall and holds them up against all but `gravity_scale` of gravity
+struct TnuaWallRun {
+    /// across up
+    velocity: Vec3,
+    /// the horizontal acceleration towards `velocity`
+    acceleration: f32,
+    gravity: f32,
+    gravity_scale: f32,
+}
+
+impl TnuaAction for TnuaWallRun {
+    const NAME: &'static str = "TnuaWallRun";
+    /// whether the fall the run started on has been caught
+    type State = bool;
+    const VIOLATES_COYOTE_TIME: bool = true;
+
+    fn apply(
+        &self,
+        caught: &mut bool,
+        ctx: TnuaActionContext,
+        lifecycle_status: TnuaActionLifecycleStatus,
+        motor: &mut TnuaMotor,
+    ) -> TnuaActionLifecycleDirective {
+        let up = *ctx.up_direction;
+        let velocity = ctx.tracker.velocity;
+        let horizontal = velocity.reject_from(up);
+        let pull =
+            (self.velocity - horizontal).clamp_length_max(self.acceleration * ctx.frame_duration);
+        let mut boost = pull;
+        if !std::mem::replace(caught, true) {
+            boost -= up * velocity.dot(up).min(0.0);
+        }
+        motor.lin = TnuaVelChange {
+            acceleration: up * self.gravity * (1.0 - self.gravity_scale),
+            boost,
+        };
+        lifecycle_status.directive_simple()
+    }
+
+    fn initiation_decision(
+        &self,
+        _ctx: TnuaActionContext,
+        _being_fed_for: &bevy::time::Stopwatch,
+    ) -> TnuaActionInitiationDirective {
+        TnuaActionInitiationDirective::Allow
+    }
+}
+
+/// a run in progress
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    /// seconds since it started
+    time: f32,
+    /// which way along the wall it's going
+    along: Vec3,
+}
+
+/// the wall run, tuned by WallRunConfig. an airborne player moving along a wall at
+/// `min_speed` or more and holding towards it starts running once they're no longer rising,
+/// and stops when the wall ends, they let go, land or run out of time. a fresh jump press
+/// jumps off the wall
+#[derive(Default)]
+pub struct WallRunAbility {
+    run: Option<Run>,
+    /// a run ran out of time, so there's no other until the player lets go of the wall
+    spent: bool,
+    was_jumping: bool,
+}
+
+impl Ability for WallRunAbility {
+    fn name(&self) -> &'static str {
+        "wall run"
+    }
+
+    fn excludes(&self) -> &'static [&'static str] {
+        &["crouch", "ground slam"]
+    }
+
+    fn update(&mut self, ctx: &mut AbilityContext) -> bool {
+        let (config, up) = (ctx.wall_run, ctx.up);
+        let just_jumped = ctx.intent.jump && !self.was_jumping;
+        self.was_jumping = ctx.intent.jump;
+
+        let wall = ctx.wall.filter(|wall| {
+            ctx.direction.dot(-wall.normal) >= WALL_HOLD && !ctx.state.is_grounded()
+        });
+        let Some(wall) = wall else {
+            self.spent = false;
+            self.end(ctx);
+            return false;
+        };
+        let along_wall = |v: Vec3| up.horizontal(v).reject_from_normalized(wall.normal);
+
+        let mut run = match self.run {
+            Some(run) => run,
+            None => {
+                // runs start from the top of a jump or on the way down
+                let along = along_wall(ctx.velocity);
+                let rising = up.vertical(ctx.velocity) > 0.0;
+                if self.spent || !ctx.ready() || along.length() < config.min_speed || rising {
+                    return false;
+                }
+                Run {
+                    time: 0.0,
+                    along: along.normalize(),
+                }
+            }
+        };
+        run.time += ctx.dt;
+        // follow the wall round, keeping the way it was going
+        run.along = along_wall(run.along).try_normalize().unwrap_or(run.along);
+        self.run = Some(run);
+        if run.time > config.max_duration {
+            self.spent = true;
+            self.end(ctx);
+            return false;
+        }
+
+        if just_jumped {
+            let vertical = up.vertical(ctx.velocity);
+            let jump = (2.0 * up.gravity * ctx.settings.jump_height).sqrt();
+            ctx.controller.action(TnuaBuiltinKnockback {
+                shove: wall.normal * config.jump_push + *up.up * (jump - vertical),
+                ..default()
+            });
+            self.end(ctx);
+            return false;
+        }
+
+        // the jump button is the run's, for jumping off
+        ctx.jump_taken = true;
+        ctx.controller.action(TnuaWallRun {
+            velocity: run.along * config.speed,
+            acceleration: ctx.movement.acceleration,
+            gravity: up.gravity,
+            gravity_scale: config.gravity_scale,
+        });
+        true
+    }
+}
+
+impl WallRunAbility {
+    /// stop running, if running, and start the cooldown. the jump button is still the run's
+    /// this tick, so jump held through the end doesn't count as a fresh press
+    fn end(&mut self, ctx: &mut AbilityContext) {
+        if self.run.take().is_some() {
+            ctx.cooldown = ctx.wall_run.cooldown;
+            ctx.jump_taken = true;
+        }
+    }
+}
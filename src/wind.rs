@@ -0,0 +1,197 @@
+//! wind zones: sensor regions that blow the dynamic bodies inside them along, and bias the walk
+//! of the players inside them so they're slowed walking into it and drift downwind in the air
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    prelude::{TnuaBuiltinWalk, TnuaController},
+    TnuaUserControlsSystemSet,
+};
+
+use crate::{
+    menu_closed,
+    push::{self, impulse_body},
+    update_player, Player, WorldUp,
+};
+
+/// blows whatever's inside its collider, which should be a Sensor, along `direction`. players
+/// walk as if the ground were moving at the wind's speed, across up only; dynamic bodies are
+/// dragged towards it
+#[derive(Component, Clone, Copy, Debug)]
+pub struct WindZone {
+    pub direction: Dir3,
+    /// wind speed, in units per second
+    pub strength: f32,
+    /// how much it gusts, from 0 (steady) to 1 (dropping to nothing and gusting to double)
+    pub turbulence: f32,
+}
+
+impl WindZone {
+    pub fn new(direction: Dir3, strength: f32) -> Self {
+        Self {
+            direction,
+            strength,
+            turbulence: 0.0,
+        }
+    }
+
+    pub fn with_turbulence(mut self, turbulence: f32) -> Self {
+        self.turbulence = turbulence;
+        self
+    }
+
+    /// the wind's velocity `seconds` in, gusting differently for each `seed` so neighbouring
+    /// zones don't gust in step
+    pub fn velocity(&self, seconds: f32, seed: u32) -> Vec3 {
+        let t = seconds + seed as f32 * 1.7;
+        // two sines out of step with each other, so the gusts don't repeat too obviously
+        let gust = ((t * 1.3).sin() + (t * 3.5).sin()) * 0.5;
+        *self.direction * self.strength * (1.0 + self.turbulence.clamp(0.0, 1.0) * gust)
+    }
+}
+
+/// wind zones blowing players and bodies along
+#[derive(Clone, Default)]
+pub struct WindPlugin;
+
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                blow_players
+                    .after(push::track_push_contacts)
+                    .before(push::push_bodies),
+                blow_bodies.after(update_player),
+            )
+                .in_set(TnuaUserControlsSystemSet)
+                .run_if(menu_closed),
+        )
+        .add_systems(
+            Update,
+            draw_wind_zones.run_if(resource_exists::<GizmoConfigStore>),
+        );
+    }
+}
+
+/// force a wind zone drags a body with for each unit per second it's slower than the wind, in
+/// newtons. a crate's bulk rather than its mass is what catches the wind, so the lightest crates
+/// blow away and the heaviest barely notice
+const WIND_DRAG: f32 = 4.0;
+
+/// the wind velocity at `entity` summed over the zones it's in
+fn wind_at(
+    entity: Entity,
+    seconds: f32,
+    rapier_context: &RapierContext,
+    zones: &Query<(Entity, &WindZone)>,
+) -> Vec3 {
+    rapier_context
+        .intersection_pairs_with(entity)
+        .filter(|&(_, _, intersecting)| intersecting)
+        .filter_map(|(a, b, _)| zones.get(if a == entity { b } else { a }).ok())
+        .map(|(zone, wind)| wind.velocity(seconds, zone.index()))
+        .sum()
+}
+
+/// add the wind the players are in to the walk update_player fed, so tnua moves them with it
+/// rather than cancelling it out as it would a force. this runs before push_bodies, which pushes
+/// with the blown walk
+pub(crate) fn blow_players(
+    time: Res<Time>,
+    up: Res<WorldUp>,
+    rapier_context: Res<RapierContext>,
+    zones: Query<(Entity, &WindZone)>,
+    mut players: Query<(Entity, &mut TnuaController), With<Player>>,
+) {
+    if zones.is_empty() {
+        return;
+    }
+
+    for (player, mut controller) in &mut players {
+        let wind = up.horizontal(wind_at(
+            player,
+            time.elapsed_seconds(),
+            &rapier_context,
+            &zones,
+        ));
+        if wind == Vec3::ZERO {
+            continue;
+        }
+        let Some((walk, _)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+            continue;
+        };
+        let mut walk = walk.clone();
+        walk.desired_velocity += wind;
+        controller.basis(walk);
+    }
+}
+
+/// drag the dynamic bodies in wind zones along towards the wind's speed
+pub(crate) fn blow_bodies(
+    time: Res<Time>,
+    mut rapier_context: ResMut<RapierContext>,
+    zones: Query<(Entity, &WindZone)>,
+    mut velocities: Query<&mut Velocity>,
+    players: Query<(), With<Player>>,
+) {
+    // each body's colliders once, however many zones they're in
+    let mut blown = Vec::new();
+    for (zone, _) in &zones {
+        for (a, b, intersecting) in rapier_context.intersection_pairs_with(zone) {
+            let other = if a == zone { b } else { a };
+            // the pair has colliders; blow the body they belong to
+            let body = rapier_context.collider_parent(other).unwrap_or(other);
+            if intersecting && !players.contains(body) && !blown.contains(&(body, other)) {
+                blown.push((body, other));
+            }
+        }
+    }
+
+    for (entity, collider) in blown {
+        let wind = wind_at(collider, time.elapsed_seconds(), &rapier_context, &zones);
+        let speed = wind.length();
+        if speed <= 0.0 {
+            continue;
+        }
+        impulse_body(&mut rapier_context, &mut velocities, entity, |body| {
+            let along = wind / speed;
+            let behind = (speed - Vec3::from(*body.linvel()).dot(along)).max(0.0);
+            Some(along * WIND_DRAG * behind * time.delta_seconds())
+        });
+    }
+}
+
+/// streaks blown along through each wind zone, faster the stronger it is, so the demo's zones
+/// can be seen
+pub(crate) fn draw_wind_zones(
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+    zones: Query<(&WindZone, &Collider, &GlobalTransform)>,
+) {
+    /// streaks per cubic unit of zone
+    const DENSITY: f32 = 0.5;
+    const LENGTH: f32 = 0.6;
+
+    for (wind, collider, transform) in &zones {
+        let aabb = collider.raw.compute_local_aabb();
+        let (mins, maxs) = (Vec3::from(aabb.mins.coords), Vec3::from(aabb.maxs.coords));
+        let size = (maxs - mins).max(Vec3::splat(f32::EPSILON));
+        let (_, rotation, _) = transform.to_scale_rotation_translation();
+        let local = rotation.inverse() * *wind.direction;
+        // at the steady wind speed, as the gusts would have them jumping back and forth
+        let travelled = local * wind.strength * time.elapsed_seconds();
+        let count = (size.x * size.y * size.z * DENSITY).ceil() as u32;
+        for i in 1..=count {
+            // spread evenly through the zone, from a low-discrepancy sequence
+            let spread = (i as f32 * Vec3::new(0.819_172_5, 0.671_043_6, 0.549_700_5)).fract();
+            let point = mins + (spread * size + travelled).rem_euclid(size);
+            let tail = point - local * LENGTH;
+            gizmos.line(
+                transform.transform_point(tail),
+                transform.transform_point(point),
+                Color::srgba(0.9, 0.95, 1.0, 0.6),
+            );
+        }
+    }
+}
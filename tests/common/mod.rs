@@ -0,0 +1,109 @@
+//! the headless app fixture shared by the integration tests
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use bevy::{
+    input::InputPlugin, prelude::*, render::view::VisibilityPlugin, scene::ScenePlugin,
+    state::app::StatesPlugin, time::TimeUpdateStrategy,
+};
+use bevy_character_starter::{
+    MechanicsPlugins, PlayerCameraPlugin, PlayerControllerPlugin, PlayerEntity, PlayerSettings,
+};
+use bevy_rapier3d::prelude::*;
+
+/// a headless app with what the controller needs and nothing of it added yet, stepping one
+/// physics tick per update
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        // rapier's async colliders need the scene spawner
+        ScenePlugin,
+        InputPlugin,
+        StatesPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / 60.0,
+    )));
+    app
+}
+
+/// a headless app with the players and their cameras over the ground, not stepped yet. the
+/// camera plugin needs a primary window, which WindowPlugin spawns without opening anything
+pub fn camera_app() -> App {
+    let mut app = headless_app();
+    app.add_plugins((VisibilityPlugin, WindowPlugin::default()))
+        .init_asset::<Image>()
+        .add_plugins((
+            PlayerControllerPlugin::default(),
+            PlayerCameraPlugin::default(),
+        ))
+        .add_systems(Startup, spawn_ground);
+    app
+}
+
+/// a fixed slab 20 wide to stand on, its top at y 0.1
+pub fn spawn_ground(mut commands: Commands) {
+    commands
+        .spawn(TransformBundle::default())
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(10.0, 0.1, 10.0));
+}
+
+/// a headless app, stepping one physics tick per update, with the player settled on the ground
+pub fn app() -> App {
+    app_with_settings(PlayerSettings::default())
+}
+
+pub fn app_with_settings(settings: PlayerSettings) -> App {
+    app_with(settings, PlayerControllerPlugin::default())
+}
+
+pub fn app_with(settings: PlayerSettings, controller: PlayerControllerPlugin) -> App {
+    let mut app = headless_app();
+    app.insert_resource(settings)
+        .add_plugins(controller)
+        .add_systems(Startup, spawn_ground);
+
+    // the player spawns in the air
+    tick(&mut app, 180);
+    app
+}
+
+/// app(), with the level mechanics as well
+pub fn mechanics_app() -> App {
+    let mut app = headless_app();
+    app.add_plugins((PlayerControllerPlugin::default(), MechanicsPlugins))
+        .add_systems(Startup, spawn_ground);
+    tick(&mut app, 180);
+    app
+}
+
+pub fn tick(app: &mut App, ticks: usize) {
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+pub fn player_position(app: &mut App) -> Vec3 {
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world().get::<Transform>(player).unwrap().translation
+}
+
+pub fn press(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+}
+
+pub fn release(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(key);
+}
@@ -0,0 +1,92 @@
+//! headless tests of the keyboard and mouse player's capsule getting out of their own view in
+//! first person
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    pbr::NotShadowCaster,
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_character_starter::{CameraPreset, FirstPersonBody, PlayerCamera, PlayerEntity};
+
+mod common;
+use common::*;
+
+/// a headless app with the player cameras, starting in first person
+fn app() -> App {
+    let mut app = camera_app();
+    tick(&mut app, 10);
+    app
+}
+
+/// cycle the camera rig `times` presets on, pressing V through keyboard events since input
+/// clears a press made straight on the ButtonInput before anything sees it as just pressed
+fn cycle_rig(app: &mut App, times: usize) {
+    for _ in 0..times {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            app.world_mut().send_event(KeyboardInput {
+                key_code: KeyCode::KeyV,
+                logical_key: Key::Character("v".into()),
+                state,
+                window: Entity::PLACEHOLDER,
+            });
+            tick(app, 1);
+        }
+    }
+}
+
+/// whether the player camera sees the player's capsule, and whether it casts a shadow
+fn body(app: &mut App) -> (bool, bool) {
+    let camera = app
+        .world_mut()
+        .query_filtered::<&RenderLayers, With<PlayerCamera>>()
+        .single(app.world())
+        .clone();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let layers = app
+        .world()
+        .get::<RenderLayers>(player)
+        .cloned()
+        .unwrap_or_default();
+    let shadow = app.world().get::<NotShadowCaster>(player).is_none();
+    (camera.intersects(&layers), shadow)
+}
+
+#[test]
+fn capsule_leaves_first_person_view_following_the_camera_rig() {
+    let mut app = app();
+    // shadow only by default
+    assert_eq!(body(&mut app), (false, true));
+    // third person, then on round to first person again
+    cycle_rig(&mut app, 1);
+    assert_eq!(body(&mut app), (true, true));
+    app.insert_resource(FirstPersonBody::Hidden);
+    cycle_rig(&mut app, 3);
+    assert_eq!(body(&mut app), (false, false));
+
+    app.insert_resource(FirstPersonBody::Visible);
+    tick(&mut app, 1);
+    assert_eq!(body(&mut app), (true, true));
+}
+
+#[test]
+fn capsule_follows_a_camera_rig_set_from_code() {
+    let mut app = app();
+    app.insert_resource(CameraPreset::ThirdPerson.rig());
+    tick(&mut app, 1);
+    assert_eq!(body(&mut app), (true, true));
+
+    // the camera sits the rig's distance behind the player
+    let camera = *app
+        .world_mut()
+        .query_filtered::<&Transform, With<PlayerCamera>>()
+        .single(app.world());
+    let distance = CameraPreset::ThirdPerson.rig().distance;
+    assert!(
+        (camera.translation.z - distance).abs() < 0.01,
+        "at {camera:?}"
+    );
+}
@@ -0,0 +1,61 @@
+//! Grounded's debouncing of tnua's airborne reports
+
+use bevy_character_starter::Grounded;
+
+const DT: f32 = 1.0 / 60.0;
+const DEBOUNCE: f32 = 0.04;
+
+fn on_ground() -> Grounded {
+    let mut grounded = Grounded::default();
+    grounded.update(DEBOUNCE, false, false, DT);
+    grounded
+}
+
+#[test]
+fn single_tick_flicker_stays_grounded() {
+    let mut grounded = on_ground();
+    for airborne in [true, false, true, false, false, true, false] {
+        grounded.update(DEBOUNCE, airborne, false, DT);
+        assert!(grounded.is_grounded());
+    }
+}
+
+#[test]
+fn sustained_airborne_counts_after_the_debounce() {
+    let mut grounded = on_ground();
+    grounded.update(DEBOUNCE, true, false, DT);
+    grounded.update(DEBOUNCE, true, false, DT);
+    assert!(grounded.is_grounded());
+    grounded.update(DEBOUNCE, true, false, DT);
+    assert!(!grounded.is_grounded());
+
+    // and landing counts straight away
+    grounded.update(DEBOUNCE, false, false, DT);
+    assert!(grounded.is_grounded());
+}
+
+#[test]
+fn flicker_doesnt_add_up_across_landings() {
+    let mut grounded = on_ground();
+    for _ in 0..10 {
+        grounded.update(DEBOUNCE, true, false, DT);
+        grounded.update(DEBOUNCE, true, false, DT);
+        grounded.update(DEBOUNCE, false, false, DT);
+    }
+    assert!(grounded.is_grounded());
+}
+
+#[test]
+fn jumping_leaves_the_ground_at_once() {
+    let mut grounded = on_ground();
+    // tnua still reports grounded on a jump's first tick
+    grounded.update(DEBOUNCE, false, true, DT);
+    assert!(!grounded.is_grounded());
+}
+
+#[test]
+fn zero_debounce_follows_tnua() {
+    let mut grounded = on_ground();
+    grounded.update(0.0, true, false, DT);
+    assert!(!grounded.is_grounded());
+}
@@ -0,0 +1,142 @@
+//! LocomotionState for given motion
+
+use bevy::prelude::*;
+use bevy_character_starter::{LocomotionConfig, LocomotionState};
+
+fn state(velocity: Vec3, grounded: bool, crouched: bool, sprinting: bool) -> LocomotionState {
+    LocomotionState::from_motion(
+        &LocomotionConfig::default(),
+        velocity,
+        grounded,
+        crouched,
+        sprinting,
+        0.0,
+        LocomotionState::Idle,
+    )
+}
+
+/// airborne for `air_time` seconds, coming from `previous`
+fn airborne(vertical_speed: f32, air_time: f32, previous: LocomotionState) -> LocomotionState {
+    LocomotionState::from_motion(
+        &LocomotionConfig::default(),
+        Vec3::Y * vertical_speed,
+        false,
+        false,
+        false,
+        air_time,
+        previous,
+    )
+}
+
+#[test]
+fn grounded_states_follow_speed() {
+    assert_eq!(state(Vec3::ZERO, true, false, false), LocomotionState::Idle);
+    assert_eq!(
+        state(Vec3::new(0.2, 0.0, 0.2), true, false, false),
+        LocomotionState::Idle
+    );
+    assert_eq!(
+        state(Vec3::new(0.0, 0.0, -10.0), true, false, false),
+        LocomotionState::Walk
+    );
+    assert_eq!(
+        state(Vec3::new(0.0, 0.0, -16.0), true, false, false),
+        LocomotionState::Run
+    );
+}
+
+#[test]
+fn vertical_speed_doesnt_count_as_moving() {
+    assert_eq!(
+        state(Vec3::new(0.0, 3.0, 0.0), true, false, false),
+        LocomotionState::Idle
+    );
+}
+
+#[test]
+fn sprinting_runs_above_idle() {
+    assert_eq!(
+        state(Vec3::new(4.0, 0.0, 0.0), true, false, true),
+        LocomotionState::Run
+    );
+    assert_eq!(state(Vec3::ZERO, true, false, true), LocomotionState::Idle);
+}
+
+#[test]
+fn airborne_wins_over_crouch() {
+    assert_eq!(
+        state(Vec3::new(0.0, 5.0, -10.0), false, true, false),
+        LocomotionState::Jump
+    );
+    assert_eq!(
+        state(Vec3::new(0.0, -5.0, 0.0), false, false, false),
+        LocomotionState::Fall
+    );
+    assert_eq!(
+        state(Vec3::new(0.0, 0.0, -10.0), true, true, true),
+        LocomotionState::Crouch
+    );
+}
+
+#[test]
+fn thresholds_are_configurable() {
+    let config = LocomotionConfig {
+        walk_speed: 2.0,
+        run_speed: 8.0,
+        ..default()
+    };
+    let state = |speed| {
+        LocomotionState::from_motion(
+            &config,
+            Vec3::X * speed,
+            true,
+            false,
+            false,
+            0.0,
+            LocomotionState::Idle,
+        )
+    };
+    assert_eq!(state(1.0), LocomotionState::Idle);
+    assert_eq!(state(5.0), LocomotionState::Walk);
+    assert_eq!(state(9.0), LocomotionState::Run);
+}
+
+#[test]
+fn jump_holds_through_the_apex() {
+    use LocomotionState::*;
+    assert_eq!(airborne(6.0, 0.1, Walk), Jump);
+    // vertical speed hovering around zero at the top doesn't flicker to fall and back
+    assert_eq!(airborne(0.1, 0.5, Jump), Jump);
+    assert_eq!(airborne(-0.1, 0.55, Jump), Jump);
+    assert_eq!(airborne(-2.0, 0.7, Jump), Fall);
+    assert_eq!(airborne(0.1, 0.75, Fall), Fall);
+}
+
+#[test]
+fn walking_off_a_ledge_falls() {
+    assert_eq!(
+        airborne(0.0, 0.02, LocomotionState::Walk),
+        LocomotionState::Fall
+    );
+}
+
+#[test]
+fn falls_after_max_rise_time() {
+    let max = LocomotionConfig::default().max_rise_time;
+    assert_eq!(
+        airborne(3.0, max - 0.1, LocomotionState::Jump),
+        LocomotionState::Jump
+    );
+    assert_eq!(
+        airborne(3.0, max, LocomotionState::Jump),
+        LocomotionState::Fall
+    );
+}
+
+#[test]
+fn air_jump_rises_again() {
+    assert_eq!(
+        airborne(6.0, 1.0, LocomotionState::Fall),
+        LocomotionState::Jump
+    );
+}
@@ -0,0 +1,186 @@
+//! headless tests of CameraLookTarget and LookAt turning the keyboard and mouse player's view
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+use bevy_character_starter::{
+    CameraLookTarget, LookAt, LookTarget, PlayerCamera, PlayerEntity, WorldUp,
+};
+
+mod common;
+use common::*;
+
+/// a headless app with the player cameras, settled on the ground
+fn app() -> App {
+    let mut app = camera_app();
+    tick(&mut app, 120);
+    app
+}
+
+/// the keyboard and mouse player's body yaw and camera pitch, in radians
+fn view(app: &mut App) -> (f32, f32) {
+    let player = app.world().resource::<PlayerEntity>().0;
+    let rotation = app.world().get::<Transform>(player).unwrap().rotation;
+    let pitch = app
+        .world_mut()
+        .query::<&PlayerCamera>()
+        .single(app.world())
+        .0;
+    (rotation.to_euler(EulerRot::YXZ).0, pitch)
+}
+
+fn eye(app: &mut App) -> Vec3 {
+    let player = app.world().resource::<PlayerEntity>().0;
+    let camera = app
+        .world_mut()
+        .query_filtered::<&GlobalTransform, With<PlayerCamera>>()
+        .single(app.world())
+        .translation();
+    let body = app.world().get::<Transform>(player).unwrap().translation;
+    Vec3::new(body.x, camera.y, body.z)
+}
+
+fn look_at(app: &mut App, target: Option<LookTarget>) {
+    app.world_mut().resource_mut::<CameraLookTarget>().0 = target;
+}
+
+#[test]
+fn eases_onto_the_target_and_lets_go_when_cleared() {
+    let mut app = app();
+    let eye = eye(&mut app);
+    look_at(&mut app, Some(LookTarget::Point(eye + Vec3::X * 10.0)));
+
+    // blended in, so the first frame barely moves
+    tick(&mut app, 1);
+    let (yaw, _) = view(&mut app);
+    assert!(yaw.abs() < 0.05, "snapped to {yaw}");
+
+    tick(&mut app, 60);
+    let (yaw, pitch) = view(&mut app);
+    assert!((yaw + FRAC_PI_2).abs() < 0.02, "turned to {yaw}");
+    assert!(pitch.abs() < 0.02, "pitched to {pitch}");
+
+    // cleared, the view stays where it was left rather than swinging back
+    look_at(&mut app, None);
+    tick(&mut app, 60);
+    let (yaw, _) = view(&mut app);
+    assert!((yaw + FRAC_PI_2).abs() < 0.02, "drifted to {yaw}");
+}
+
+#[test]
+fn look_at_event_turns_the_view_once() {
+    let mut app = app();
+    let eye = eye(&mut app);
+    app.world_mut().send_event(LookAt {
+        target: eye + Vec3::X * 10.0,
+        duration: 0.0,
+    });
+    tick(&mut app, 1);
+    let (yaw, pitch) = view(&mut app);
+    assert!((yaw + FRAC_PI_2).abs() < 0.02, "turned to {yaw}");
+    assert!(pitch.abs() < 0.02, "pitched to {pitch}");
+
+    // and leaves it there
+    tick(&mut app, 30);
+    let (yaw, _) = view(&mut app);
+    assert!((yaw + FRAC_PI_2).abs() < 0.02, "drifted to {yaw}");
+}
+
+#[test]
+fn respects_the_pitch_clamp() {
+    let mut app = app();
+    let eye = eye(&mut app);
+    look_at(
+        &mut app,
+        Some(LookTarget::Point(eye + Vec3::new(0.0, 100.0, -0.01))),
+    );
+    tick(&mut app, 120);
+    let (_, pitch) = view(&mut app);
+    assert!(pitch > 1.4, "only pitched to {pitch}");
+    assert!(
+        pitch <= 89.01_f32.to_radians(),
+        "pitched past the clamp to {pitch}"
+    );
+}
+
+#[test]
+fn follows_an_entity_until_it_despawns() {
+    let mut app = app();
+    let eye = eye(&mut app);
+    let target = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(eye + Vec3::X * 10.0),
+        ))
+        .id();
+    look_at(&mut app, Some(LookTarget::Entity(target)));
+    tick(&mut app, 60);
+    let (yaw, _) = view(&mut app);
+    assert!((yaw + FRAC_PI_2).abs() < 0.02, "turned to {yaw}");
+
+    // round behind the player
+    app.world_mut()
+        .get_mut::<Transform>(target)
+        .unwrap()
+        .translation = eye + Vec3::Z * 10.0;
+    tick(&mut app, 90);
+    let (yaw, _) = view(&mut app);
+    assert!((yaw.abs() - PI).abs() < 0.02, "turned to {yaw}");
+
+    app.world_mut().despawn(target);
+    tick(&mut app, 1);
+    assert_eq!(
+        *app.world().resource::<CameraLookTarget>(),
+        CameraLookTarget(None)
+    );
+}
+
+#[test]
+fn looks_at_the_origin_standing_up_to_tilted_gravity() {
+    let mut app = camera_app();
+    let up = WorldUp::tilted();
+    app.insert_resource(up);
+    tick(&mut app, 2);
+
+    let player = app.world().resource::<PlayerEntity>().0;
+    let eye = app
+        .world_mut()
+        .query_filtered::<&GlobalTransform, With<PlayerCamera>>()
+        .single(app.world())
+        .translation();
+    // through keyboard events, as the look-at key's matched by the character it types
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::KeyL,
+            logical_key: Key::Character("l".into()),
+            state,
+            window: Entity::PLACEHOLDER,
+        });
+        tick(&mut app, 1);
+    }
+    tick(&mut app, 40);
+
+    // turned about the tilted up rather than the world's y, so still standing along it
+    let body = app.world().get::<Transform>(player).unwrap().rotation;
+    assert!(
+        (body * Vec3::Y).dot(*up.up) > 0.999,
+        "body up {}",
+        body * Vec3::Y
+    );
+    let camera = app
+        .world_mut()
+        .query_filtered::<&GlobalTransform, With<PlayerCamera>>()
+        .single(app.world())
+        .forward();
+    let to_origin = -eye.normalize();
+    assert!(
+        camera.dot(to_origin) > 0.99,
+        "looking along {camera:?}, not {to_origin}"
+    );
+}
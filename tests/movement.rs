@@ -0,0 +1,2040 @@
+//! headless movement tests: the player from PlayerControllerPlugin, driven by synthetic key input
+
+use std::{
+    f32::consts::PI,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use bevy::{
+    ecs::system::RunSystemOnce,
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent, GamepadInfo},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+use bevy_character_starter::{
+    crate_body, spawn_collectible, spawn_player, Abilities, Ability, AbilityContext, ActionEnded,
+    ActionStarted, Activate, Activation, AirTime, Breakable, CallElevator, CeilingBonk, Checkpoint,
+    CheckpointReached, CollectedEvent, Collectible, CurrentCheckpoint, DespawnPlayer, Elevator,
+    GroundMaterial, GroundSlam, InputAction, InputSnapshot, JumpAbility, JumpDirection,
+    KeyboardMouseInput, LocomotionState, MovementIntent, MovementStateChanged, MovingPlatform,
+    PlatformPath, PlayerCamera, PlayerCameraOptions, PlayerControllerPlugin, PlayerEntity,
+    PlayerInput, PlayerMovementState, PlayerSettings, PlayerSpawnOptions, PressurePlate,
+    PropDestroyed, ReachedCheckpoint, ResetLevel, RespawnConfig, RotatingPlatform, SceneObject,
+    Score, SlamShockwave, SlidingDoor, SpawnPoint, SpawnSelection, SpawnedPlayer, TimeScale,
+    TriggerEnter, TriggerExit, TriggerVolume, Upright, UseButton, WallRunConfig, Waypoint,
+    WindZone, WorldUp, DEBRIS_GROUP, HEAVY_CRATE_MASS, LIGHT_CRATE_MASS, MEDIUM_CRATE_MASS,
+};
+use bevy_rapier3d::prelude::*;
+
+mod common;
+use common::*;
+
+#[test]
+fn stays_put_without_input() {
+    let mut app = app();
+    let start = player_position(&mut app);
+
+    tick(&mut app, 60);
+    assert!(player_position(&mut app).distance(start) < 0.01);
+}
+
+#[test]
+fn w_moves_forward() {
+    let mut app = app();
+    let start = player_position(&mut app);
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    let moved = player_position(&mut app) - start;
+
+    // the player starts facing -Z
+    assert!(moved.z < -1.0, "moved {moved}");
+    assert!(moved.x.abs() < 0.01, "moved {moved}");
+
+    let player = app.world().resource::<PlayerEntity>().0;
+    assert_eq!(
+        app.world().get::<LocomotionState>(player),
+        Some(&LocomotionState::Walk)
+    );
+}
+
+#[test]
+fn space_jumps() {
+    let mut app = app();
+    let start = player_position(&mut app);
+
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 20);
+    let height = player_position(&mut app).y - start.y;
+    assert!(height > 1.0, "rose {height}");
+    assert!(air_time(&mut app) > 0.0);
+
+    // and comes back down
+    release(&mut app, KeyCode::Space);
+    tick(&mut app, 120);
+    let height = player_position(&mut app).y - start.y;
+    assert!(height.abs() < 0.05, "landed {height} off");
+    assert_eq!(air_time(&mut app), 0.0);
+}
+
+#[test]
+fn pressing_jump_sends_one_action_started() {
+    let mut app = app();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    app.add_systems(
+        Last,
+        move |mut er_started: EventReader<ActionStarted>,
+              mut er_ended: EventReader<ActionEnded>| {
+            let mut events = sink.lock().unwrap();
+            events.extend(er_started.read().map(|ev| (true, ev.action)));
+            events.extend(er_ended.read().map(|ev| (false, ev.action)));
+        },
+    );
+
+    // held over many ticks, it still only starts once
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 20);
+    assert_eq!(*events.lock().unwrap(), [(true, InputAction::Jump)]);
+
+    release(&mut app, KeyCode::Space);
+    tick(&mut app, 5);
+    assert_eq!(
+        *events.lock().unwrap(),
+        [(true, InputAction::Jump), (false, InputAction::Jump)]
+    );
+}
+
+#[test]
+fn trigger_volumes_send_enter_and_exit() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    app.add_systems(
+        Last,
+        move |mut er_enter: EventReader<TriggerEnter>, mut er_exit: EventReader<TriggerExit>| {
+            let mut events = sink.lock().unwrap();
+            events.extend(er_enter.read().map(|ev| (true, ev.volume, ev.other)));
+            events.extend(er_exit.read().map(|ev| (false, ev.volume, ev.other)));
+        },
+    );
+    let volume_at = |app: &mut App, position: Vec3| {
+        app.world_mut()
+            .spawn(TransformBundle::from_transform(
+                Transform::from_translation(position),
+            ))
+            .insert(Collider::cuboid(1.0, 1.0, 1.0))
+            .insert(Sensor)
+            .insert(TriggerVolume::tagged("test"))
+            .id()
+    };
+
+    // standing in one enters it once
+    let start = player_position(&mut app);
+    let here = volume_at(&mut app, start);
+    tick(&mut app, 10);
+    assert_eq!(*events.lock().unwrap(), [(true, here, player)]);
+
+    // teleporting out leaves it, and into another enters that
+    let there = volume_at(&mut app, Vec3::new(5.0, 1.0, 5.0));
+    let position = player_position(&mut app) + Vec3::new(5.0, 0.0, 5.0);
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = position;
+    tick(&mut app, 10);
+    assert_eq!(
+        events.lock().unwrap()[1..],
+        [(true, there, player), (false, here, player)]
+    );
+
+    // despawning an occupied volume leaves it too
+    app.world_mut().despawn(there);
+    tick(&mut app, 5);
+    assert_eq!(events.lock().unwrap()[3..], [(false, there, player)]);
+}
+
+#[test]
+fn respawns_at_the_latest_new_checkpoint() {
+    let mut app = mechanics_app();
+    app.world_mut().resource_mut::<RespawnConfig>().delay = 0.5;
+    let player = app.world().resource::<PlayerEntity>().0;
+    let reached = Arc::new(Mutex::new(Vec::new()));
+    let sink = reached.clone();
+    app.add_systems(
+        Last,
+        move |mut er_reached: EventReader<CheckpointReached>| {
+            sink.lock()
+                .unwrap()
+                .extend(er_reached.read().map(|ev| ev.checkpoint));
+        },
+    );
+    let checkpoint_at = |app: &mut App, position: Vec3| {
+        app.world_mut()
+            .spawn(TransformBundle::from_transform(
+                Transform::from_translation(position),
+            ))
+            .insert(Collider::cuboid(1.0, 1.0, 1.0))
+            .insert(Sensor)
+            .insert(TriggerVolume::default())
+            .insert(Checkpoint::default())
+            .id()
+    };
+    let teleport = |app: &mut App, position: Vec3| {
+        app.world_mut()
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation = position;
+        tick(app, 10);
+    };
+
+    let start = player_position(&mut app);
+    let first = checkpoint_at(&mut app, start);
+    let second = checkpoint_at(&mut app, Vec3::new(5.0, 0.1, 5.0));
+    tick(&mut app, 10);
+    teleport(&mut app, start + Vec3::new(5.0, 0.0, 5.0));
+    // going back to the first doesn't take over from the second
+    teleport(&mut app, start);
+    assert_eq!(*reached.lock().unwrap(), [first, second]);
+    let current = app.world().resource::<CurrentCheckpoint>().0.unwrap();
+    assert_eq!(current.checkpoint, second);
+    assert!(app.world().get::<Checkpoint>(first).unwrap().reached());
+
+    // falling out of the world respawns at the second
+    teleport(&mut app, Vec3::new(0.0, -60.0, 0.0));
+    assert!(app.world().get_entity(player).is_none());
+    tick(&mut app, 240);
+    let position = player_position(&mut app);
+    assert!(
+        (position.xz() - Vec2::new(5.0, 5.0)).length() < 0.01,
+        "respawned at {position}"
+    );
+}
+
+/// the player's horizontal speed a moment after starting to walk forward, then a moment after
+/// letting go at (near enough) full speed, on ground made of `material`
+fn speeds_on(material: GroundMaterial) -> (f32, f32) {
+    let mut app = app();
+    let ground = app
+        .world_mut()
+        .query_filtered::<Entity, (With<RigidBody>, Without<Velocity>)>()
+        .single(app.world());
+    app.world_mut().entity_mut(ground).insert(material);
+    let speed = |app: &mut App| {
+        let player = app.world().resource::<PlayerEntity>().0;
+        app.world()
+            .get::<Velocity>(player)
+            .unwrap()
+            .linvel
+            .xz()
+            .length()
+    };
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 10);
+    let walking = speed(&mut app);
+    // not so long they walk off the ground
+    tick(&mut app, 50);
+    release(&mut app, KeyCode::KeyW);
+    tick(&mut app, 10);
+    (walking, speed(&mut app))
+}
+
+#[test]
+fn ice_is_slow_to_start_and_stop() {
+    let (walking, stopping) = speeds_on(GroundMaterial::Default);
+    let (walking_on_ice, sliding) = speeds_on(GroundMaterial::Ice);
+    assert!(
+        walking_on_ice < walking * 0.5,
+        "{walking_on_ice} vs {walking}"
+    );
+    assert!(stopping < 0.1, "{stopping}");
+    assert!(sliding > 1.0, "{sliding}");
+}
+
+#[test]
+fn collects_coins_in_passing_and_puts_them_back_on_reset() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let collected = Arc::new(AtomicU32::new(0));
+    let sink = collected.clone();
+    app.add_systems(
+        Last,
+        move |mut er_collected: EventReader<CollectedEvent>| {
+            sink.fetch_add(er_collected.read().count() as u32, Ordering::Relaxed);
+        },
+    );
+    let start = player_position(&mut app);
+    app.world_mut()
+        .run_system_once(move |mut commands: Commands| {
+            for z in [-2.0, -3.0, -4.0] {
+                spawn_collectible(&mut commands, start + Vec3::Z * z);
+            }
+        });
+    let mut coins = app.world_mut().query_filtered::<(), With<Collectible>>();
+    tick(&mut app, 2);
+    assert_eq!(coins.iter(app.world()).count(), 3);
+
+    // shot through the row far faster than walking
+    app.world_mut().get_mut::<Velocity>(player).unwrap().linvel = Vec3::NEG_Z * 30.0;
+    tick(&mut app, 20);
+    assert_eq!(app.world().resource::<Score>().0, 3);
+    assert_eq!(collected.load(Ordering::Relaxed), 3);
+    assert_eq!(coins.iter(app.world()).count(), 0);
+
+    // from clear of the row, so nothing's picked straight back up
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = start + Vec3::X * 5.0;
+    app.world_mut().send_event(ResetLevel);
+    tick(&mut app, 2);
+    assert_eq!(app.world().resource::<Score>().0, 0);
+    assert_eq!(coins.iter(app.world()).count(), 3);
+}
+
+#[test]
+fn spawn_points_pick_where_players_spawn_and_respawn() {
+    let mut app = app();
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            3.0, 0.1, 0.0,
+        )))
+        .insert(SpawnPoint::new(0));
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_xyz(-4.0, 0.1, -4.0).with_rotation(Quat::from_rotation_y(PI)),
+        ))
+        .insert(SpawnPoint::named("far").with_index(1));
+
+    // picking one moves the players straight there, just over it, facing its way
+    app.world_mut().resource_mut::<SpawnSelection>().0 = Some("far".into());
+    tick(&mut app, 1);
+    let player = app.world().resource::<PlayerEntity>().0;
+    let transform = *app.world().get::<Transform>(player).unwrap();
+    assert!(
+        transform.translation.xz().distance(Vec2::new(-4.0, -4.0)) < 0.01,
+        "at {}",
+        transform.translation
+    );
+    assert!((transform.forward().z - 1.0).abs() < 0.01);
+    // settling onto it rather than falling from a height
+    tick(&mut app, 30);
+    let settled = player_position(&mut app);
+    let standing = 0.1 + PlayerSettings::default().float_height();
+    assert!((settled.y - standing).abs() < 0.1, "at {settled}");
+
+    // and respawns there
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = Vec3::new(5.0, 1.6, 5.0);
+    app.world_mut().send_event(DespawnPlayer { player });
+    tick(&mut app, 120);
+    let respawned = player_position(&mut app);
+    assert!(
+        respawned.xz().distance(Vec2::new(-4.0, -4.0)) < 0.05,
+        "respawned at {respawned}"
+    );
+
+    // a name that isn't one falls back to the default
+    app.world_mut().resource_mut::<SpawnSelection>().0 = Some("nowhere".into());
+    tick(&mut app, 30);
+    let position = player_position(&mut app);
+    assert!(
+        position.xz().distance(Vec2::new(3.0, 0.0)) < 0.05,
+        "at {position}"
+    );
+}
+
+/// a level of one crate off to the side, spawned afresh on each ResetLevel
+fn spawn_level(mut commands: Commands) {
+    commands
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            3.0, 0.5, 0.0,
+        )))
+        .insert(crate_body(0.8, MEDIUM_CRATE_MASS))
+        .insert(Velocity::default())
+        .insert(SceneObject);
+}
+
+#[test]
+fn reset_level_puts_the_player_and_scene_back() {
+    let mut app = mechanics_app();
+    app.add_systems(PostUpdate, spawn_level.run_if(on_event::<ResetLevel>()));
+    app.world_mut().run_system_once(spawn_level);
+    tick(&mut app, 30);
+    let mut crates = app
+        .world_mut()
+        .query_filtered::<&Transform, (With<SceneObject>, With<RigidBody>)>();
+    let resting = crates.single(app.world()).translation;
+    let spawn = player_position(&mut app);
+
+    // walked off, looking down, with the crate knocked away and a checkpoint reached
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    release(&mut app, KeyCode::KeyW);
+    let player = app.world().resource::<PlayerEntity>().0;
+    let checkpoint = app.world_mut().spawn_empty().id();
+    app.world_mut().resource_mut::<CurrentCheckpoint>().0 = Some(ReachedCheckpoint {
+        checkpoint,
+        respawn: Transform::from_xyz(0.0, 3.0, -5.0),
+    });
+    let mut cameras = app.world_mut().query::<&mut PlayerCamera>();
+    cameras.single_mut(app.world_mut()).0 = -0.8;
+    let mut bodies = app
+        .world_mut()
+        .query_filtered::<&mut Velocity, With<SceneObject>>();
+    bodies.single_mut(app.world_mut()).linvel = Vec3::X * 5.0;
+    tick(&mut app, 30);
+    assert!(player_position(&mut app).distance(spawn) > 2.0);
+    assert!(crates.single(app.world()).translation.distance(resting) > 1.0);
+
+    app.world_mut().send_event(ResetLevel);
+    tick(&mut app, 1);
+    assert!(
+        app.world().get_entity(player).is_none(),
+        "player not respawned"
+    );
+    assert_eq!(app.world().resource::<CurrentCheckpoint>().0, None);
+    assert_eq!(cameras.single(app.world()).0, 0.0);
+    let position = player_position(&mut app);
+    assert!(
+        (position - spawn).with_y(0.0).length() < 0.01,
+        "{position} not back at {spawn}"
+    );
+    let player = app.world().resource::<PlayerEntity>().0;
+    assert_eq!(
+        app.world().get::<Velocity>(player).unwrap().linvel,
+        Vec3::ZERO
+    );
+    assert!(
+        crates
+            .single(app.world())
+            .translation
+            .distance(Vec3::new(3.0, 0.5, 0.0))
+            < 0.01
+    );
+
+    // and it settles back where it started
+    tick(&mut app, 180);
+    assert!(player_position(&mut app).distance(spawn) < 0.05);
+    assert!(crates.single(app.world()).translation.distance(resting) < 0.05);
+}
+
+/// a door 2 wide and 2.5 tall standing on the ground at `position`, rising 2.6 to open
+fn door_at(app: &mut App, position: Vec3) -> Entity {
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(position + Vec3::Y * 1.35),
+        ))
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cuboid(1.0, 1.25, 0.1))
+        .insert(SlidingDoor::new(Vec3::Y * 2.6, 1.0))
+        .id()
+}
+
+fn openness(app: &App, door: Entity) -> f32 {
+    app.world().get::<SlidingDoor>(door).unwrap().openness()
+}
+
+#[test]
+fn plates_and_buttons_open_doors_that_turn_back_smoothly() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let start = player_position(&mut app);
+    let door = door_at(&mut app, Vec3::new(5.0, 0.1, 5.0));
+    let plate = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(start),
+        ))
+        .insert(Collider::cuboid(1.0, 1.0, 1.0))
+        .insert(Sensor)
+        .insert(TriggerVolume::default())
+        .insert(PressurePlate::new([door]))
+        .id();
+
+    // standing on the plate opens it, eased in
+    let mut open = Vec::new();
+    for _ in 0..70 {
+        tick(&mut app, 1);
+        open.push(openness(&app, door));
+    }
+    assert!(app
+        .world()
+        .get::<PressurePlate>(plate)
+        .unwrap()
+        .is_pressed());
+    assert_eq!(open[69], 1.0);
+
+    // stepping off closes it, and back on halfway turns it round without a jump
+    let teleport = |app: &mut App, position: Vec3| {
+        app.world_mut()
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation = position;
+    };
+    teleport(&mut app, start + Vec3::X * 3.0);
+    for _ in 0..30 {
+        tick(&mut app, 1);
+        open.push(openness(&app, door));
+    }
+    assert!(open[99] < 0.9 && open[99] > 0.1, "{}", open[99]);
+    teleport(&mut app, start);
+    for _ in 0..30 {
+        tick(&mut app, 1);
+        open.push(openness(&app, door));
+    }
+    assert!(open[129] > open[100], "{open:?}");
+    // at most the speed of the steepest part of the ease, 1.5 per second
+    assert!(open
+        .windows(2)
+        .all(|w| (w[1] - w[0]).abs() <= 1.5 / 60.0 + 1e-4));
+
+    // a button toggles it, pressed while looking at it
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(start + Vec3::new(0.0, 0.0, -1.5)),
+        ))
+        .insert(Collider::cuboid(0.5, 3.0, 0.1))
+        .insert(UseButton::new([door]));
+    press(&mut app, KeyCode::KeyF);
+    tick(&mut app, 70);
+    assert_eq!(openness(&app, door), 0.0);
+    assert!(!app.world().get::<SlidingDoor>(door).unwrap().open);
+}
+
+#[test]
+fn doors_reopen_instead_of_closing_on_a_player() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let door = door_at(&mut app, Vec3::new(5.0, 0.1, 5.0));
+    let activate = |app: &mut App, activation| {
+        app.world_mut().send_event(Activate {
+            target: door,
+            source: door,
+            activation,
+        });
+    };
+    activate(&mut app, Activation::On);
+    tick(&mut app, 70);
+
+    // stand in the doorway and shut it
+    let position = player_position(&mut app) + Vec3::new(5.0, 0.0, 5.0);
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = position;
+    tick(&mut app, 10);
+    activate(&mut app, Activation::Off);
+    let mut fastest: f32 = 0.0;
+    for _ in 0..120 {
+        tick(&mut app, 1);
+        let player = app.world().resource::<PlayerEntity>().0;
+        fastest = fastest.max(app.world().get::<Velocity>(player).unwrap().linvel.length());
+    }
+    assert!(app.world().get::<SlidingDoor>(door).unwrap().open);
+    assert_eq!(openness(&app, door), 1.0);
+    assert!(fastest < 1.0, "shoved at {fastest}");
+    let moved = player_position(&mut app) - position;
+    assert!(moved.length() < 0.1, "moved {moved}");
+}
+
+/// an elevator 3 across at `position`, going between there and `floors`, and a button calling
+/// it to each floor
+fn elevator_at(app: &mut App, position: Vec3, floors: [f32; 2]) -> (Entity, [Entity; 2]) {
+    let elevator = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ))
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cuboid(1.5, 0.1, 1.5))
+        .insert(Elevator::new(2.0, 2.5, floors).with_dwell(0.5))
+        .id();
+    let buttons = [0, 1].map(|floor| {
+        app.world_mut()
+            .spawn(UseButton::new([elevator]))
+            .insert(CallElevator(floor))
+            .id()
+    });
+    (elevator, buttons)
+}
+
+fn call(app: &mut App, elevator: Entity, button: Entity) {
+    app.world_mut().send_event(Activate {
+        target: elevator,
+        source: button,
+        activation: Activation::Toggle,
+    });
+}
+
+#[test]
+fn elevators_ease_between_floors_carrying_players_and_queue_calls() {
+    let mut app = mechanics_app();
+    let start = player_position(&mut app);
+    let under = start * Vec3::new(1.0, 0.0, 1.0) + Vec3::Y * 0.25;
+    let (elevator, buttons) = elevator_at(&mut app, under, [0.25, 4.25]);
+    let height = |app: &App| {
+        app.world()
+            .get::<Transform>(elevator)
+            .unwrap()
+            .translation
+            .y
+    };
+
+    // up, then back down once it's finished going up
+    let mut heights = vec![height(&app)];
+    call(&mut app, elevator, buttons[1]);
+    for _ in 0..2 {
+        tick(&mut app, 1);
+        heights.push(height(&app));
+    }
+    call(&mut app, elevator, buttons[0]);
+    let mut riding = Vec::new();
+    for _ in 0..185 {
+        tick(&mut app, 1);
+        heights.push(height(&app));
+        riding.push(player_position(&mut app).y - height(&app));
+    }
+    assert_eq!(height(&app), 4.25);
+    assert!(app.world().get::<Elevator>(elevator).unwrap().is_busy());
+    let carried = player_position(&mut app) - start;
+    // its top starts a little above the ground
+    assert!((carried.y - 4.25).abs() < 0.1, "carried {carried}");
+
+    for _ in 0..200 {
+        tick(&mut app, 1);
+        riding.push(player_position(&mut app).y - height(&app));
+    }
+    assert_eq!(height(&app), 0.25);
+    // riding along the whole way, turning round at the top included, floating over its top as
+    // over the ground without dropping onto or bouncing off it
+    assert!(
+        riding[30..].iter().all(|y| (y - start.y).abs() < 0.2),
+        "rode at {riding:?}"
+    );
+    assert_eq!(app.world().get::<Elevator>(elevator).unwrap().floor(), 0);
+    assert!(!app.world().get::<Elevator>(elevator).unwrap().is_busy());
+
+    // eased in and out: no more than 2.5 per second faster or slower each tick, give or take
+    // the last tick's rounding onto the floor
+    let speeds: Vec<f32> = heights.windows(2).map(|w| (w[1] - w[0]) * 60.0).collect();
+    assert!(speeds
+        .windows(2)
+        .all(|w| (w[1] - w[0]).abs() <= 2.0 * 2.5 / 60.0));
+    assert!(speeds.iter().all(|&speed| speed <= 2.0 + 1e-3));
+}
+
+#[test]
+fn elevators_wait_above_players_instead_of_squashing_them() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let start = player_position(&mut app);
+    let over = start * Vec3::new(1.0, 0.0, 1.0) + Vec3::Y * 4.25;
+    let (elevator, buttons) = elevator_at(&mut app, over, [4.25, 0.25]);
+    let height = |app: &App| {
+        app.world()
+            .get::<Transform>(elevator)
+            .unwrap()
+            .translation
+            .y
+    };
+
+    call(&mut app, elevator, buttons[1]);
+    tick(&mut app, 300);
+    let settings = PlayerSettings::default();
+    let head = start.y + settings.capsule_length * 0.5 + settings.capsule_radius;
+    assert!(height(&app) - 0.1 >= head, "came down to {}", height(&app));
+    assert!(
+        height(&app) - 0.1 < head + 0.2,
+        "stopped at {}",
+        height(&app)
+    );
+    let moved = player_position(&mut app) - start;
+    assert!(moved.length() < 0.1, "moved {moved}");
+
+    // and carries on once they're out from under it
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = start + Vec3::X * 5.0;
+    tick(&mut app, 200);
+    assert_eq!(height(&app), 0.25);
+}
+
+#[test]
+fn ground_slams_drive_down_throw_light_bodies_and_recover_slowly() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let start = player_position(&mut app);
+    let shockwaves = Arc::new(Mutex::new(Vec::new()));
+    let sink = shockwaves.clone();
+    app.add_systems(Last, move |mut er_shockwave: EventReader<SlamShockwave>| {
+        sink.lock().unwrap().extend(er_shockwave.read().copied());
+    });
+    let light = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            2.5, 0.5, 0.0,
+        )))
+        .insert(crate_body(0.8, LIGHT_CRATE_MASS))
+        .id();
+    let heavy = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            -2.5, 0.7, 0.0,
+        )))
+        .insert(crate_body(1.2, HEAVY_CRATE_MASS))
+        .id();
+    tick(&mut app, 10);
+    let crate_at = |app: &App, entity| app.world().get::<Transform>(entity).unwrap().translation;
+    let (light_start, heavy_start) = (crate_at(&app, light), crate_at(&app, heavy));
+
+    // jump, then slam from the way up
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 15);
+    release(&mut app, KeyCode::Space);
+    press(&mut app, KeyCode::ControlLeft);
+    let mut fastest: f32 = 0.0;
+    let mut slamming = false;
+    for _ in 0..60 {
+        tick(&mut app, 1);
+        let velocity = app.world().get::<Velocity>(player).unwrap().linvel;
+        fastest = fastest.max(-velocity.y);
+        assert!(velocity.xz().length() < 0.5, "drifted at {velocity}");
+        let state = *app.world().get::<PlayerMovementState>(player).unwrap();
+        slamming |= state == PlayerMovementState::GroundSlamming;
+        if !shockwaves.lock().unwrap().is_empty() {
+            break;
+        }
+    }
+    release(&mut app, KeyCode::ControlLeft);
+    assert!(slamming);
+    assert!(fastest > 25.0, "only fell at {fastest}");
+    let landed = shockwaves.lock().unwrap().clone();
+    assert_eq!(landed.len(), 1);
+    assert_eq!(landed[0].player, player);
+    assert!(landed[0].position.distance(start) < 0.2, "{:?}", landed[0]);
+    assert!(matches!(
+        app.world().get::<GroundSlam>(player).unwrap(),
+        GroundSlam::Recovering(_)
+    ));
+
+    // while recovering the player walks slowly, and then at full speed again
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 10);
+    let recovering = app
+        .world()
+        .get::<Velocity>(player)
+        .unwrap()
+        .linvel
+        .xz()
+        .length();
+    assert!(recovering < 3.5, "walked at {recovering} while recovering");
+    tick(&mut app, 30);
+    let recovered = app
+        .world()
+        .get::<Velocity>(player)
+        .unwrap()
+        .linvel
+        .xz()
+        .length();
+    assert!(recovered > 8.0, "walked at {recovered} once recovered");
+    assert_eq!(
+        *app.world().get::<GroundSlam>(player).unwrap(),
+        GroundSlam::Ready
+    );
+
+    // the light crate beside the landing was thrown, the heavy one didn't budge
+    let thrown = crate_at(&app, light) - light_start;
+    assert!(thrown.x > 0.5, "light crate moved {thrown}");
+    let budged = crate_at(&app, heavy) - heavy_start;
+    assert!(budged.length() < 0.05, "heavy crate moved {budged}");
+}
+
+/// a wind zone over the whole ground, blowing towards +z at `strength`
+fn wind(app: &mut App, strength: f32) {
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            0.0, 5.0, 0.0,
+        )))
+        .insert(Collider::cuboid(10.0, 5.0, 10.0))
+        .insert(Sensor)
+        .insert(WindZone::new(Dir3::Z, strength));
+}
+
+#[test]
+fn walking_into_the_wind_is_slower_and_jumps_drift_downwind() {
+    let mut app = mechanics_app();
+    wind(&mut app, 4.0);
+    let player = app.world().resource::<PlayerEntity>().0;
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 60);
+    let walking = app.world().get::<Velocity>(player).unwrap().linvel;
+    assert!(
+        (walking.z + 6.0).abs() < 0.5 && walking.x.abs() < 0.1,
+        "walked into the wind at {walking}"
+    );
+    release(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+
+    // jumping straight up comes down downwind
+    let start = player_position(&mut app);
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 10);
+    release(&mut app, KeyCode::Space);
+    let mut airborne = false;
+    for _ in 0..180 {
+        tick(&mut app, 1);
+        let state = app.world().get::<PlayerMovementState>(player).unwrap();
+        airborne |= !state.is_grounded();
+        if airborne && state.is_grounded() {
+            break;
+        }
+    }
+    assert!(airborne);
+    let drift = player_position(&mut app) - start;
+    assert!(drift.z > 3.0 && drift.x.abs() < 0.1, "drifted {drift}");
+}
+
+#[test]
+fn wind_blows_light_bodies_along_but_not_heavy_ones() {
+    let mut app = mechanics_app();
+    let crate_at = |app: &mut App, x: f32, mass: f32| {
+        app.world_mut()
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                x, 0.5, -5.0,
+            )))
+            .insert(crate_body(0.8, mass))
+            .insert(Velocity::default())
+            .id()
+    };
+    let light = crate_at(&mut app, 3.0, LIGHT_CRATE_MASS);
+    let heavy = crate_at(&mut app, -3.0, HEAVY_CRATE_MASS);
+    tick(&mut app, 30);
+    let position = |app: &App, entity| app.world().get::<Transform>(entity).unwrap().translation;
+    let (light_start, heavy_start) = (position(&app, light), position(&app, heavy));
+
+    wind(&mut app, 8.0);
+    tick(&mut app, 60);
+    let light_moved = position(&app, light) - light_start;
+    let heavy_moved = position(&app, heavy) - heavy_start;
+    assert!(light_moved.z > 0.5, "light crate moved {light_moved}");
+    assert!(
+        heavy_moved.length() < 0.05,
+        "heavy crate moved {heavy_moved}"
+    );
+}
+
+/// a breakable light crate on the ground at `position`, and the PropDestroyed events sent
+fn breakable_crate(app: &mut App, position: Vec3) -> (Entity, Arc<Mutex<Vec<PropDestroyed>>>) {
+    let destroyed = Arc::new(Mutex::new(Vec::new()));
+    let sink = destroyed.clone();
+    app.add_systems(Last, move |mut er_destroyed: EventReader<PropDestroyed>| {
+        sink.lock().unwrap().extend(er_destroyed.read().copied());
+    });
+    let prop = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ))
+        .insert(crate_body(0.8, LIGHT_CRATE_MASS))
+        .insert(Breakable::new(30.0))
+        .id();
+    (prop, destroyed)
+}
+
+fn debris_count(app: &mut App) -> usize {
+    app.world_mut()
+        .query::<&CollisionGroups>()
+        .iter(app.world())
+        .filter(|groups| groups.memberships == DEBRIS_GROUP)
+        .count()
+}
+
+#[test]
+fn breakables_break_into_debris_on_a_hard_hit_only() {
+    let mut app = mechanics_app();
+    let (prop, destroyed) = breakable_crate(&mut app, Vec3::new(3.0, 0.5, -3.0));
+    let drop = |app: &mut App, mass: f32| {
+        app.world_mut()
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                3.0, 4.0, -3.0,
+            )))
+            .insert(RigidBody::Dynamic)
+            .insert(Collider::ball(0.2))
+            .insert(ColliderMassProperties::Mass(mass));
+    };
+
+    // a light ball bounces off
+    drop(&mut app, 0.5);
+    tick(&mut app, 60);
+    assert!(destroyed.lock().unwrap().is_empty());
+    assert!(app.world().get_entity(prop).is_some());
+
+    // a heavy one breaks it
+    drop(&mut app, 20.0);
+    tick(&mut app, 60);
+    let events = destroyed.lock().unwrap().clone();
+    assert_eq!(events.len(), 1, "{events:?}");
+    assert_eq!(events[0].entity, prop);
+    assert!(events[0].impulse >= 30.0);
+    assert!(app.world().get_entity(prop).is_none());
+    // into 8 pieces, gone a few seconds later
+    assert_eq!(debris_count(&mut app), 8);
+    tick(&mut app, 240);
+    assert_eq!(debris_count(&mut app), 0);
+}
+
+#[test]
+fn slamming_onto_a_breakable_breaks_it_without_landing_on_the_debris() {
+    let mut app = mechanics_app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let (prop, destroyed) = breakable_crate(&mut app, Vec3::new(0.0, 0.5, -3.0));
+    // stand on it
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = Vec3::new(0.0, 2.6, -3.0);
+    tick(&mut app, 60);
+    assert!(destroyed.lock().unwrap().is_empty());
+    // on top of the crate rather than beside it
+    let standing = player_position(&mut app);
+    assert!(standing.y > 2.0, "standing at {standing}");
+
+    // landing an ordinary jump on it isn't enough, but a slam is
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 15);
+    release(&mut app, KeyCode::Space);
+    tick(&mut app, 120);
+    assert!(destroyed.lock().unwrap().is_empty());
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 15);
+    release(&mut app, KeyCode::Space);
+    press(&mut app, KeyCode::ControlLeft);
+    tick(&mut app, 60);
+    release(&mut app, KeyCode::ControlLeft);
+    tick(&mut app, 60);
+    assert_eq!(destroyed.lock().unwrap().len(), 1);
+    assert!(app.world().get_entity(prop).is_none());
+    // down past the debris to the ground
+    let landed = player_position(&mut app);
+    let standing = 0.1 + PlayerSettings::default().float_height();
+    assert!((landed.y - standing).abs() < 0.1, "landed at {landed}");
+}
+
+/// jump beside a tall wall to the player's right, running along it towards -z for `length`, and
+/// hold forward and towards it. returns the player's state, position and velocity each tick
+fn wall_run(
+    length: f32,
+    ticks: usize,
+    jump_off_at: Option<usize>,
+) -> Vec<(PlayerMovementState, Vec3, Vec3)> {
+    let mut app = app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            0.9,
+            -5.0,
+            1.0 - length * 0.5,
+        )))
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(0.25, 12.0, length * 0.5));
+    press(&mut app, KeyCode::KeyW);
+    press(&mut app, KeyCode::KeyD);
+    tick(&mut app, 10);
+    press(&mut app, KeyCode::Space);
+
+    let mut frames = Vec::new();
+    for i in 0..ticks {
+        // a fresh press jumps off; holding the first one through doesn't
+        if Some(i) == jump_off_at {
+            release(&mut app, KeyCode::Space);
+            tick(&mut app, 1);
+            press(&mut app, KeyCode::Space);
+        }
+        tick(&mut app, 1);
+        let world = app.world();
+        frames.push((
+            *world.get::<PlayerMovementState>(player).unwrap(),
+            world.get::<Transform>(player).unwrap().translation,
+            world.get::<Velocity>(player).unwrap().linvel,
+        ));
+    }
+    frames
+}
+
+#[test]
+fn wall_runs_last_until_the_wall_ends_or_time_runs_out() {
+    let running = |frames: &[(PlayerMovementState, Vec3, Vec3)]| {
+        frames
+            .iter()
+            .filter(|(state, ..)| *state == PlayerMovementState::WallRunning)
+            .copied()
+            .collect::<Vec<_>>()
+    };
+    let config = WallRunConfig::default();
+
+    // along a long wall, it runs at the wall run speed and barely falls, until time's up
+    let frames = wall_run(60.0, 150, None);
+    let run = running(&frames);
+    let ticks = (config.max_duration * 60.0) as usize;
+    assert!(run.len().abs_diff(ticks) <= 2, "ran {} ticks", run.len());
+    let (_, first, _) = run[0];
+    let (_, last, velocity) = run[run.len() - 1];
+    assert!((velocity.z + config.speed).abs() < 0.1, "{velocity}");
+    let fell = first.y - last.y;
+    // most of a second and a half of free fall, even without tnua's extra fall gravity
+    assert!(
+        fell > 0.0 && fell < 0.3 * 9.81 * 1.5 * 1.5 / 2.0,
+        "fell {fell}"
+    );
+    // and still beside the wall once the cooldown's over, it doesn't start another
+    let started = frames
+        .windows(2)
+        .filter(|w| w[0].0 != w[1].0 && w[1].0 == PlayerMovementState::WallRunning)
+        .count();
+    assert_eq!(started, 1);
+
+    // along a short one it drops off the end
+    let frames = wall_run(6.0, 100, None);
+    let run = running(&frames);
+    assert!(
+        !run.is_empty() && run.len() < ticks / 2,
+        "ran {} ticks",
+        run.len()
+    );
+    let (_, last, _) = run[run.len() - 1];
+    assert!((last.z + 5.0).abs() < 0.5, "left the wall at {last}");
+}
+
+#[test]
+fn wall_jumps_push_off_the_wall() {
+    let frames = wall_run(60.0, 60, Some(40));
+    assert_eq!(frames[39].0, PlayerMovementState::WallRunning);
+    let (state, _, velocity) = frames[45];
+    assert_eq!(state, PlayerMovementState::Airborne);
+    // the wall is to the right, at +x
+    assert!(velocity.x < -3.0, "{velocity}");
+    assert!(velocity.y > 3.0, "{velocity}");
+}
+
+/// how far the player walks forward in `updates` frames at a time scale
+fn walked(scale: f32, updates: usize) -> f32 {
+    let mut app = app();
+    app.insert_resource(TimeScale(scale));
+    tick(&mut app, 1);
+    let start = player_position(&mut app);
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, updates);
+    (player_position(&mut app) - start).xz().length()
+}
+
+#[test]
+fn time_scale_slows_movement_down() {
+    // at half speed it takes twice as many frames to walk as far
+    let full = walked(1.0, 60);
+    let half = walked(0.5, 120);
+    assert!(full > 1.0, "{full}");
+    assert!((full - half).abs() < 0.15, "{full} then {half}");
+    assert!(walked(0.5, 60) < full * 0.6);
+
+    assert_eq!(TimeScale(100.0).clamped(), TimeScale::MAX);
+    assert_eq!(TimeScale(0.0).clamped(), TimeScale::MIN);
+}
+
+#[test]
+fn slow_motion_still_steps_physics_every_frame() {
+    let mut app = app();
+    app.insert_resource(TimeScale(0.3));
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+
+    // each 60 Hz frame moves the player a little, rather than every third or fourth moving
+    // them a full tick's worth
+    let mut last = player_position(&mut app);
+    for _ in 0..30 {
+        tick(&mut app, 1);
+        let position = player_position(&mut app);
+        let step = last.distance(position);
+        assert!(step > 0.01 && step < 0.1, "stepped {step}");
+        last = position;
+    }
+}
+
+/// the keyboard and mouse player's look for one tick at a time scale, with the spare gamepad's
+/// right stick held fully right
+fn stick_look(scale: f32) -> f32 {
+    let mut app = app();
+    app.insert_resource(TimeScale(scale));
+    let gamepad = Gamepad::new(0);
+    app.world_mut().send_event(GamepadConnectionEvent::new(
+        gamepad,
+        GamepadConnection::Connected(GamepadInfo { name: "pad".into() }),
+    ));
+    tick(&mut app, 1);
+    app.world_mut()
+        .resource_mut::<Axis<GamepadAxis>>()
+        .set(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX), 1.0);
+    tick(&mut app, 10);
+
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world().get::<InputSnapshot>(player).unwrap().look.x
+}
+
+#[test]
+fn time_scale_leaves_stick_look_in_real_time() {
+    // one tick is a sixtieth of a real second at any scale, so it turns as far
+    let full = stick_look(1.0);
+    let slow = stick_look(0.25);
+    assert!(full > 0.01, "{full}");
+    assert!((full - slow).abs() < 1e-4, "{full} then {slow}");
+}
+
+/// walk forward for a second, then jump: the distance walked and the jump's peak height, with
+/// the app updating `hz` times a second around the fixed 60 Hz physics tick
+fn walk_and_jump_at(hz: f64) -> (f32, f32) {
+    let mut app = headless_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / hz,
+    )))
+    .add_plugins(PlayerControllerPlugin::default())
+    .add_systems(Startup, spawn_ground);
+    let updates = |seconds: f64| (seconds * hz).round() as usize;
+    tick(&mut app, updates(3.0));
+
+    let start = player_position(&mut app);
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, updates(1.0));
+    release(&mut app, KeyCode::KeyW);
+    tick(&mut app, updates(1.0));
+    let walked = (player_position(&mut app) - start).xz().length();
+
+    let ground = player_position(&mut app).y;
+    let mut peak = ground;
+    press(&mut app, KeyCode::Space);
+    for _ in 0..updates(1.5) {
+        tick(&mut app, 1);
+        peak = peak.max(player_position(&mut app).y);
+    }
+    (walked, peak - ground)
+}
+
+#[test]
+fn moves_and_jumps_the_same_at_any_frame_rate() {
+    let (walked, height) = walk_and_jump_at(60.0);
+    assert!(walked > 5.0, "walked {walked}");
+    assert!(height > 3.0, "jumped {height}");
+
+    // slower frames run several physics ticks each and faster ones often none, on the same input
+    for hz in [30.0, 144.0] {
+        let (other_walked, other_height) = walk_and_jump_at(hz);
+        assert!(
+            (other_walked - walked).abs() < 0.01,
+            "walked {other_walked} at {hz} Hz against {walked}"
+        );
+        assert!(
+            (other_height - height).abs() < 0.01,
+            "jumped {other_height} at {hz} Hz against {height}"
+        );
+    }
+}
+
+fn air_time(app: &mut App) -> f32 {
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world().get::<AirTime>(player).unwrap().0
+}
+
+#[test]
+fn player_settings_apply_at_runtime() {
+    let mut app = app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut()
+        .get_mut::<PlayerSettings>(player)
+        .unwrap()
+        .speed = 20.0;
+
+    // long enough to reach full speed, short enough to stay on the ground
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    let speed = app
+        .world()
+        .get::<Velocity>(player)
+        .unwrap()
+        .linvel
+        .xz()
+        .length();
+    assert!((speed - 20.0).abs() < 0.5, "moving at {speed}");
+}
+
+#[test]
+fn spawn_player_spawns_independent_characters() {
+    let mut app = app();
+    let spawned = app.world_mut().run_system_once(
+        |mut commands: Commands,
+         mut meshes: ResMut<Assets<Mesh>>,
+         mut materials: ResMut<Assets<StandardMaterial>>| {
+            [-4.0, 4.0].map(|x| {
+                spawn_player(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    PlayerSpawnOptions {
+                        transform: Transform::from_xyz(x, 2.0, 4.0),
+                        input: PlayerInput::None,
+                        camera: (x > 0.0).then(|| PlayerCameraOptions {
+                            slot: 1,
+                            ..default()
+                        }),
+                        ..default()
+                    },
+                )
+            })
+        },
+    );
+    let [SpawnedPlayer {
+        player: left,
+        camera: None,
+    }, SpawnedPlayer {
+        player: right,
+        camera: Some(camera),
+    }] = spawned
+    else {
+        panic!("expected a camera on the right player only");
+    };
+    tick(&mut app, 120);
+
+    // both settle on the ground where they were put, and the keyboard player keeps its own
+    // PlayerEntity
+    for (player, x) in [(left, -4.0), (right, 4.0)] {
+        let position = app.world().get::<Transform>(player).unwrap().translation;
+        assert!(position.xz().distance(Vec2::new(x, 4.0)) < 0.01);
+        assert!(position.y < 2.0);
+    }
+    assert_ne!(app.world().resource::<PlayerEntity>().0, left);
+    assert_eq!(app.world().get::<Parent>(camera).unwrap().get(), right);
+    assert!(app.world().get::<Children>(left).is_none());
+}
+
+#[test]
+fn spawns_facing_configured_yaw_and_pitch() {
+    let app = app_with_settings(PlayerSettings {
+        spawn_yaw: 90.0,
+        spawn_pitch: -20.0,
+        ..default()
+    });
+    let player = app.world().resource::<PlayerEntity>().0;
+    let camera = app
+        .world()
+        .get::<Children>(player)
+        .unwrap()
+        .iter()
+        .copied()
+        .find(|&child| app.world().get::<Camera>(child).is_some())
+        .unwrap();
+
+    // turned left a quarter turn, from -z to -x
+    let forward = app.world().get::<Transform>(player).unwrap().forward();
+    assert!(forward.distance(Vec3::NEG_X) < 1e-4);
+
+    let look = app
+        .world()
+        .get::<GlobalTransform>(camera)
+        .unwrap()
+        .forward();
+    let expected = Quat::from_rotation_y(90f32.to_radians())
+        * Quat::from_rotation_x(-20f32.to_radians())
+        * Vec3::NEG_Z;
+    assert!(look.distance(expected) < 1e-4);
+}
+
+#[test]
+fn moves_without_a_player_camera() {
+    let mut app = app_with(
+        PlayerSettings::default(),
+        PlayerControllerPlugin {
+            player_cameras: false,
+            ..default()
+        },
+    );
+    let player = app.world().resource::<PlayerEntity>().0;
+    assert!(app.world().get::<Children>(player).is_none());
+
+    // floats as high as a player with a camera
+    let start = player_position(&mut app);
+    let with_camera = player_position(&mut self::app());
+    assert!((start.y - with_camera.y).abs() < 0.01);
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    assert!(player_position(&mut app).z < start.z - 1.0);
+}
+
+#[test]
+fn turns_with_a_rotating_platform() {
+    let mut app = app();
+    let under = player_position(&mut app) * Vec3::new(1.0, 0.0, 1.0);
+    let disc = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(under + Vec3::Y * 0.15),
+        ))
+        .insert(RigidBody::KinematicVelocityBased)
+        .insert(Velocity::angular(Vec3::Y))
+        .insert(Collider::cylinder(0.1, 2.0))
+        .id();
+    let yaw = |app: &mut App| {
+        let player = app.world().resource::<PlayerEntity>().0;
+        let rotation = app.world().get::<Transform>(player).unwrap().rotation;
+        rotation.to_euler(EulerRot::YXZ).0
+    };
+
+    // a second at one radian per second
+    tick(&mut app, 60);
+    let turned = yaw(&mut app);
+    assert!((turned - 1.0).abs() < 0.1, "turned {turned}");
+
+    // back on fixed ground the turning stops straight away
+    app.world_mut().despawn(disc);
+    tick(&mut app, 60);
+    assert!((yaw(&mut app) - turned).abs() < 0.01);
+}
+
+#[test]
+fn carried_round_a_rotating_platform_from_near_its_rim() {
+    let mut app = mechanics_app();
+    let start = player_position(&mut app);
+    let centre = start * Vec3::new(1.0, 0.0, 1.0) + Vec3::new(2.0, 0.15, 0.0);
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(centre),
+        ))
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cylinder(0.1, 2.5))
+        .insert(RotatingPlatform::new(Dir3::Y, 1.0));
+    let player = app.world().resource::<PlayerEntity>().0;
+    let yaw = |app: &mut App| {
+        let rotation = app.world().get::<Transform>(player).unwrap().rotation;
+        rotation.to_euler(EulerRot::YXZ).0
+    };
+    // once it's up to speed
+    tick(&mut app, 20);
+    let (start, start_yaw) = (player_position(&mut app), yaw(&mut app));
+
+    // a second at one radian per second, two units out from the middle
+    tick(&mut app, 60);
+    let expected = centre + Quat::from_rotation_y(1.0) * (start - centre);
+    let position = player_position(&mut app);
+    assert!(
+        position.xz().distance(expected.xz()) < 0.1,
+        "at {position}, expected {expected}"
+    );
+    let turned = yaw(&mut app) - start_yaw;
+    assert!((turned - 1.0).abs() < 0.1, "turned {turned}");
+}
+
+#[test]
+fn rides_a_moving_platform_there_and_back() {
+    let mut app = mechanics_app();
+    let start = player_position(&mut app);
+    let under = start * Vec3::new(1.0, 0.0, 1.0) + Vec3::Y * 0.25;
+    let platform = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(under),
+        ))
+        .insert(RigidBody::KinematicPositionBased)
+        .insert(Velocity::default())
+        .insert(Collider::cuboid(1.5, 0.1, 1.5))
+        .insert(MovingPlatform::new(
+            PlatformPath::PingPong,
+            2.0,
+            [under, under + Vec3::X * 4.0].map(|point| Waypoint::new(point).with_dwell(0.5)),
+        ))
+        .id();
+    let platform_x = |app: &mut App| {
+        app.world()
+            .get::<Transform>(platform)
+            .unwrap()
+            .translation
+            .x
+    };
+
+    // half a second waiting at the start, then two seconds across
+    tick(&mut app, 30);
+    assert!((platform_x(&mut app) - under.x).abs() < 0.01);
+    tick(&mut app, 130);
+    assert!((platform_x(&mut app) - under.x - 4.0).abs() < 0.01);
+    let carried = player_position(&mut app) - start;
+    assert!((carried.x - 4.0).abs() < 0.2, "carried {carried}");
+
+    // and after waiting at the far end, back again
+    tick(&mut app, 160);
+    assert!((platform_x(&mut app) - under.x).abs() < 0.01);
+    let carried = player_position(&mut app) - start;
+    assert!(carried.x.abs() < 0.3, "carried {carried}");
+}
+
+#[test]
+fn settles_at_float_height() {
+    // the ground's top is at 0.1
+    for (capsule_length, ground_clearance) in [(1.0, 0.1), (1.0, 1.0), (2.0, 0.1)] {
+        let settings = PlayerSettings {
+            capsule_length,
+            ground_clearance,
+            ..default()
+        };
+        let float_height = settings.float_height();
+        let mut app = app_with_settings(settings);
+        let height = player_position(&mut app).y - 0.1;
+        assert!(
+            (height - float_height).abs() < 0.05,
+            "floated at {height}, not {float_height}"
+        );
+        // with the bottom of the capsule just clear of the ground
+        let feet = height - capsule_length * 0.5 - 0.5;
+        assert!(
+            (feet - ground_clearance).abs() < 0.05,
+            "feet {feet} off the ground"
+        );
+    }
+}
+
+#[test]
+fn crouches_under_a_low_slab_and_stands_once_clear() {
+    let mut app = app();
+    // the demo's crouch slab: its bottom 1.8 over the ground, lower than the 2 tall capsule
+    app.world_mut().spawn((
+        TransformBundle::from_transform(Transform::from_xyz(0.0, 2.15, -4.0)),
+        RigidBody::Fixed,
+        Collider::cuboid(1.5, 0.25, 1.5),
+    ));
+    let standing = player_position(&mut app).y;
+
+    // walking upright bumps into it
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 60);
+    let blocked = player_position(&mut app);
+    assert!(blocked.z > -3.0, "walked to {blocked}");
+
+    // crouched, the player fits under it, and stays crouched there with the button released
+    press(&mut app, KeyCode::ControlLeft);
+    tick(&mut app, 20);
+    release(&mut app, KeyCode::KeyW);
+    tick(&mut app, 20);
+    release(&mut app, KeyCode::ControlLeft);
+    tick(&mut app, 30);
+    let under = player_position(&mut app);
+    assert!(under.z < -3.5, "crawled to {under}");
+    assert!(
+        under.y < standing - 0.3,
+        "stood up at {under} under the slab"
+    );
+
+    // and stands back up on walking out the far side
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    release(&mut app, KeyCode::KeyW);
+    tick(&mut app, 60);
+    let out = player_position(&mut app);
+    assert!(out.z < -6.0, "walked out to {out}");
+    assert!((out.y - standing).abs() < 0.05, "stood at {out}");
+}
+
+/// run forward with a sprint and a jump on the keyboard, recording the player's InputSnapshot a
+/// frame at a time
+fn record_run(app: &mut App) -> Vec<InputSnapshot> {
+    let player = app.world().resource::<PlayerEntity>().0;
+    let mut recording = Vec::new();
+    for frame in 0..150 {
+        match frame {
+            0 => press(app, KeyCode::KeyW),
+            30 => press(app, KeyCode::ShiftLeft),
+            60 => press(app, KeyCode::Space),
+            70 => release(app, KeyCode::Space),
+            100 => release(app, KeyCode::KeyW),
+            _ => {}
+        }
+        tick(app, 1);
+        recording.push(*app.world().get::<InputSnapshot>(player).unwrap());
+    }
+    release(app, KeyCode::ShiftLeft);
+    recording
+}
+
+#[test]
+fn input_snapshots_replay_a_run() {
+    let mut app = app();
+    let start = player_position(&mut app);
+    let puppet = app.world_mut().run_system_once(
+        |mut commands: Commands,
+         mut meshes: ResMut<Assets<Mesh>>,
+         mut materials: ResMut<Assets<StandardMaterial>>| {
+            spawn_player(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                PlayerSpawnOptions {
+                    transform: Transform::from_xyz(6.0, 2.0, 0.0),
+                    input: PlayerInput::None,
+                    camera: None,
+                    ..default()
+                },
+            )
+            .player
+        },
+    );
+    tick(&mut app, 120);
+
+    let recording = record_run(&mut app);
+    let run = player_position(&mut app) - start;
+    assert!(run.length() > 5.0, "only ran {run}");
+    assert!(recording.iter().any(|snapshot| snapshot.jump));
+
+    // replayed on a player the keyboard doesn't drive, it does the same
+    let puppet_start = app.world().get::<Transform>(puppet).unwrap().translation;
+    for snapshot in recording {
+        *app.world_mut().get_mut::<InputSnapshot>(puppet).unwrap() = snapshot;
+        tick(&mut app, 1);
+    }
+    let replayed = app.world().get::<Transform>(puppet).unwrap().translation - puppet_start;
+    assert!(
+        replayed.distance(run) < 0.01,
+        "replayed {replayed}, ran {run}"
+    );
+}
+
+#[test]
+fn replaying_snapshots_after_a_reset_retraces_the_run() {
+    let mut app = app();
+    // both runs start from a fresh player settled at the spawn point
+    app.world_mut().send_event(ResetLevel);
+    tick(&mut app, 180);
+    let recording = record_run(&mut app);
+    let player = app.world().resource::<PlayerEntity>().0;
+    let ran = *app.world().get::<Transform>(player).unwrap();
+
+    app.world_mut().send_event(ResetLevel);
+    tick(&mut app, 180);
+    let player = app.world().resource::<PlayerEntity>().0;
+    // off the keyboard, so the replayed snapshots are all that move them
+    app.world_mut()
+        .entity_mut(player)
+        .remove::<KeyboardMouseInput>();
+    for snapshot in recording {
+        *app.world_mut().get_mut::<InputSnapshot>(player).unwrap() = snapshot;
+        tick(&mut app, 1);
+    }
+    let replayed = *app.world().get::<Transform>(player).unwrap();
+    assert!(
+        replayed.translation.distance(ran.translation) < 1e-4,
+        "replayed to {}, ran to {}",
+        replayed.translation,
+        ran.translation
+    );
+    assert!(replayed.rotation.angle_between(ran.rotation) < 1e-4);
+}
+
+#[test]
+fn only_the_keyboard_player_reads_the_keyboard() {
+    let mut app = app();
+    let [idle, driven] = app.world_mut().run_system_once(
+        |mut commands: Commands,
+         mut meshes: ResMut<Assets<Mesh>>,
+         mut materials: ResMut<Assets<StandardMaterial>>| {
+            [-4.0, 4.0].map(|x| {
+                spawn_player(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    PlayerSpawnOptions {
+                        transform: Transform::from_xyz(x, 2.0, 4.0),
+                        input: PlayerInput::None,
+                        camera: None,
+                        ..default()
+                    },
+                )
+                .player
+            })
+        },
+    );
+    app.world_mut().entity_mut(driven).insert(MovementIntent {
+        direction: Vec3::X,
+        ..default()
+    });
+    tick(&mut app, 60);
+    let position =
+        |app: &mut App, player| app.world().get::<Transform>(player).unwrap().translation;
+    let starts = [position(&mut app, idle), position(&mut app, driven)];
+    let start = player_position(&mut app);
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    assert!(player_position(&mut app).z < start.z - 1.0);
+    assert!(position(&mut app, idle).distance(starts[0]) < 0.01);
+    let moved = position(&mut app, driven) - starts[1];
+    assert!(moved.x > 1.0 && moved.z.abs() < 0.01, "moved {moved}");
+}
+
+#[test]
+fn despawns_and_respawns_the_player() {
+    let mut app = app();
+    app.world_mut().resource_mut::<RespawnConfig>().delay = 0.5;
+    let player = app.world().resource::<PlayerEntity>().0;
+
+    // moving while despawned or about to be shouldn't panic anything
+    press(&mut app, KeyCode::KeyW);
+    app.world_mut().send_event(DespawnPlayer { player });
+    tick(&mut app, 10);
+    assert!(app.world().get_entity(player).is_none());
+    assert!(app.world().get_resource::<PlayerEntity>().is_none());
+    // a camera stays behind in the meantime
+    let mut cameras = app.world_mut().query::<&Camera>();
+    assert_eq!(cameras.iter(app.world()).count(), 1);
+    release(&mut app, KeyCode::KeyW);
+
+    tick(&mut app, 30);
+    let respawned = app.world().resource::<PlayerEntity>().0;
+    assert_ne!(respawned, player);
+    // back at the spawn point, falling in from the spawn height, with only its own camera
+    tick(&mut app, 180);
+    let position = player_position(&mut app);
+    assert!(position.xz().length() < 0.01, "respawned at {position}");
+    let mut cameras = app.world_mut().query::<&Parent>();
+    let camera_parents = app
+        .world_mut()
+        .query_filtered::<Entity, With<Camera>>()
+        .iter(app.world())
+        .map(|camera| cameras.get(app.world(), camera).map(|parent| parent.get()))
+        .collect::<Vec<_>>();
+    assert!(camera_parents.contains(&Ok(respawned)));
+    assert!(camera_parents.iter().all(Result::is_ok));
+}
+
+/// every MovementStateChanged sent, in order
+#[derive(Resource, Default)]
+struct Transitions(Vec<(PlayerMovementState, PlayerMovementState)>);
+
+fn record_transitions(app: &mut App) {
+    app.init_resource::<Transitions>().add_systems(
+        Last,
+        |mut er_changed: EventReader<MovementStateChanged>,
+         mut transitions: ResMut<Transitions>| {
+            transitions
+                .0
+                .extend(er_changed.read().map(|ev| (ev.from, ev.to)));
+        },
+    );
+}
+
+#[test]
+fn movement_state_changes_once_per_transition() {
+    use PlayerMovementState::*;
+    let mut app = app();
+    record_transitions(&mut app);
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 20);
+    release(&mut app, KeyCode::KeyW);
+    tick(&mut app, 40);
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 5);
+    release(&mut app, KeyCode::Space);
+    // the float spring settling after landing doesn't flicker back to airborne
+    tick(&mut app, 120);
+
+    assert_eq!(
+        app.world().resource::<Transitions>().0,
+        [
+            (Idle, Walking),
+            (Walking, Idle),
+            (Idle, Airborne),
+            (Airborne, Idle)
+        ]
+    );
+}
+
+/// the jumps taken holding jump for 150 ticks, long enough to land twice, then letting go
+fn jumps_holding(requires_release: bool) -> usize {
+    let mut app = app();
+    record_transitions(&mut app);
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut()
+        .get_mut::<Abilities>(player)
+        .unwrap()
+        .add(JumpAbility::default().with_requires_release(requires_release));
+
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 150);
+    release(&mut app, KeyCode::Space);
+    tick(&mut app, 120);
+    app.world()
+        .resource::<Transitions>()
+        .0
+        .iter()
+        .filter(|(_, to)| *to == PlayerMovementState::Airborne)
+        .count()
+}
+
+#[test]
+fn holding_jump_only_jumps_again_without_requiring_release() {
+    // the press jumps once, however long it's held
+    assert_eq!(jumps_holding(true), 1);
+    // held, it jumps again on each of the two landings, then stops once let go
+    assert_eq!(jumps_holding(false), 3);
+}
+
+/// how far a box of the given mass, just in front of the player, moves as they walk into it
+fn push_box(mass: f32) -> f32 {
+    let mut app = app();
+    let start = Vec3::new(0.0, 0.5, -1.5);
+    let crate_box = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(start),
+        ))
+        .insert(RigidBody::Dynamic)
+        .insert(Collider::cuboid(0.4, 0.4, 0.4))
+        .insert(ColliderMassProperties::Mass(mass))
+        .id();
+    tick(&mut app, 10);
+
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 60);
+    let end = app.world().get::<Transform>(crate_box).unwrap().translation;
+    start.xz().distance(end.xz())
+}
+
+#[test]
+fn pushes_light_bodies_but_not_heavy_ones() {
+    let light = push_box(5.0);
+    assert!(light > 2.0, "light box moved {light}");
+
+    let heavy = push_box(500.0);
+    assert!(heavy < 0.2, "heavy box moved {heavy}");
+}
+
+/// how far a crate of the given mass moves, and the highest the player floats, as they walk
+/// into it for a second. the crate is shorter than the capsule's rounded bottom reaches
+fn push_crate(mass: f32) -> (f32, f32) {
+    let mut app = app();
+    let start = Vec3::new(0.0, 0.5, -1.5);
+    let crate_box = app
+        .world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(start),
+        ))
+        .insert(crate_body(0.8, mass))
+        .id();
+    tick(&mut app, 10);
+
+    press(&mut app, KeyCode::KeyW);
+    let mut highest = player_position(&mut app).y;
+    for _ in 0..60 {
+        tick(&mut app, 1);
+        highest = highest.max(player_position(&mut app).y);
+    }
+    let end = app.world().get::<Transform>(crate_box).unwrap().translation;
+    (start.xz().distance(end.xz()), highest)
+}
+
+#[test]
+fn crates_push_slower_the_heavier_they_are_without_being_climbed() {
+    let standing = player_position(&mut app()).y;
+    let (light, light_height) = push_crate(LIGHT_CRATE_MASS);
+    let (medium, medium_height) = push_crate(MEDIUM_CRATE_MASS);
+    let (heavy, heavy_height) = push_crate(HEAVY_CRATE_MASS);
+    assert!(light > medium * 1.5, "light moved {light}, medium {medium}");
+    assert!(medium > 1.5, "medium moved {medium}");
+    assert!(heavy < 0.1, "heavy moved {heavy}");
+
+    // the float sensor never lifts the player onto the crate they're pushing
+    for height in [light_height, medium_height, heavy_height] {
+        assert!(height < standing + 0.15, "rose to {height} from {standing}");
+    }
+}
+
+#[test]
+fn abilities_can_be_removed_and_added_at_runtime() {
+    let mut app = app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let start = player_position(&mut app);
+    assert!(app
+        .world_mut()
+        .get_mut::<Abilities>(player)
+        .unwrap()
+        .remove("jump"));
+
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 20);
+    release(&mut app, KeyCode::Space);
+    let height = player_position(&mut app).y - start.y;
+    assert!(height < 0.05, "rose {height} without a jump");
+
+    app.world_mut()
+        .get_mut::<Abilities>(player)
+        .unwrap()
+        .add(JumpAbility::default());
+    tick(&mut app, 1);
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 20);
+    let height = player_position(&mut app).y - start.y;
+    assert!(height > 1.0, "rose {height}");
+}
+
+/// counts its uses, which a second of cooldown spaces out
+struct Counted(Arc<AtomicU32>);
+
+impl Ability for Counted {
+    fn name(&self) -> &'static str {
+        "counted"
+    }
+
+    fn excludes(&self) -> &'static [&'static str] {
+        &["crouch"]
+    }
+
+    fn update(&mut self, ctx: &mut AbilityContext) -> bool {
+        if ctx.intent.jump && ctx.ready() {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            ctx.cooldown = 1.0;
+        }
+        ctx.intent.jump
+    }
+}
+
+fn counted_uses(keys: &[KeyCode]) -> u32 {
+    let mut app = app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    let uses = Arc::new(AtomicU32::new(0));
+    app.world_mut()
+        .get_mut::<Abilities>(player)
+        .unwrap()
+        .add(Counted(uses.clone()));
+
+    for &key in keys {
+        press(&mut app, key);
+    }
+    // the second use comes a second in
+    tick(&mut app, 90);
+    uses.load(Ordering::Relaxed)
+}
+
+#[test]
+fn abilities_have_cooldowns_and_exclusions() {
+    assert_eq!(counted_uses(&[KeyCode::Space]), 2);
+    // crouching blocks it
+    assert_eq!(counted_uses(&[KeyCode::Space, KeyCode::ControlLeft]), 0);
+}
+
+#[test]
+fn bonking_a_ceiling_cuts_the_jump_short() {
+    let mut app = app();
+    let start = player_position(&mut app);
+    // the head tops out a metre above where it stands
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(start + Vec3::Y * 2.2),
+        ))
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(3.0, 0.1, 3.0));
+    let bonks = Arc::new(AtomicU32::new(0));
+    let counter = bonks.clone();
+    app.add_systems(Last, move |mut er_bonk: EventReader<CeilingBonk>| {
+        counter.fetch_add(er_bonk.read().count() as u32, Ordering::Relaxed);
+    });
+    tick(&mut app, 1);
+
+    // holding jump doesn't keep the player pressed against it
+    press(&mut app, KeyCode::Space);
+    let player = app.world().resource::<PlayerEntity>().0;
+    let mut landed = None;
+    for i in 0..60 {
+        tick(&mut app, 1);
+        let state = app.world().get::<PlayerMovementState>(player).unwrap();
+        if i > 5 && state.is_grounded() {
+            landed = Some(i);
+            break;
+        }
+    }
+    assert!(
+        landed.is_some_and(|i| i < 20),
+        "landed after {landed:?} ticks"
+    );
+    assert_eq!(bonks.load(Ordering::Relaxed), 1);
+}
+
+/// where the player ends up along z after being flung at a thin wall 2.6 units ahead
+fn fling_at_thin_wall(ccd: bool) -> f32 {
+    let mut app = app_with_settings(PlayerSettings { ccd, ..default() });
+    let start = player_position(&mut app);
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(
+            Transform::from_translation(start - Vec3::Z * 2.6),
+        ))
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(2.0, 2.0, 0.025));
+    tick(&mut app, 1);
+
+    // faster per tick than the capsule is wide
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut().get_mut::<Velocity>(player).unwrap().linvel = Vec3::NEG_Z * 100.0;
+    tick(&mut app, 30);
+    player_position(&mut app).z - start.z
+}
+
+#[test]
+fn ccd_stops_tunneling_through_thin_walls() {
+    let without = fling_at_thin_wall(false);
+    assert!(without < -2.6, "stopped at {without} without ccd");
+
+    let with = fling_at_thin_wall(true);
+    assert!(with > -2.6, "passed through to {with} with ccd");
+}
+
+/// how far the player leans off vertical, in degrees, `ticks` after being knocked sideways
+fn tilt_after_knock(upright: Upright, ticks: usize) -> f32 {
+    let mut app = app_with_settings(PlayerSettings {
+        upright,
+        ..default()
+    });
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut().get_mut::<Velocity>(player).unwrap().angvel = Vec3::X * 4.0;
+    tick(&mut app, ticks);
+    let up = app.world().get::<Transform>(player).unwrap().up();
+    up.angle_between(Vec3::Y).to_degrees()
+}
+
+#[test]
+fn springy_upright_tips_and_recovers() {
+    let spring = Upright::Spring {
+        stiffness: 60.0,
+        damping: 12.0,
+    };
+    let tipped = tilt_after_knock(spring, 8);
+    assert!(tipped > 5.0, "only tipped {tipped}°");
+    let recovered = tilt_after_knock(spring, 120);
+    assert!(recovered < 1.0, "still leaning {recovered}°");
+
+    // a locked capsule can't tip at all
+    let locked = tilt_after_knock(Upright::Locked, 8);
+    assert!(locked < 0.01, "locked capsule leaned {locked}°");
+}
+
+#[test]
+fn stands_and_walks_along_a_tilted_up() {
+    let mut app = app();
+    let up = WorldUp::tilted();
+    app.insert_resource(up);
+    // tilt the ground with gravity, so it's still flat underfoot
+    let mut ground = app
+        .world_mut()
+        .query_filtered::<&mut Transform, (With<RigidBody>, Without<PlayerSettings>)>();
+    ground.single_mut(app.world_mut()).rotation = up.frame();
+    tick(&mut app, 180);
+
+    let player = app.world().resource::<PlayerEntity>().0;
+    let body_up = app.world().get::<Transform>(player).unwrap().up();
+    let leaning = body_up.angle_between(*up.up).to_degrees();
+    assert!(leaning < 1.0, "leaning {leaning}° off up");
+    // tnua keeps its ground sensor's disc square to world y, so the tilted disc's rim meets the
+    // ground a little early and the player floats slightly high
+    let height = up.vertical(player_position(&mut app)) - 0.1;
+    let float_height = PlayerSettings::default().float_height();
+    assert!((height - float_height).abs() < 0.2, "floated at {height}");
+
+    let start = player_position(&mut app);
+    press(&mut app, KeyCode::KeyW);
+    tick(&mut app, 30);
+    let moved = player_position(&mut app) - start;
+    assert!(up.horizontal(moved).length() > 1.0, "moved {moved}");
+    assert!(
+        up.vertical(moved).abs() < 0.05,
+        "moved {moved} off the ground"
+    );
+}
+
+/// how far across up a standing jump from a 30° ramp has carried the player half a second in
+fn jump_from_ramp(direction: JumpDirection) -> Vec3 {
+    let mut app = app();
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut()
+        .get_mut::<Abilities>(player)
+        .unwrap()
+        .add(JumpAbility::default().with_direction(direction));
+    let mut ground = app
+        .world_mut()
+        .query_filtered::<&mut Transform, (With<RigidBody>, Without<PlayerSettings>)>();
+    ground.single_mut(app.world_mut()).rotation = Quat::from_rotation_x(30f32.to_radians());
+    tick(&mut app, 120);
+
+    let start = player_position(&mut app);
+    press(&mut app, KeyCode::Space);
+    tick(&mut app, 30);
+    let moved = player_position(&mut app) - start;
+    Vec3::new(moved.x, 0.0, moved.z)
+}
+
+#[test]
+fn ramp_jumps_go_straight_up_or_out_along_the_normal() {
+    let straight = jump_from_ramp(JumpDirection::WorldUp);
+    assert!(straight.length() < 0.1, "drifted {straight}");
+    // the ramp rises towards -z, so its normal leans towards +z
+    let leaning = jump_from_ramp(JumpDirection::SurfaceNormal);
+    assert!(
+        leaning.z > 0.2 && leaning.x.abs() < 0.05,
+        "leaned out {leaning}"
+    );
+}
+
+/// whether the player goes airborne walking off the far edge of a block `height` tall
+fn falls_off_ledge(height: f32) -> bool {
+    let mut app = app();
+    // the ground's top is at 0.1; the block covers where the player stands and ends 1.5 ahead
+    app.world_mut()
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            0.0,
+            0.1 + height * 0.5,
+            0.5,
+        )))
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(2.0, height * 0.5, 2.0));
+    let player = app.world().resource::<PlayerEntity>().0;
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation += Vec3::Y * height;
+    tick(&mut app, 60);
+    assert!(app
+        .world()
+        .get::<PlayerMovementState>(player)
+        .unwrap()
+        .is_grounded());
+
+    press(&mut app, KeyCode::KeyW);
+    (0..60).any(|_| {
+        tick(&mut app, 1);
+        !app.world()
+            .get::<PlayerMovementState>(player)
+            .unwrap()
+            .is_grounded()
+    })
+}
+
+#[test]
+fn snaps_down_small_steps_but_falls_off_ledges() {
+    // the tallest of the obstacle course's stairs
+    assert!(!falls_off_ledge(0.45), "fell down a stair");
+    assert!(falls_off_ledge(1.0), "snapped down a ledge");
+}
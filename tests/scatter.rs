@@ -0,0 +1,53 @@
+//! obstacle scattering: determinism, and keeping clear of the spawn point
+
+use bevy::prelude::*;
+use bevy_character_starter::{scatter_layout, Obstacle, ScatterConfig, ScatterRng};
+
+fn config() -> ScatterConfig {
+    ScatterConfig {
+        count: 40,
+        ..default()
+    }
+}
+
+fn layout(seed: u64) -> Vec<Obstacle> {
+    scatter_layout(
+        &config(),
+        &mut ScatterRng::new(seed),
+        Vec2::splat(20.0),
+        Vec2::ZERO,
+    )
+}
+
+#[test]
+fn same_seed_same_layout() {
+    assert_eq!(layout(7), layout(7));
+    assert_ne!(layout(7), layout(8));
+
+    // and again from the same rng, as on a level reset
+    let mut rng = ScatterRng::new(7);
+    let first = scatter_layout(&config(), &mut rng, Vec2::splat(20.0), Vec2::ZERO);
+    let again = scatter_layout(&config(), &mut rng, Vec2::splat(20.0), Vec2::ZERO);
+    assert_eq!(first, again);
+}
+
+#[test]
+fn obstacles_keep_clear_of_the_spawn_point_and_on_the_ground() {
+    let clearance = config().spawn_clearance;
+    for seed in 0..20 {
+        let obstacles = layout(seed);
+        assert!(obstacles.len() > 30, "only placed {}", obstacles.len());
+        for obstacle in obstacles {
+            let position = obstacle.transform.translation.xz();
+            let footprint = obstacle.footprint();
+            assert!(
+                position.length() >= clearance + footprint,
+                "{obstacle:?} is in the way of the spawn point"
+            );
+            assert!(
+                position.abs().max_element() + footprint <= 20.0,
+                "{obstacle:?} hangs off the ground"
+            );
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! the demo scene loaded from assets/demo.scene.ron instead of spawned in code
+
+use bevy::prelude::*;
+use bevy_character_starter::{
+    DemoSceneConfig, DemoScenePlugin, MechanicsPlugins, PlayerControllerPlugin, PlayerEntity,
+    PlayerSettings,
+};
+use bevy_rapier3d::prelude::*;
+
+mod common;
+use common::*;
+
+/// a headless app with the demo scene loaded from `file`, stepped until the player has landed
+fn app_with_scene(file: &str) -> App {
+    let mut app = headless_app();
+    app.insert_resource(DemoSceneConfig {
+        file: Some(file.into()),
+        ..default()
+    })
+    .add_plugins((
+        PlayerControllerPlugin::default(),
+        MechanicsPlugins,
+        DemoScenePlugin,
+    ));
+
+    tick(&mut app, 240);
+    app
+}
+
+#[test]
+fn spawns_the_scene_file_and_stands_the_player_on_it() {
+    let mut app = app_with_scene("demo.scene.ron");
+
+    let fixed = app
+        .world_mut()
+        .query::<&RigidBody>()
+        .iter(app.world())
+        .filter(|&&body| body == RigidBody::Fixed)
+        .count();
+    assert!(fixed >= 4, "only {fixed} fixed bodies");
+    let sensors = app
+        .world_mut()
+        .query_filtered::<(), With<Sensor>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(sensors, 3);
+
+    // moved to the file's spawn point, and standing on its ground rather than falling forever
+    let player = app.world().resource::<PlayerEntity>().0;
+    let position = app.world().get::<Transform>(player).unwrap().translation;
+    assert!(
+        position.xz().distance(Vec2::new(0.0, 4.0)) < 0.1,
+        "at {position}"
+    );
+    let float_height = PlayerSettings::default().float_height();
+    assert!((position.y - float_height).abs() < 0.1, "at {position}");
+}
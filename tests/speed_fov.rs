@@ -0,0 +1,63 @@
+//! headless tests of the speed fov widening the keyboard and mouse player's view
+
+use bevy::prelude::*;
+use bevy_character_starter::{PlayerCamera, SpeedFovConfig};
+
+mod common;
+use common::*;
+
+/// a headless app with the player cameras and the speed fov on, settled on the ground. the
+/// fov goes from 90 standing still to 110 at walking speed
+fn app() -> App {
+    let mut app = camera_app();
+    app.insert_resource(SpeedFovConfig {
+        enabled: true,
+        min_speed: 0.0,
+        max_speed: 10.0,
+        min_fov: 90.0,
+        max_fov: 110.0,
+        smoothing: 4.0,
+    });
+    tick(&mut app, 120);
+    app
+}
+
+/// the player camera's vertical fov, in degrees
+fn fov(app: &mut App) -> f32 {
+    let projection = app
+        .world_mut()
+        .query_filtered::<&Projection, With<PlayerCamera>>()
+        .single(app.world());
+    let Projection::Perspective(perspective) = projection else {
+        panic!("camera isn't perspective");
+    };
+    perspective.fov.to_degrees()
+}
+
+#[test]
+fn widens_with_speed_easing_in_and_out() {
+    let mut app = app();
+    let standing = fov(&mut app);
+    assert!((standing - 90.0).abs() < 0.5, "standing fov {standing}");
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyW);
+    // eased, so the first few frames of walking barely widen it
+    tick(&mut app, 3);
+    let starting = fov(&mut app);
+    assert!(starting < 93.0, "jumped to {starting}");
+
+    tick(&mut app, 60);
+    let walking = fov(&mut app);
+    assert!(walking > 105.0, "only widened to {walking}");
+    assert!(walking <= 110.0, "widened past the max to {walking}");
+
+    // and back as the player stops
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(KeyCode::KeyW);
+    tick(&mut app, 120);
+    let stopped = fov(&mut app);
+    assert!((stopped - 90.0).abs() < 1.0, "stopped fov {stopped}");
+}
@@ -0,0 +1,107 @@
+//! terrain generation: determinism, and the collider lining up with the mesh
+
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use bevy_character_starter::{
+    DemoScenePlugin, Heightmap, MechanicsPlugins, PlayerControllerPlugin, PlayerEntity,
+    PlayerSettings, TerrainConfig,
+};
+
+mod common;
+use common::*;
+
+fn config(seed: u64) -> TerrainConfig {
+    TerrainConfig {
+        enabled: true,
+        seed,
+        size: Vec2::splat(20.0),
+        resolution: 33,
+        ..default()
+    }
+}
+
+#[test]
+fn same_seed_same_terrain() {
+    assert_eq!(
+        Heightmap::generate(&config(7)),
+        Heightmap::generate(&config(7))
+    );
+    assert_ne!(
+        Heightmap::generate(&config(7)),
+        Heightmap::generate(&config(8))
+    );
+}
+
+#[test]
+fn terrain_is_not_flat() {
+    let heightmap = Heightmap::generate(&config(0));
+    let n = heightmap.resolution();
+    let heights: Vec<f32> = (0..n)
+        .flat_map(|row| (0..n).map(move |col| (row, col)))
+        .map(|(row, col)| heightmap.height(row, col))
+        .collect();
+    let (min, max) = heights.iter().fold((f32::MAX, f32::MIN), |(min, max), &h| {
+        (min.min(h), max.max(h))
+    });
+    assert!(max - min > 1.0, "heights only span {min}..{max}");
+}
+
+#[test]
+fn mesh_has_a_vertex_per_height_facing_up() {
+    let heightmap = Heightmap::generate(&config(0));
+    let n = heightmap.resolution();
+    let mesh = heightmap.mesh();
+    assert_eq!(mesh.count_vertices(), n * n);
+    assert_eq!(mesh.indices().unwrap().len(), (n - 1) * (n - 1) * 6);
+
+    let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+    else {
+        panic!("no normals");
+    };
+    assert!(normals.iter().all(|normal| normal[1] > 0.0));
+}
+
+#[test]
+fn collider_matches_the_grid() {
+    let heightmap = Heightmap::generate(&config(3));
+    let collider = heightmap.collider();
+    let n = heightmap.resolution();
+    // at grid vertices, where the mesh and heightfield triangulations agree
+    for (row, col) in [(1, 1), (5, 20), (16, 16), (30, 2), (n - 2, n - 2)] {
+        let point = heightmap.point(row, col);
+        let hit = collider
+            .cast_local_ray(Vec3::new(point.x, 100.0, point.y), Vec3::NEG_Y, 200.0, true)
+            .expect("ray missed the terrain");
+        let surface = 100.0 - hit;
+        let expected = heightmap.height(row, col);
+        assert!(
+            (surface - expected).abs() < 1e-3,
+            "at ({row}, {col}) the collider is at {surface}, the mesh at {expected}"
+        );
+    }
+}
+
+#[test]
+fn players_spawn_standing_on_the_terrain() {
+    // a seed whose surface at the start spawn point is well above the point
+    let terrain = (0..)
+        .map(config)
+        .find(|terrain| terrain.height_at(0.0, 0.0) > 1.0)
+        .unwrap();
+    let surface = terrain.height_at(0.0, 0.0);
+    let mut app = headless_app();
+    app.insert_resource(terrain).add_plugins((
+        PlayerControllerPlugin::default(),
+        MechanicsPlugins,
+        DemoScenePlugin,
+    ));
+
+    // dropped from just over the surface, so the player's landed well within a second
+    tick(&mut app, 60);
+    let player = app.world().resource::<PlayerEntity>().0;
+    let position = app.world().get::<Transform>(player).unwrap().translation;
+    let float_height = PlayerSettings::default().float_height();
+    assert!(
+        (position.y - surface - float_height).abs() < 0.1,
+        "at {position} over a surface at {surface}"
+    );
+}
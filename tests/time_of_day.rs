@@ -0,0 +1,58 @@
+//! the day-night cycle: time passing, and the light it gives
+
+use bevy::prelude::*;
+use bevy_character_starter::TimeOfDay;
+
+fn at(time: f32) -> TimeOfDay {
+    TimeOfDay {
+        time,
+        day_length: 100.0,
+        ..default()
+    }
+}
+
+#[test]
+fn advances_wraps_and_pauses() {
+    let mut time_of_day = at(0.9);
+    time_of_day.advance(20.0);
+    assert!(
+        (time_of_day.time - 0.1).abs() < 1e-4,
+        "at {}",
+        time_of_day.time
+    );
+
+    time_of_day.paused = true;
+    time_of_day.advance(20.0);
+    assert!(
+        (time_of_day.time - 0.1).abs() < 1e-4,
+        "at {}",
+        time_of_day.time
+    );
+}
+
+#[test]
+fn light_always_comes_from_above() {
+    for step in 0..100 {
+        let daylight = at(step as f32 / 100.0).daylight();
+        assert!(daylight.direction.y <= 0.0, "lit from below at step {step}");
+    }
+}
+
+#[test]
+fn noon_is_brighter_and_whiter_than_dusk_and_night() {
+    let noon = at(0.5).daylight();
+    let dusk = at(0.72).daylight();
+    let night = at(0.0).daylight();
+    assert!(noon.illuminance > dusk.illuminance);
+    assert!(noon.illuminance > night.illuminance);
+    assert!(noon.ambient_brightness > night.ambient_brightness);
+
+    // the sun is high at noon and low at dusk, and dusk is much redder
+    assert!(-noon.direction.y > 0.8);
+    assert!(-dusk.direction.y < 0.3);
+    let blueness = |color: Color| {
+        let color = color.to_srgba();
+        color.blue / color.red
+    };
+    assert!(blueness(dusk.color) < blueness(noon.color) * 0.7);
+}